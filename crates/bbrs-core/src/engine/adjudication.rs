@@ -0,0 +1,102 @@
+/// What a bot should do after its move, based on the scores of its recent searches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Continue,
+    Resign,
+    OfferDraw,
+}
+
+/// Resign and draw behaviour for bot and match play, both off by default.
+///
+/// The score of each of our searches is recorded, from our point of view. We resign once
+/// the score has stayed at or below the resign threshold for the given number of moves,
+/// and offer or accept a draw once it has stayed within the draw margin of zero as long.
+#[derive(Debug, Clone, Default)]
+pub struct Adjudication {
+    resign: Option<(i32, usize)>,
+    draw: Option<(i32, usize)>,
+    scores: Vec<i32>,
+}
+
+impl Adjudication {
+    pub fn new() -> Self {
+        Adjudication::default()
+    }
+
+    /// Resign when the score is at or below `-threshold` for `moves` moves in a row.
+    pub fn resign(mut self, threshold: i32, moves: usize) -> Self {
+        self.resign = Some((threshold.abs(), moves.max(1)));
+        self
+    }
+
+    /// Offer and accept draws when the score is within `margin` of zero for `moves` moves in a row.
+    pub fn draw(mut self, margin: i32, moves: usize) -> Self {
+        self.draw = Some((margin.abs(), moves.max(1)));
+        self
+    }
+
+    /// The recorded scores, oldest first.
+    pub fn scores(&self) -> &[i32] {
+        &self.scores
+    }
+
+    /// Forgets the scores, for a new game.
+    pub fn clear(&mut self) {
+        self.scores.clear();
+    }
+
+    /// Records the score of our latest search and decides what to do.
+    pub fn record(&mut self, score: i32) -> Decision {
+        self.scores.push(score);
+        if self
+            .resign
+            .is_some_and(|(threshold, moves)| self.sustained(moves, |score| score <= -threshold))
+        {
+            Decision::Resign
+        } else if self.accept_draw() {
+            Decision::OfferDraw
+        } else {
+            Decision::Continue
+        }
+    }
+
+    /// Whether to accept a draw offered by the opponent.
+    pub fn accept_draw(&self) -> bool {
+        self.draw
+            .is_some_and(|(margin, moves)| self.sustained(moves, |score| score.abs() <= margin))
+    }
+
+    fn sustained(&self, moves: usize, condition: impl Fn(i32) -> bool) -> bool {
+        self.scores.len() >= moves
+            && self.scores[self.scores.len() - moves..]
+                .iter()
+                .all(|&score| condition(score))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adjudication() {
+        let mut adjudication = Adjudication::new().resign(500, 3).draw(10, 2);
+        assert_eq!(adjudication.record(-600), Decision::Continue);
+        assert_eq!(adjudication.record(-700), Decision::Continue);
+        assert_eq!(adjudication.record(-100), Decision::Continue);
+        assert_eq!(adjudication.record(-600), Decision::Continue);
+        assert_eq!(adjudication.record(-550), Decision::Continue);
+        assert_eq!(adjudication.record(-900), Decision::Resign);
+
+        adjudication.clear();
+        assert_eq!(adjudication.record(5), Decision::Continue);
+        assert!(!adjudication.accept_draw());
+        assert_eq!(adjudication.record(-10), Decision::OfferDraw);
+        assert!(adjudication.accept_draw());
+        assert_eq!(adjudication.record(40), Decision::Continue);
+
+        let mut disabled = Adjudication::new();
+        assert_eq!(disabled.record(-10_000), Decision::Continue);
+        assert!(!disabled.accept_draw());
+    }
+}
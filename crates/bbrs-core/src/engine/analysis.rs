@@ -0,0 +1,222 @@
+use std::time::Duration;
+
+use super::{
+    info::Score,
+    pgn::{PgnGame, PgnMove},
+};
+
+pub const INACCURACY_LOSS: i32 = 50;
+pub const MISTAKE_LOSS: i32 = 100;
+pub const BLUNDER_LOSS: i32 = 300;
+/// Mates count as this many centipawns when measuring what a move lost.
+const MATE_VALUE: i32 = 1_000;
+
+/// How much worse a move was than the engine's choice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Classification {
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl Classification {
+    /// The NAG for the class: `?!`, `?` or `??`.
+    pub fn nag(self) -> u8 {
+        match self {
+            Classification::Inaccuracy => 6,
+            Classification::Mistake => 2,
+            Classification::Blunder => 4,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Classification::Inaccuracy => "Inaccuracy",
+            Classification::Mistake => "Mistake",
+            Classification::Blunder => "Blunder",
+        }
+    }
+}
+
+/// What the engine thought of one move of an analyzed game.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlyAnalysis {
+    /// The move played, in SAN.
+    pub san: String,
+    /// The engine's choice in the position before the move, followed by its line.
+    pub pv: Vec<String>,
+    /// The clock time left after the move, when the game records it.
+    pub clock: Option<Duration>,
+    /// How much the move took off the clock, ignoring any increment.
+    pub clock_used: Option<Duration>,
+}
+
+/// The evaluation of every position of a game, as needed to draw an evaluation graph.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameAnalysis {
+    pub depth: u8,
+    /// Whether White plays the first analyzed move.
+    pub white_starts: bool,
+    /// White's score before the first move and after each move, one more than there are plies.
+    pub scores: Vec<i32>,
+    pub plies: Vec<PlyAnalysis>,
+}
+
+fn format_score(score: i32) -> (String, String) {
+    match Score::from_search(score) {
+        Score::Cp(cp) => (cp.to_string(), String::new()),
+        Score::Mate(moves) => (String::new(), moves.to_string()),
+    }
+}
+
+/// A `[%eval]` comment command with White's score in pawns, or `#n` for a mate. There is
+/// none once the game is over by mate.
+fn format_eval(score: i32) -> Option<String> {
+    match Score::from_search(score) {
+        Score::Cp(cp) => Some(format!("[%eval {:.2}]", cp as f64 / 100.0)),
+        Score::Mate(0) => None,
+        Score::Mate(moves) => Some(format!("[%eval #{}]", moves)),
+    }
+}
+
+fn format_seconds(time: Option<Duration>) -> String {
+    time.map_or(String::new(), |time| format!("{:.1}", time.as_secs_f64()))
+}
+
+impl GameAnalysis {
+    /// The best move in the position before ply `index`, if the search found one.
+    pub fn best_move(&self, index: usize) -> Option<&str> {
+        self.plies[index].pv.first().map(String::as_str)
+    }
+
+    /// How many centipawns ply `index` lost for the side that played it.
+    pub fn loss(&self, index: usize) -> i32 {
+        let clamp = |score: i32| score.clamp(-MATE_VALUE, MATE_VALUE);
+        let loss = clamp(self.scores[index]) - clamp(self.scores[index + 1]);
+        if self.white_starts == index.is_multiple_of(2) {
+            loss
+        } else {
+            -loss
+        }
+    }
+
+    /// The class of ply `index`, `None` for the engine's own choice and small losses.
+    pub fn classify(&self, index: usize) -> Option<Classification> {
+        if self.best_move(index) == Some(self.plies[index].san.as_str()) {
+            return None;
+        }
+        match self.loss(index) {
+            loss if loss >= BLUNDER_LOSS => Some(Classification::Blunder),
+            loss if loss >= MISTAKE_LOSS => Some(Classification::Mistake),
+            loss if loss >= INACCURACY_LOSS => Some(Classification::Inaccuracy),
+            _ => None,
+        }
+    }
+
+    /// The analyzed game with an `[%eval]` after every move, and the class's NAG and the
+    /// engine's line as a variation after every inaccuracy, mistake and blunder.
+    /// Existing comments are kept, existing variations dropped. `ECO` and `Opening` tags
+    /// are added when the opening is known and the game does not have them.
+    pub fn annotate(&self, game: &PgnGame) -> PgnGame {
+        let moves = self
+            .plies
+            .iter()
+            .enumerate()
+            .map(|(index, ply)| {
+                let original = game.moves.get(index);
+                let mut comments = Vec::from_iter(format_eval(self.scores[index + 1]));
+                let mut pgn_move = PgnMove::new(&ply.san);
+                if let Some(class) = self.classify(index) {
+                    pgn_move.nags.push(class.nag());
+                    if let Some(best) = self.best_move(index) {
+                        comments.push(format!("{}. {} was best.", class.name(), best));
+                        let line = ply.pv.iter().map(|san| PgnMove::new(san)).collect();
+                        pgn_move.variations.push(line);
+                    }
+                }
+                comments.extend(original.and_then(|pgn_move| pgn_move.comment.clone()));
+                pgn_move.comment = (!comments.is_empty()).then(|| comments.join(" "));
+                pgn_move
+            })
+            .collect();
+        let mut tags = game.tags.clone();
+        if let Some(opening) = game.eco().filter(|_| game.tag("ECO").is_none()) {
+            tags.push(("ECO".to_string(), opening.eco.to_string()));
+            tags.push(("Opening".to_string(), opening.name.to_string()));
+        }
+        PgnGame {
+            tags,
+            moves,
+            result: game.result.clone(),
+        }
+    }
+
+    /// One `ply,san,best,cp,mate,clock,clock_used` row per position, the first one for
+    /// the starting position with no move. A score is either `cp` or `mate`, times are
+    /// in seconds.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("ply,san,best,cp,mate,clock,clock_used\n");
+        for (ply, &score) in self.scores.iter().enumerate() {
+            let (cp, mate) = format_score(score);
+            let (san, best, clock, clock_used) = match ply.checked_sub(1) {
+                Some(index) => (
+                    self.plies[index].san.as_str(),
+                    self.best_move(index).unwrap_or_default(),
+                    format_seconds(self.plies[index].clock),
+                    format_seconds(self.plies[index].clock_used),
+                ),
+                None => ("", "", String::new(), String::new()),
+            };
+            csv += &format!(
+                "{},{},{},{},{},{},{}\n",
+                ply, san, best, cp, mate, clock, clock_used
+            );
+        }
+        csv
+    }
+
+    /// An object with the depth, the score series as `{"cp":n}` or `{"mate":n}` objects,
+    /// and the plies with their best lines and times in seconds.
+    pub fn to_json(&self) -> String {
+        let scores = self
+            .scores
+            .iter()
+            .map(|&score| match format_score(score) {
+                (cp, mate) if mate.is_empty() => format!("{{\"cp\":{}}}", cp),
+                (_, mate) => format!("{{\"mate\":{}}}", mate),
+            })
+            .collect::<Vec<String>>();
+        let optional = |time: Option<Duration>| {
+            let seconds = format_seconds(time);
+            if seconds.is_empty() {
+                "null".to_string()
+            } else {
+                seconds
+            }
+        };
+        let plies = self
+            .plies
+            .iter()
+            .map(|ply| {
+                let pv = ply
+                    .pv
+                    .iter()
+                    .map(|san| format!("\"{}\"", san))
+                    .collect::<Vec<String>>();
+                format!(
+                    "{{\"san\":\"{}\",\"pv\":[{}],\"clock\":{},\"clock_used\":{}}}",
+                    ply.san,
+                    pv.join(","),
+                    optional(ply.clock),
+                    optional(ply.clock_used)
+                )
+            })
+            .collect::<Vec<String>>();
+        format!(
+            "{{\"depth\":{},\"scores\":[{}],\"plies\":[{}]}}",
+            self.depth,
+            scores.join(","),
+            plies.join(",")
+        )
+    }
+}
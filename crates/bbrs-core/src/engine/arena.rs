@@ -0,0 +1,145 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use super::{
+    attacks::AttackTable, Engine, Output, Rules, SearchResult, Standard, TimeManager,
+    START_POSITION,
+};
+
+/// A position to analyse, searched to `depth` or until `budget` runs out.
+#[derive(Debug, Clone)]
+pub struct AnalysisJob {
+    pub fen: String,
+    pub depth: u8,
+    pub budget: Option<Duration>,
+}
+
+impl AnalysisJob {
+    pub fn new(fen: &str, depth: u8) -> Self {
+        AnalysisJob {
+            fen: fen.to_string(),
+            depth,
+            budget: None,
+        }
+    }
+}
+
+/// Runs independent searches of many positions concurrently.
+///
+/// Each worker thread owns one engine with its own transposition table, which is
+/// cleared between jobs so results do not depend on which jobs ran before. The attack
+/// tables are shared by all workers.
+#[derive(Debug, Clone)]
+pub struct Arena {
+    threads: usize,
+    hash_megabytes: usize,
+    rules: Arc<dyn Rules>,
+    queen_promotions_only: bool,
+    /// Positions with less material than this are skipped, see `EngineState::material`.
+    min_material: i32,
+}
+
+impl Default for Arena {
+    fn default() -> Self {
+        Arena::new(thread::available_parallelism().map_or(1, |threads| threads.get()))
+    }
+}
+
+impl Arena {
+    pub fn new(threads: usize) -> Self {
+        Arena {
+            threads: threads.max(1),
+            hash_megabytes: 16,
+            rules: Arc::new(Standard),
+            queen_promotions_only: false,
+            min_material: 0,
+        }
+    }
+
+    /// The total hash size, split evenly between the workers.
+    pub fn hash_size(mut self, megabytes: usize) -> Self {
+        self.hash_megabytes = megabytes;
+        self
+    }
+
+    pub fn rules(mut self, rules: Arc<dyn Rules>) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Makes the searches only promote to queens, see `Engine::set_queen_promotions_only`.
+    pub fn queen_promotions_only(mut self, only: bool) -> Self {
+        self.queen_promotions_only = only;
+        self
+    }
+
+    /// Skips the positions with less than `centipawns` of material on the board, kings
+    /// left out, whose results are errors instead.
+    pub fn min_material(mut self, centipawns: i32) -> Self {
+        self.min_material = centipawns;
+        self
+    }
+
+    /// Searches every job and returns the results in the order of `jobs`.
+    pub fn analyse(&self, jobs: &[AnalysisJob]) -> Vec<Result<SearchResult, String>> {
+        let next_job = AtomicUsize::new(0);
+        let workers = self.threads.min(jobs.len());
+        let (sender, receiver) = mpsc::channel();
+        let attack_table = Arc::new(AttackTable::init());
+
+        thread::scope(|scope| {
+            for _ in 0..workers {
+                let sender = sender.clone();
+                let next_job = &next_job;
+                let attack_table = Arc::clone(&attack_table);
+                scope.spawn(move || {
+                    let mut engine = self.worker(attack_table, workers);
+                    loop {
+                        let index = next_job.fetch_add(1, Ordering::Relaxed);
+                        let Some(job) = jobs.get(index) else {
+                            break;
+                        };
+                        let _ = sender.send((index, self.run(&mut engine, job)));
+                    }
+                });
+            }
+        });
+        drop(sender);
+
+        let mut results = vec![Err("Not searched".to_string()); jobs.len()];
+        for (index, result) in receiver {
+            results[index] = result;
+        }
+        results
+    }
+
+    fn worker(&self, attack_table: Arc<AttackTable>, workers: usize) -> Engine {
+        let mut engine = Engine::with_attack_table(attack_table, START_POSITION)
+            .expect("Invalid start position");
+        engine.set_output(Output::sink());
+        engine.set_rules(Arc::clone(&self.rules));
+        engine.set_hash_size((self.hash_megabytes / workers).max(1));
+        engine.set_queen_promotions_only(self.queen_promotions_only);
+        engine
+    }
+
+    fn run(&self, engine: &mut Engine, job: &AnalysisJob) -> Result<SearchResult, String> {
+        engine.set_position(&job.fen).map_err(str::to_string)?;
+        let material = engine.state.material();
+        if material < self.min_material {
+            return Err(format!(
+                "Skipped, material {} is below {}",
+                material, self.min_material
+            ));
+        }
+        engine.new_game();
+        engine.set_time_manager(TimeManager::new(job.budget));
+        Ok(engine.search_position(job.depth))
+    }
+}
@@ -1,4 +1,6 @@
-use std::array;
+use std::{array, mem};
+
+use super::{board::Square, cpu};
 
 pub mod masks {
     /// FILE_MASKS represents the 8 files (columns) on an 8x8 chessboard.
@@ -101,6 +103,11 @@ pub mod masks {
     pub const HBORDER_MASK: u64 = RANK_1 | RANK_8;
 
     pub const BORDER_MASK: u64 = VBORDER_MASK | HBORDER_MASK;
+
+    /// The light squares, a8 and h1 among them.
+    pub const LIGHT_SQUARES: u64 = 0xAA55AA55AA55AA55;
+
+    pub const DARK_SQUARES: u64 = !LIGHT_SQUARES;
 }
 
 #[rustfmt::skip]
@@ -306,6 +313,36 @@ fn mask_king_attacks(square: u8) -> u64 {
     mask_leaper_attacks(square, &KING_OFFSETS)
 }
 
+/// The squares in front of a pawn on its own file. Square 0 is a8, so White moves towards lower indices.
+fn mask_forward_file(square: u8, side: u8) -> u64 {
+    let rank = (square >> 3) as u32;
+    let ahead = if side == 0 {
+        !u64::MAX.checked_shl(rank * 8).unwrap_or(0)
+    } else {
+        u64::MAX.checked_shl((rank + 1) * 8).unwrap_or(0)
+    };
+    masks::FILE_MASKS[(square & 7) as usize] & ahead
+}
+
+/// The squares in front of a pawn on its own and adjacent files, which must be free of
+/// enemy pawns for it to be passed.
+fn mask_passed_pawn(square: u8, side: u8) -> u64 {
+    let file = square & 7;
+    let mut mask = mask_forward_file(square, side);
+    if file > 0 {
+        mask |= mask_forward_file(square - 1, side);
+    }
+    if file < 7 {
+        mask |= mask_forward_file(square + 1, side);
+    }
+    mask
+}
+
+/// The squares a pawn could ever attack while advancing, i.e. the adjacent files in front of it.
+fn mask_pawn_attack_span(square: u8, side: u8) -> u64 {
+    mask_passed_pawn(square, side) & !mask_forward_file(square, side)
+}
+
 /// Generates slider attacks using the Hyperbola Quintessence formula:
 /// (o - 2s) ^ reverse_bits( reverse_bits(o) - 2 * reverse_bits(s) ).
 fn generate_slider_attacks(square: u8, slider_mask: u64, occupancy: u64) -> u64 {
@@ -371,6 +408,23 @@ pub fn generate_rook_attacks(square: u8, occupancy: u64) -> u64 {
         | generate_slider_attacks(square, masks::FILE_MASKS[(square & 7) as usize], occupancy)
 }
 
+/// The pieces giving check to `side`, found without the lookup tables for a position that
+/// was parsed rather than reached by a move.
+pub fn checkers(bitboards: &[u64; 12], side: u8) -> u64 {
+    let king = bitboards[side as usize * 6 + 5];
+    if king == 0 {
+        return 0;
+    }
+    let square = get_lsb!(king) as u8;
+    let occupancy = bitboards.iter().fold(0, |acc, bitboard| acc | bitboard);
+    let [pawns, knights, bishops, rooks, queens, _] =
+        array::from_fn(|piece| bitboards[(side ^ 1) as usize * 6 + piece]);
+    (mask_pawn_attacks(square, side) & pawns)
+        | (mask_knight_attacks(square) & knights)
+        | (generate_bishop_attacks(square, occupancy) & (bishops | queens))
+        | (generate_rook_attacks(square, occupancy) & (rooks | queens))
+}
+
 pub fn create_occupancy(index: usize, mask: u64, bits: u8) -> u64 {
     let mut copy = mask;
     (0..bits).fold(0, |mut occupancy, count| {
@@ -383,7 +437,56 @@ pub fn create_occupancy(index: usize, mask: u64, bits: u8) -> u64 {
     })
 }
 
-fn init_slider_attacks(masks: [u64; 64], is_bishop: bool) -> [Box<[u64]>; 64] {
+/// How the relevant occupancy of a slider is turned into an index into its attack table,
+/// or whether there are tables at all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SliderIndexing {
+    /// Multiply by a magic number and keep the top bits. Works on any CPU.
+    Magic,
+    /// Gather the relevant bits with BMI2 `pext`. Only available when the CPU supports it.
+    #[cfg(target_arch = "x86_64")]
+    Pext,
+    /// No tables: attacks are generated on every lookup by Hyperbola Quintessence, the
+    /// same generators that fill the tables. Slower, but over 800 KiB smaller.
+    Hyperbola,
+}
+
+impl SliderIndexing {
+    /// `Hyperbola` with the `small-tables` feature, otherwise `Pext` if the CPU supports
+    /// it and `Magic` if not.
+    pub fn detect() -> Self {
+        if cfg!(feature = "small-tables") {
+            return SliderIndexing::Hyperbola;
+        }
+        #[cfg(target_arch = "x86_64")]
+        if cpu::has_bmi2() {
+            return SliderIndexing::Pext;
+        }
+        SliderIndexing::Magic
+    }
+
+    #[inline(always)]
+    fn index(self, occupancy: u64, mask: u64, magic: u64, bits: u8) -> usize {
+        match self {
+            SliderIndexing::Magic => {
+                ((occupancy & mask).wrapping_mul(magic) >> (64 - bits)) as usize
+            }
+            // SAFETY: `Pext` is only selected when the CPU supports BMI2
+            #[cfg(target_arch = "x86_64")]
+            SliderIndexing::Pext => unsafe { cpu::pext(occupancy, mask) as usize },
+            SliderIndexing::Hyperbola => unreachable!("hyperbola quintessence has no tables"),
+        }
+    }
+}
+
+fn init_slider_attacks(
+    masks: [u64; 64],
+    is_bishop: bool,
+    indexing: SliderIndexing,
+) -> [Box<[u64]>; 64] {
+    if indexing == SliderIndexing::Hyperbola {
+        return array::from_fn(|_| Box::default());
+    }
     array::from_fn(|square| {
         let mask = masks[square];
         let (magic, bits) = if is_bishop {
@@ -395,8 +498,7 @@ fn init_slider_attacks(masks: [u64; 64], is_bishop: bool) -> [Box<[u64]>; 64] {
         let mut attacks = vec![0; variations];
         (0..variations).for_each(|index| {
             let occupancy = create_occupancy(index, mask, bits);
-            let magic_index = ((occupancy.wrapping_mul(magic)) >> (64 - bits)) as usize;
-            attacks[magic_index] = if is_bishop {
+            attacks[indexing.index(occupancy, mask, magic, bits)] = if is_bishop {
                 generate_bishop_attacks(square as u8, occupancy)
             } else {
                 generate_rook_attacks(square as u8, occupancy)
@@ -415,15 +517,36 @@ pub struct AttackTable {
 
     bishop_masks: [u64; 64],
     rook_masks: [u64; 64],
+    indexing: SliderIndexing,
+
+    forward_files: [[u64; 64]; 2],
+    passed_pawns: [[u64; 64]; 2],
+    pawn_attack_spans: [[u64; 64]; 2],
 }
 
 impl AttackTable {
     pub fn init() -> Self {
+        AttackTable::with_indexing(SliderIndexing::detect())
+    }
+
+    /// Builds the tables for the given slider indexing.
+    ///
+    /// # Panics
+    /// If `indexing` is `Pext` on a CPU without BMI2.
+    pub fn with_indexing(indexing: SliderIndexing) -> Self {
+        #[cfg(target_arch = "x86_64")]
+        assert!(
+            indexing != SliderIndexing::Pext || cpu::has_bmi2(),
+            "pext indexing requires BMI2"
+        );
         let mut pawns = [[0; 64]; 2];
         let mut knights = [0; 64];
         let mut kings = [0; 64];
         let mut bishop_masks = [0; 64];
         let mut rook_masks = [0; 64];
+        let mut forward_files = [[0; 64]; 2];
+        let mut passed_pawns = [[0; 64]; 2];
+        let mut pawn_attack_spans = [[0; 64]; 2];
 
         // Initialize attack masks
         (0..64).for_each(|square| {
@@ -433,11 +556,16 @@ impl AttackTable {
             kings[square] = mask_king_attacks(square as u8);
             bishop_masks[square] = mask_bishop_attacks(square as u8);
             rook_masks[square] = mask_rook_attacks(square as u8);
+            for side in 0..2 {
+                forward_files[side][square] = mask_forward_file(square as u8, side as u8);
+                passed_pawns[side][square] = mask_passed_pawn(square as u8, side as u8);
+                pawn_attack_spans[side][square] = mask_pawn_attack_span(square as u8, side as u8);
+            }
         });
 
         // Initialize bishop and rook attack tables
-        let bishops: [Box<[u64]>; 64] = init_slider_attacks(bishop_masks, true);
-        let rooks: [Box<[u64]>; 64] = init_slider_attacks(rook_masks, false);
+        let bishops: [Box<[u64]>; 64] = init_slider_attacks(bishop_masks, true, indexing);
+        let rooks: [Box<[u64]>; 64] = init_slider_attacks(rook_masks, false, indexing);
 
         AttackTable {
             pawns,
@@ -447,10 +575,21 @@ impl AttackTable {
             rooks,
             bishop_masks,
             rook_masks,
+            indexing,
+            forward_files,
+            passed_pawns,
+            pawn_attack_spans,
         }
     }
 
     fn get_slider_attacks(&self, square: usize, occupancy: u64, is_bishop: bool) -> u64 {
+        if self.indexing == SliderIndexing::Hyperbola {
+            return if is_bishop {
+                generate_bishop_attacks(square as u8, occupancy)
+            } else {
+                generate_rook_attacks(square as u8, occupancy)
+            };
+        }
         let (mask, magic, bits) = if is_bishop {
             (
                 self.bishop_masks[square],
@@ -464,7 +603,7 @@ impl AttackTable {
                 ROOK_RELEVANT_BITS[square],
             )
         };
-        let magic_index = ((occupancy & mask).wrapping_mul(magic) >> (64 - bits)) as usize;
+        let magic_index = self.indexing.index(occupancy, mask, magic, bits);
         if is_bishop {
             self.bishops[square][magic_index]
         } else {
@@ -472,6 +611,55 @@ impl AttackTable {
         }
     }
 
+    /// Bytes taken by the tables, slider attacks included.
+    pub fn memory(&self) -> usize {
+        mem::size_of::<Self>()
+            + self
+                .bishops
+                .iter()
+                .chain(&self.rooks)
+                .map(|attacks| mem::size_of_val(&**attacks))
+                .sum::<usize>()
+    }
+
+    pub fn indexing(&self) -> SliderIndexing {
+        self.indexing
+    }
+
+    /// Compares the slider attacks looked up for every relevant occupancy of every square
+    /// with the ones generated directly, so a bad magic number or a miscompiled `pext`
+    /// shows up before it corrupts a search.
+    pub fn verify(&self) -> Result<(), String> {
+        for square in 0..64 {
+            for (mask, bits, is_bishop) in [
+                (
+                    self.bishop_masks[square],
+                    BISHOP_RELEVANT_BITS[square],
+                    true,
+                ),
+                (self.rook_masks[square], ROOK_RELEVANT_BITS[square], false),
+            ] {
+                for index in 0..1 << bits {
+                    let occupancy = create_occupancy(index, mask, bits);
+                    let expected = if is_bishop {
+                        generate_bishop_attacks(square as u8, occupancy)
+                    } else {
+                        generate_rook_attacks(square as u8, occupancy)
+                    };
+                    if self.get_slider_attacks(square, occupancy, is_bishop) != expected {
+                        return Err(format!(
+                            "{} attacks from {} with occupancy {:#X}",
+                            if is_bishop { "Bishop" } else { "Rook" },
+                            Square::ALL[square],
+                            occupancy
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
     pub fn get_pawn_attacks(&self, side: u8, square: usize) -> u64 {
         self.pawns[side as usize][square]
     }
@@ -490,15 +678,96 @@ impl AttackTable {
     pub fn get_queen_attacks(&self, square: usize, occupancy: u64) -> u64 {
         self.get_bishop_attacks(square, occupancy) | self.get_rook_attacks(square, occupancy)
     }
+    pub fn get_forward_file(&self, side: u8, square: usize) -> u64 {
+        self.forward_files[side as usize][square]
+    }
+    pub fn get_passed_pawn_mask(&self, side: u8, square: usize) -> u64 {
+        self.passed_pawns[side as usize][square]
+    }
+    pub fn get_pawn_attack_span(&self, side: u8, square: usize) -> u64 {
+        self.pawn_attack_spans[side as usize][square]
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
-    use crate::engine::board::Square;
+    use std::sync::Arc;
+
+    use crate::engine::{board::Square, Engine};
 
     use super::*;
 
+    #[test]
+    #[cfg(target_arch = "x86_64")]
+    fn test_pext_matches_magic() {
+        if !cpu::has_bmi2() {
+            return;
+        }
+        let (magic, pext) = (
+            AttackTable::with_indexing(SliderIndexing::Magic),
+            AttackTable::with_indexing(SliderIndexing::Pext),
+        );
+        let mut occupancy = 0x9E37_79B9_7F4A_7C15u64;
+        for square in 0..64 {
+            for _ in 0..64 {
+                occupancy ^= occupancy << 13;
+                occupancy ^= occupancy >> 7;
+                occupancy ^= occupancy << 17;
+                assert_eq!(
+                    magic.get_queen_attacks(square, occupancy),
+                    pext.get_queen_attacks(square, occupancy)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_hyperbola_matches_magic() {
+        let (magic, hyperbola) = (
+            AttackTable::with_indexing(SliderIndexing::Magic),
+            AttackTable::with_indexing(SliderIndexing::Hyperbola),
+        );
+        // Every relevant occupancy, with unrelated pieces around it
+        for square in 0..64 {
+            for (mask, bits, is_bishop) in [
+                (
+                    magic.bishop_masks[square],
+                    BISHOP_RELEVANT_BITS[square],
+                    true,
+                ),
+                (magic.rook_masks[square], ROOK_RELEVANT_BITS[square], false),
+            ] {
+                for index in 0..1 << bits {
+                    let occupancy = create_occupancy(index, mask, bits) | !mask & 0x8142_2418_0000;
+                    assert_eq!(
+                        magic.get_slider_attacks(square, occupancy, is_bishop),
+                        hyperbola.get_slider_attacks(square, occupancy, is_bishop)
+                    );
+                }
+            }
+        }
+
+        let mut engines = [magic, hyperbola].map(|attack_table| {
+            Engine::with_attack_table(
+                Arc::new(attack_table),
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            )
+            .unwrap()
+        });
+        assert_eq!(engines[1].perft_driver(3), 97862);
+        assert_eq!(engines[0].perft_driver(3), engines[1].perft_driver(3));
+    }
+
+    #[test]
+    fn test_memory() {
+        let magic = AttackTable::with_indexing(SliderIndexing::Magic).memory();
+        let hyperbola = AttackTable::with_indexing(SliderIndexing::Hyperbola).memory();
+        // 102400 rook and 5248 bishop entries
+        assert_eq!(magic - hyperbola, 107_648 * 8);
+        assert!(hyperbola < 64 * 1024, "{}", hyperbola);
+    }
+
     #[test]
     fn test_file_masks() {
         assert_eq!(masks::FILE_A, 0x101010101010101);
@@ -544,6 +813,21 @@ mod tests {
         assert_eq!(mask_rook_attacks(Square::D5 as u8), 0x8080876080800);
     }
 
+    #[test]
+    fn test_mask_passed_pawn() {
+        // White pawn on e4: d5-f5 up to d8-f8
+        assert_eq!(mask_passed_pawn(Square::E4 as u8, 0), 0x38383838);
+
+        // Black pawn on a7: a6-b6 down to a1-b1
+        assert_eq!(mask_passed_pawn(Square::A7 as u8, 1), 0x303030303030000);
+    }
+
+    #[test]
+    fn test_mask_pawn_attack_span() {
+        // White pawn on e4: d5-d8 and f5-f8
+        assert_eq!(mask_pawn_attack_span(Square::E4 as u8, 0), 0x28282828);
+    }
+
     #[test]
     fn test_generate_bishop_attacks() {
         // Bishop on a8 (square 0)
@@ -0,0 +1,153 @@
+use std::fmt;
+
+/// Squares are indexed from a8 = 0 to h1 = 63, rank by rank from the eighth, so bit 0 of a
+/// bitboard is a8 and `masks::RANK_1` is `0xFF00000000000000`. Code that needs ranks or
+/// files should go through `rank_of`, `file_of` and `Square::from_rank_file`, which count
+/// from the first rank and the a-file whatever the index order.
+#[allow(dead_code)]
+#[rustfmt::skip]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Square {
+    A8, B8, C8, D8, E8, F8, G8, H8, 
+    A7, B7, C7, D7, E7, F7, G7, H7, 
+    A6, B6, C6, D6, E6, F6, G6, H6, 
+    A5, B5, C5, D5, E5, F5, G5, H5, 
+    A4, B4, C4, D4, E4, F4, G4, H4, 
+    A3, B3, C3, D3, E3, F3, G3, H3, 
+    A2, B2, C2, D2, E2, F2, G2, H2, 
+    A1, B1, C1, D1, E1, F1, G1, H1,
+}
+
+impl Square {
+    #[rustfmt::skip]
+    pub const ALL: [Square; 64] = {
+        use Square::*;
+        [
+            A8, B8, C8, D8, E8, F8, G8, H8,
+            A7, B7, C7, D7, E7, F7, G7, H7,
+            A6, B6, C6, D6, E6, F6, G6, H6,
+            A5, B5, C5, D5, E5, F5, G5, H5,
+            A4, B4, C4, D4, E4, F4, G4, H4,
+            A3, B3, C3, D3, E3, F3, G3, H3,
+            A2, B2, C2, D2, E2, F2, G2, H2,
+            A1, B1, C1, D1, E1, F1, G1, H1,
+        ]
+    };
+
+    pub fn from_index(index: u8) -> Option<Square> {
+        Square::ALL.get(index as usize).copied()
+    }
+
+    /// The square on `rank` and `file`, both counted from 0: `from_rank_file(0, 0)` is a1.
+    pub fn from_rank_file(rank: u8, file: u8) -> Option<Square> {
+        (rank < 8 && file < 8).then(|| Square::ALL[(7 - rank as usize) * 8 + file as usize])
+    }
+
+    pub fn parse(name: &str) -> Option<Square> {
+        Square::from_index(parse_square(name)?)
+    }
+
+    pub fn index(self) -> u8 {
+        self as u8
+    }
+
+    /// The rank as written, from 1 to 8.
+    pub fn rank(self) -> u8 {
+        rank_of(self as u8) + 1
+    }
+
+    pub fn file(self) -> char {
+        (file_of(self as u8) + b'a') as char
+    }
+
+    /// The square seen from the other side: a1 for a8, e4 for e5.
+    pub fn flip(self) -> Square {
+        Square::ALL[flip(self as u8) as usize]
+    }
+
+    /// Whether this is a light square, like a8 and h1.
+    pub fn is_light(self) -> bool {
+        (rank_of(self as u8) + file_of(self as u8)) % 2 == 1
+    }
+}
+
+/// The rank of a square index, from 0 for the first rank to 7 for the eighth.
+pub fn rank_of(square: u8) -> u8 {
+    7 - (square >> 3)
+}
+
+/// The file of a square index, from 0 for the a-file to 7 for the h-file.
+pub fn file_of(square: u8) -> u8 {
+    square & 7
+}
+
+/// Mirrors a square index vertically, swapping the first and eighth ranks.
+pub fn flip(square: u8) -> u8 {
+    square ^ 56
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.file(), self.rank())
+    }
+}
+
+/// Convert an algebraic square (e.g., "a8") to a bitboard index (0-63).
+pub fn parse_square(square: &str) -> Option<u8> {
+    match square.as_bytes() {
+        &[file @ b'a'..=b'h', rank @ b'1'..=b'8'] => {
+            Square::from_rank_file(rank - b'1', file - b'a').map(Square::index)
+        }
+        _ => None,
+    }
+}
+
+/// Convert a bitboard index (0-63) to an algebraic square (e.g., 0 to "a8").
+pub fn index_to_algebraic(index: usize) -> String {
+    let file = file_of(index as u8) + b'a';
+    let rank = rank_of(index as u8) + 1;
+    format!("{}{}", file as char, rank)
+}
+
+/// Chebyshev distance between two squares, i.e. the number of king moves between them.
+pub fn distance(a: u8, b: u8) -> i32 {
+    let (rank_a, file_a) = ((a >> 3) as i32, (a & 7) as i32);
+    let (rank_b, file_b) = ((b >> 3) as i32, (b & 7) as i32);
+    (rank_a - rank_b).abs().max((file_a - file_b).abs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::attacks::masks;
+
+    #[test]
+    fn test_rank_and_file_conversions() {
+        assert_eq!(Square::from_rank_file(0, 0), Some(Square::A1));
+        assert_eq!(Square::from_rank_file(7, 7), Some(Square::H8));
+        assert_eq!(Square::from_rank_file(3, 4), Some(Square::E4));
+        assert_eq!(Square::from_rank_file(8, 0), None);
+        assert_eq!(Square::E4.flip(), Square::E5);
+        assert_eq!(Square::A1.flip(), Square::A8);
+
+        for square in Square::ALL {
+            let (rank, file) = (rank_of(square.index()), file_of(square.index()));
+            assert_eq!(Square::from_rank_file(rank, file), Some(square));
+            assert_eq!(square.flip().flip(), square);
+            assert_eq!(rank_of(flip(square.index())), 7 - rank);
+            assert_eq!(Square::parse(&square.to_string()), Some(square));
+            assert_eq!(
+                index_to_algebraic(square.index() as usize),
+                square.to_string()
+            );
+            // The masks follow the same orientation
+            assert_ne!(
+                masks::RANK_MASKS[7 - rank as usize] & 1 << square.index(),
+                0
+            );
+            assert_ne!(masks::FILE_MASKS[file as usize] & 1 << square.index(), 0);
+        }
+        assert_eq!(masks::RANK_1, 0xFF << Square::A1.index());
+        assert_eq!(masks::RANK_8, 0xFF << Square::A8.index());
+    }
+}
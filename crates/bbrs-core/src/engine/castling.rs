@@ -0,0 +1,173 @@
+use super::{board::Square, piece::side};
+
+pub mod flags {
+    pub const WK: u8 = 1 << 0;
+    pub const WQ: u8 = 1 << 1;
+    pub const BK: u8 = 1 << 2;
+    pub const BQ: u8 = 1 << 3;
+}
+
+/// The castling rights, in the order used to index `EngineState::castling_rooks`.
+pub const RIGHTS: [u8; 4] = [flags::WK, flags::WQ, flags::BK, flags::BQ];
+
+/// The rook squares used by each castling right in standard chess.
+pub const DEFAULT_ROOKS: [u8; 4] = [
+    Square::H1 as u8,
+    Square::A1 as u8,
+    Square::H8 as u8,
+    Square::A8 as u8,
+];
+
+/// The index of a castling right in `RIGHTS`.
+pub fn index(side: u8, king_side: bool) -> usize {
+    side as usize * 2 + usize::from(!king_side)
+}
+
+/// The (king, rook) target squares of a castle, which are the same in standard chess and Chess960.
+pub fn targets(side: u8, king_side: bool) -> (u8, u8) {
+    let (king, rook) = match (side == side::WHITE, king_side) {
+        (true, true) => (Square::G1, Square::F1),
+        (true, false) => (Square::C1, Square::D1),
+        (false, true) => (Square::G8, Square::F8),
+        (false, false) => (Square::C8, Square::D8),
+    };
+    (king as u8, rook as u8)
+}
+
+/// The squares from `from` to `to` inclusive, both on the same rank.
+pub fn span(from: u8, to: u8) -> u64 {
+    (from.min(to)..=from.max(to)).fold(0, |acc, square| acc | bitboard!(square))
+}
+
+/// The squares a castling king must not be attacked on: its own and every one it passes,
+/// short of its destination. A Chess960 king that already stands on its destination still
+/// may not castle out of check, even where the rook would block it.
+pub fn transit(king: u8, king_target: u8) -> u64 {
+    span(king, king_target) & !bitboard!(king_target) | bitboard!(king)
+}
+
+/// Builds the per-square masks that clear castling rights when a king or rook
+/// leaves (or a rook is captured on) its starting square.
+pub fn rights_masks(kings: [Option<u8>; 2], rooks: [u8; 4]) -> [u8; 64] {
+    let mut masks = [15; 64];
+    kings.iter().enumerate().for_each(|(side, king)| {
+        if let Some(king) = king {
+            masks[*king as usize] &= !(RIGHTS[side * 2] | RIGHTS[side * 2 + 1]);
+        }
+    });
+    rooks.iter().zip(RIGHTS).for_each(|(&rook, right)| {
+        masks[rook as usize] &= !right;
+    });
+    masks
+}
+
+pub fn format(castling: u8) -> String {
+    match castling {
+        0 => "-".to_string(),
+        _ => {
+            let mut result = String::new();
+            if castling & flags::WK != 0 {
+                result.push('K');
+            }
+            if castling & flags::WQ != 0 {
+                result.push('Q');
+            }
+            if castling & flags::BK != 0 {
+                result.push('k');
+            }
+            if castling & flags::BQ != 0 {
+                result.push('q');
+            }
+            result
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::super::{rules::Chess960, Engine};
+
+    fn engine(fen: &str, chess960: bool) -> Engine {
+        let mut engine = Engine::new(fen).unwrap();
+        if chess960 {
+            engine.set_rules(Arc::new(Chess960));
+        }
+        engine
+    }
+
+    fn castles(fen: &str, chess960: bool) -> Vec<String> {
+        let mut engine = engine(fen, chess960);
+        let mut castles = engine
+            .generate_legal_moves()
+            .iter()
+            .map(|&move_| engine.format_san(move_))
+            .filter(|san| san.starts_with("O-O"))
+            .collect::<Vec<String>>();
+        castles.sort();
+        castles
+    }
+
+    #[test]
+    fn test_castling_perft() {
+        for (fen, chess960, nodes) in [
+            ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", false, 13744),
+            ("r3k2r/8/8/8/8/8/8/1R2K2R w Kkq - 0 1", false, 14095),
+            ("r3k2r/8/8/8/8/8/8/2R1K2R w Kkq - 0 1", false, 13502),
+            ("r3k2r/8/8/8/8/8/8/R3K1R1 w Qkq - 0 1", false, 13579),
+            ("1r2k2r/8/8/8/8/8/8/R3K2R w KQk - 0 1", false, 14252),
+            (
+                "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+                true,
+                18002,
+            ),
+            (
+                "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+                true,
+                10471,
+            ),
+            (
+                "qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9",
+                true,
+                13440,
+            ),
+        ] {
+            assert_eq!(engine(fen, chess960).perft_driver(3), nodes, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_every_transit_square_is_checked() {
+        // The king passes c1, d1, e1 and f1 on its way from b1 to g1
+        for rank in ["2r5", "3r4", "4r3", "5r2"] {
+            let fen = format!("7k/8/8/8/8/{}/8/1K4R1 w G - 0 1", rank);
+            assert!(castles(&fen, true).is_empty(), "{}", fen);
+        }
+        assert_eq!(castles("7k/8/8/8/8/r7/8/1K4R1 w G - 0 1", true), ["O-O"]);
+        // Standard chess: attacks on b1 do not matter, on d1 they do
+        assert_eq!(
+            castles("4k3/8/8/8/8/8/1r6/R3K2R w KQ - 0 1", false),
+            ["O-O", "O-O-O"]
+        );
+        assert_eq!(
+            castles("4k3/8/8/8/8/8/3r4/R3K2R w KQ - 0 1", false),
+            ["O-O"]
+        );
+    }
+
+    #[test]
+    fn test_castling_needs_an_empty_path() {
+        // Another piece on the rook's destination blocks it, the castling pieces do not
+        assert!(castles("k7/8/8/8/8/8/8/1K2N1R1 w G - 0 1", true).is_empty());
+        assert!(castles("k7/8/8/8/8/8/8/5BKR w H - 0 1", true).is_empty());
+        assert_eq!(castles("k7/8/8/8/8/8/8/6KR w H - 0 1", true), ["O-O"]);
+        assert_eq!(castles("k7/8/8/8/8/8/8/5KR1 w G - 0 1", true), ["O-O"]);
+    }
+
+    #[test]
+    fn test_no_castling_out_of_check() {
+        // The rook would block the check, but the king stands in it before castling
+        assert!(castles("k7/8/8/8/8/8/8/r5KR w H - 0 1", true).is_empty());
+    }
+}
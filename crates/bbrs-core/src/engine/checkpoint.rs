@@ -0,0 +1,159 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use super::{
+    search::{RootMove, RootMoves},
+    tt::TranspositionTable,
+};
+
+const MAGIC: &[u8; 8] = b"BBRSCP01";
+
+/// Where and how often a search writes checkpoints.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpointing {
+    pub path: PathBuf,
+    pub interval: Duration,
+}
+
+/// A snapshot of an analysis: the position, the deepest completed iteration and what it
+/// found for every root move. Saved with the transposition table, so resuming it repeats
+/// the finished iterations from stored results instead of searching them again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub fen: String,
+    pub depth: u8,
+    pub root_moves: RootMoves,
+}
+
+/// FNV-1a, to tell a truncated or corrupted file from a valid one.
+pub(crate) fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xCBF2_9CE4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01B3)
+    })
+}
+
+/// Checks the magic and the trailing checksum, returning the bytes between them.
+pub(crate) fn verify<'a>(bytes: &'a [u8], magic: &[u8; 8]) -> Result<&'a [u8], String> {
+    let body = bytes
+        .strip_prefix(magic)
+        .ok_or("Not a bbrs file of this kind or version")?;
+    if body.len() < 8 {
+        return Err("Truncated file".to_string());
+    }
+    let (body, sum) = body.split_at(body.len() - 8);
+    if checksum(&bytes[..bytes.len() - 8]).to_le_bytes() != sum {
+        return Err("Checksum mismatch, the file is corrupted".to_string());
+    }
+    Ok(body)
+}
+
+/// Reads little-endian values from the front of a byte slice.
+pub(crate) struct Reader<'a>(pub &'a [u8]);
+
+impl<'a> Reader<'a> {
+    pub fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.0.len() < len {
+            return Err("Truncated file".to_string());
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    pub fn i32(&mut self) -> Result<i32, String> {
+        Ok(i32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
+
+impl Checkpoint {
+    pub fn to_bytes(&self, tt: &TranspositionTable) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend((self.fen.len() as u32).to_le_bytes());
+        bytes.extend(self.fen.as_bytes());
+        bytes.push(self.depth);
+        bytes.extend((self.root_moves.len() as u32).to_le_bytes());
+        for root_move in self.root_moves.iter() {
+            bytes.extend(root_move.move_.to_le_bytes());
+            bytes.extend(root_move.score.to_le_bytes());
+            bytes.extend(root_move.previous_score.to_le_bytes());
+            bytes.extend(root_move.nodes.to_le_bytes());
+            bytes.extend(root_move.iteration_nodes.to_le_bytes());
+            bytes.extend((root_move.pv.len() as u32).to_le_bytes());
+            root_move
+                .pv
+                .iter()
+                .for_each(|move_| bytes.extend(move_.to_le_bytes()));
+        }
+        let tt = tt.to_bytes();
+        bytes.extend((tt.len() as u64).to_le_bytes());
+        bytes.extend(tt);
+        bytes.extend(checksum(&bytes).to_le_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<(Checkpoint, TranspositionTable), String> {
+        let mut reader = Reader(verify(bytes, MAGIC)?);
+        let len = reader.u32()? as usize;
+        let fen = String::from_utf8(reader.take(len)?.to_vec()).map_err(|err| err.to_string())?;
+        let depth = reader.u8()?;
+        let count = reader.u32()? as usize;
+        let mut moves = vec![];
+        for _ in 0..count {
+            let mut root_move = RootMove::new(reader.u32()?);
+            root_move.score = reader.i32()?;
+            root_move.previous_score = reader.i32()?;
+            root_move.nodes = reader.u64()?;
+            root_move.iteration_nodes = reader.u64()?;
+            let len = reader.u32()? as usize;
+            root_move.pv = (0..len)
+                .map(|_| reader.u32())
+                .collect::<Result<Vec<u32>, String>>()?;
+            moves.push(root_move);
+        }
+        let mut root_moves = RootMoves::new(
+            &moves
+                .iter()
+                .map(|root_move| root_move.move_)
+                .collect::<Vec<u32>>(),
+        );
+        root_moves.as_mut_slice().clone_from_slice(&moves);
+        let len = reader.u64()? as usize;
+        let tt = TranspositionTable::from_bytes(reader.take(len)?)?;
+        Ok((
+            Checkpoint {
+                fen,
+                depth,
+                root_moves,
+            },
+            tt,
+        ))
+    }
+
+    /// Writes to a temporary file first, so an interrupted save keeps the last checkpoint.
+    pub fn save(&self, tt: &TranspositionTable, path: &Path) -> Result<(), String> {
+        let temporary = path.with_extension("tmp");
+        fs::write(&temporary, self.to_bytes(tt))
+            .and_then(|_| fs::rename(&temporary, path))
+            .map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    pub fn load(path: &Path) -> Result<(Checkpoint, TranspositionTable), String> {
+        let bytes = fs::read(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        Checkpoint::from_bytes(&bytes).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+}
@@ -0,0 +1,191 @@
+use std::{fmt, time::Duration};
+
+/// Moves assumed to be left in the game when the time control does not say.
+pub const DEFAULT_MOVES_TO_GO: u32 = 30;
+
+/// An even share of `remaining` over the moves to the next control, or
+/// `DEFAULT_MOVES_TO_GO` moves in sudden death, plus the increment, but never more
+/// than is left.
+pub fn allot(remaining: Duration, increment: Duration, moves_to_go: Option<u32>) -> Duration {
+    let moves = moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+    (remaining / moves + increment).min(remaining)
+}
+
+/// One period of a time control: `moves` moves in `time`, or the rest of the game if
+/// `moves` is `None`, with `increment` added after every move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stage {
+    pub moves: Option<u32>,
+    pub time: Duration,
+    pub increment: Duration,
+}
+
+/// A time control made of stages, in the PGN `TimeControl` format with times in seconds:
+/// `300+2` is five minutes with a two second increment, `40/5400+30:1800+30` is 40 moves
+/// in 90 minutes followed by 30 minutes for the rest, both with a 30 second increment.
+/// A last stage with a move count repeats.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeControl {
+    stages: Vec<Stage>,
+}
+
+fn parse_seconds(seconds: &str) -> Result<Duration, &'static str> {
+    seconds
+        .parse::<f64>()
+        .ok()
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+        .ok_or("Invalid time in time control")
+}
+
+impl TimeControl {
+    pub fn new(stages: Vec<Stage>) -> Result<Self, &'static str> {
+        if stages.is_empty() {
+            return Err("Time control without stages");
+        }
+        if stages[..stages.len() - 1]
+            .iter()
+            .any(|stage| stage.moves.is_none())
+        {
+            return Err("Only the last stage of a time control may last the rest of the game");
+        }
+        Ok(TimeControl { stages })
+    }
+
+    /// Sudden death in `time`, with `increment` after every move.
+    pub fn sudden_death(time: Duration, increment: Duration) -> Self {
+        TimeControl {
+            stages: vec![Stage {
+                moves: None,
+                time,
+                increment,
+            }],
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, &'static str> {
+        let stages = text
+            .trim()
+            .split(':')
+            .map(|stage| {
+                let (moves, rest) = match stage.split_once('/') {
+                    Some((moves, rest)) => (
+                        Some(
+                            moves
+                                .parse()
+                                .map_err(|_| "Invalid move count in time control")?,
+                        ),
+                        rest,
+                    ),
+                    None => (None, stage),
+                };
+                let (time, increment) = rest.split_once('+').unwrap_or((rest, "0"));
+                Ok(Stage {
+                    moves,
+                    time: parse_seconds(time)?,
+                    increment: parse_seconds(increment)?,
+                })
+            })
+            .collect::<Result<Vec<Stage>, &'static str>>()?;
+        TimeControl::new(stages)
+    }
+
+    pub fn stages(&self) -> &[Stage] {
+        &self.stages
+    }
+
+    /// The stage in effect after `stage` is over.
+    fn next_stage(&self, stage: usize) -> usize {
+        (stage + 1).min(self.stages.len() - 1)
+    }
+}
+
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, stage) in self.stages.iter().enumerate() {
+            if index > 0 {
+                write!(f, ":")?;
+            }
+            if let Some(moves) = stage.moves {
+                write!(f, "{}/", moves)?;
+            }
+            write!(f, "{}", stage.time.as_secs_f64())?;
+            if !stage.increment.is_zero() {
+                write!(f, "+{}", stage.increment.as_secs_f64())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One player's clock under a time control.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Clock {
+    control: TimeControl,
+    stage: usize,
+    moves_in_stage: u32,
+    remaining: Duration,
+    flagged: bool,
+}
+
+impl Clock {
+    pub fn new(control: TimeControl) -> Self {
+        let remaining = control.stages[0].time;
+        Clock {
+            control,
+            stage: 0,
+            moves_in_stage: 0,
+            remaining,
+            flagged: false,
+        }
+    }
+
+    pub fn time_control(&self) -> &TimeControl {
+        &self.control
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.remaining
+    }
+
+    pub fn increment(&self) -> Duration {
+        self.control.stages[self.stage].increment
+    }
+
+    /// Moves left until the next time control, `None` in sudden death.
+    pub fn moves_to_go(&self) -> Option<u32> {
+        self.control.stages[self.stage]
+            .moves
+            .map(|moves| moves - self.moves_in_stage)
+    }
+
+    /// Whether the time ran out during a move. A flagged clock stays at zero.
+    pub fn is_flagged(&self) -> bool {
+        self.flagged
+    }
+
+    /// Charges a move that took `elapsed`, then adds the increment and, once the stage's
+    /// moves are played, the time of the next stage. Returns false if the flag fell.
+    pub fn record_move(&mut self, elapsed: Duration) -> bool {
+        if self.flagged {
+            return false;
+        }
+        if elapsed > self.remaining {
+            self.remaining = Duration::ZERO;
+            self.flagged = true;
+            return false;
+        }
+        self.remaining = self.remaining - elapsed + self.increment();
+        self.moves_in_stage += 1;
+        if self.moves_to_go() == Some(0) {
+            self.stage = self.control.next_stage(self.stage);
+            self.moves_in_stage = 0;
+            self.remaining += self.control.stages[self.stage].time;
+        }
+        true
+    }
+
+    /// The time to spend on the next move, see `allot`.
+    pub fn allotment(&self) -> Duration {
+        allot(self.remaining, self.increment(), self.moves_to_go())
+    }
+}
@@ -0,0 +1,94 @@
+use super::pawns::pawn_key;
+
+/// Entries are kept in fractions of a centipawn so small updates are not rounded away.
+const GRAIN: i32 = 256;
+/// The largest correction, in centipawns.
+const MAX_CORRECTION: i32 = 128;
+/// Updates move an entry `min(depth + 1, 16) / 256` of the way towards the latest error.
+const WEIGHT_SCALE: i32 = 256;
+
+/// Learns how far the static eval tends to be from the search score for a pawn
+/// structure and side to move, and corrects later static evals by that much.
+#[derive(Debug, Clone)]
+pub struct CorrectionHistory {
+    entries: Vec<[i32; 2]>,
+}
+
+impl CorrectionHistory {
+    /// Creates a table with `size` entries per side, rounded up to a power of two.
+    pub fn new(size: usize) -> Self {
+        CorrectionHistory {
+            entries: vec![[0; 2]; size.next_power_of_two()],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.fill([0; 2]);
+    }
+
+    pub fn memory(&self) -> usize {
+        std::mem::size_of_val(self.entries.as_slice())
+    }
+
+    fn entry(&mut self, side: u8, pawns: [u64; 2]) -> &mut i32 {
+        let index = (pawn_key(pawns[0], pawns[1]) >> 32) as usize & (self.entries.len() - 1);
+        &mut self.entries[index][side as usize]
+    }
+
+    /// The static eval of the side to move, corrected by what was learned so far.
+    pub fn correct(&mut self, side: u8, pawns: [u64; 2], eval: i32) -> i32 {
+        eval + *self.entry(side, pawns) / GRAIN
+    }
+
+    /// Moves the correction towards `error`, the search score minus the corrected eval,
+    /// more so the deeper the search behind the score.
+    pub fn update(&mut self, side: u8, pawns: [u64; 2], depth: u8, error: i32) {
+        let weight = (depth as i32 + 1).min(16);
+        let entry = self.entry(side, pawns);
+        *entry = (*entry + error * GRAIN * weight / WEIGHT_SCALE)
+            .clamp(-MAX_CORRECTION * GRAIN, MAX_CORRECTION * GRAIN);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START: [u64; 2] = [0x00FF000000000000, 0xFF00];
+    const AFTER_E4: [u64; 2] = [0x00EF001000000000, 0xFF00];
+
+    #[test]
+    fn test_corrections_follow_the_error() {
+        let mut history = CorrectionHistory::new(1024);
+        assert_eq!(history.correct(0, START, 50), 50);
+        // A depth 15 update moves the entry by 16 / 256 of the error
+        history.update(0, START, 15, 160);
+        assert_eq!(history.correct(0, START, 50), 60);
+        history.update(0, START, 15, -320);
+        assert_eq!(history.correct(0, START, 50), 40);
+    }
+
+    #[test]
+    fn test_corrections_are_clamped() {
+        let mut history = CorrectionHistory::new(1024);
+        for _ in 0..100 {
+            history.update(0, START, 20, 10_000);
+        }
+        assert_eq!(history.correct(0, START, 0), MAX_CORRECTION);
+        for _ in 0..100 {
+            history.update(0, START, 20, -10_000);
+        }
+        assert_eq!(history.correct(0, START, 0), -MAX_CORRECTION);
+    }
+
+    #[test]
+    fn test_corrections_are_kept_per_side_and_pawn_structure() {
+        let mut history = CorrectionHistory::new(1024);
+        history.update(0, START, 15, 320);
+        assert_eq!(history.correct(0, START, 0), 20);
+        assert_eq!(history.correct(1, START, 0), 0);
+        assert_eq!(history.correct(0, AFTER_E4, 0), 0);
+        history.clear();
+        assert_eq!(history.correct(0, START, 0), 0);
+    }
+}
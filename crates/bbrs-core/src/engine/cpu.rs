@@ -0,0 +1,45 @@
+//! CPU feature detection for the fast paths in move generation and evaluation.
+//!
+//! Prebuilt binaries detect the features at runtime. With the `native` feature the
+//! answers are fixed at compile time from the target features instead, which lets
+//! `RUSTFLAGS=-Ctarget-cpu=native` builds inline the fast paths everywhere.
+
+/// Whether the CPU has a hardware popcount instruction.
+pub fn has_popcnt() -> bool {
+    #[cfg(all(target_arch = "x86_64", not(feature = "native")))]
+    return std::is_x86_feature_detected!("popcnt");
+    #[cfg(any(not(target_arch = "x86_64"), feature = "native"))]
+    return cfg!(target_feature = "popcnt");
+}
+
+/// Whether the CPU has BMI2, and with it `pext` for slider attack indexing.
+pub fn has_bmi2() -> bool {
+    #[cfg(all(target_arch = "x86_64", not(feature = "native")))]
+    return std::is_x86_feature_detected!("bmi2");
+    #[cfg(any(not(target_arch = "x86_64"), feature = "native"))]
+    return cfg!(target_feature = "bmi2");
+}
+
+/// Extracts the bits of `value` selected by `mask` into the low bits.
+///
+/// # Safety
+/// The CPU must support BMI2, see `has_bmi2`.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "bmi2")]
+pub unsafe fn pext(value: u64, mask: u64) -> u64 {
+    std::arch::x86_64::_pext_u64(value, mask)
+}
+
+/// Describes the detected fast paths, e.g. for benchmark output.
+pub fn describe() -> String {
+    format!(
+        "popcnt: {}, bmi2: {}{}",
+        if has_popcnt() { "yes" } else { "no" },
+        if has_bmi2() { "yes" } else { "no" },
+        if cfg!(feature = "native") {
+            " (native build)"
+        } else {
+            ""
+        }
+    )
+}
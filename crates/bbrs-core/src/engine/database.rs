@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+
+use super::{
+    pgn::{self, PgnGame},
+    Engine, Output, START_POSITION,
+};
+
+/// A position reached by different move orders, in the games and plies listed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transposition {
+    pub key: u64,
+    /// `(game, ply)` pairs, ply 0 being the game's starting position.
+    pub occurrences: Vec<(usize, usize)>,
+}
+
+/// A collection of games indexed by the positions of their main lines.
+///
+/// Positions are identified by `Engine::position_key`, so the same position counts as
+/// such whichever move order led to it.
+pub struct Database {
+    engine: Engine,
+    games: Vec<PgnGame>,
+    /// The key of every position of each game's main line, starting position first.
+    lines: Vec<Vec<u64>>,
+    positions: HashMap<u64, Vec<(usize, usize)>>,
+}
+
+impl Default for Database {
+    fn default() -> Self {
+        Database::new()
+    }
+}
+
+impl Database {
+    pub fn new() -> Self {
+        let mut engine = Engine::new(START_POSITION).expect("Invalid start position");
+        engine.set_output(Output::sink());
+        Database {
+            engine,
+            games: vec![],
+            lines: vec![],
+            positions: HashMap::new(),
+        }
+    }
+
+    pub fn from_pgn(text: &str) -> Result<Self, String> {
+        let mut database = Database::new();
+        for game in pgn::parse(text)? {
+            database.add(game)?;
+        }
+        Ok(database)
+    }
+
+    /// Adds a game and returns its index.
+    pub fn add(&mut self, game: PgnGame) -> Result<usize, String> {
+        let fen = game.start_position();
+        self.engine
+            .set_position(fen)
+            .map_err(|err| format!("{}: {}", err, fen))?;
+        let mut line = vec![self.engine.position_key()];
+        for pgn_move in game.moves.iter() {
+            let move_ = self
+                .engine
+                .parse_san(&pgn_move.san)
+                .ok_or_else(|| format!("Illegal move: {}", pgn_move.san))?;
+            self.engine.make_move(move_);
+            line.push(self.engine.position_key());
+        }
+        let index = self.games.len();
+        for (ply, &key) in line.iter().enumerate() {
+            self.positions.entry(key).or_default().push((index, ply));
+        }
+        self.games.push(game);
+        self.lines.push(line);
+        Ok(index)
+    }
+
+    pub fn games(&self) -> &[PgnGame] {
+        &self.games
+    }
+
+    pub fn len(&self) -> usize {
+        self.games.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.games.is_empty()
+    }
+
+    /// The `(game, ply)` pairs where the engine's current position occurs.
+    pub fn find(&self, engine: &Engine) -> &[(usize, usize)] {
+        self.positions
+            .get(&engine.position_key())
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Groups of games with the same moves from the same position, whatever their tags
+    /// and comments. Each group is in the order the games were added.
+    pub fn duplicates(&self) -> Vec<Vec<usize>> {
+        let mut groups: HashMap<&[u64], Vec<usize>> = HashMap::new();
+        for (index, line) in self.lines.iter().enumerate() {
+            groups.entry(line).or_default().push(index);
+        }
+        let mut duplicates = groups
+            .into_values()
+            .filter(|group| group.len() > 1)
+            .collect::<Vec<Vec<usize>>>();
+        duplicates.sort();
+        duplicates
+    }
+
+    /// Removes every duplicate but the first of its group and returns how many were removed.
+    pub fn dedupe(&mut self) -> usize {
+        let mut remove = self
+            .duplicates()
+            .into_iter()
+            .flat_map(|group| group.into_iter().skip(1))
+            .collect::<Vec<usize>>();
+        remove.sort();
+        for &index in remove.iter().rev() {
+            self.games.remove(index);
+            self.lines.remove(index);
+        }
+        self.positions.clear();
+        for (index, line) in self.lines.iter().enumerate() {
+            for (ply, &key) in line.iter().enumerate() {
+                self.positions.entry(key).or_default().push((index, ply));
+            }
+        }
+        remove.len()
+    }
+
+    /// The positions where games that got there by different moves meet, earliest first.
+    /// Positions after the meeting point are not repeated, as the games share their moves
+    /// from there.
+    pub fn transpositions(&self) -> Vec<Transposition> {
+        let mut transpositions = self
+            .positions
+            .iter()
+            .filter(|(_, occurrences)| {
+                let mut previous = occurrences
+                    .iter()
+                    .filter_map(|&(game, ply)| ply.checked_sub(1).map(|ply| self.lines[game][ply]))
+                    .collect::<Vec<u64>>();
+                previous.sort();
+                previous.dedup();
+                previous.len() > 1
+            })
+            .map(|(&key, occurrences)| Transposition {
+                key,
+                occurrences: occurrences.clone(),
+            })
+            .collect::<Vec<Transposition>>();
+        transpositions.sort_by_key(|transposition| transposition.occurrences[0]);
+        transpositions
+    }
+}
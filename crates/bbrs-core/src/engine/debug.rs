@@ -1,6 +1,8 @@
+//! Console printing for development, with the `debug` feature.
+#![allow(clippy::print_stdout)]
+
 use crate::engine::{moves, ASCII_PIECES};
 
-#[allow(dead_code)]
 /// Print the bitboard for debugging.
 pub fn print_bitboard(bitboard: u64) {
     let divider = "-------------------";
@@ -26,7 +28,6 @@ pub fn print_bitboard(bitboard: u64) {
     println!("{}", divider);
 }
 
-#[allow(dead_code)]
 pub fn print_move_list(moves: &[u32]) {
     let print_divider = || {
         println!("{}", "─".repeat(65));
@@ -0,0 +1,166 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, OnceLock},
+};
+
+use super::{attacks::AttackTable, pgn::PgnGame, Engine, Output, START_POSITION};
+
+/// An opening from the ECO classification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Opening {
+    pub eco: &'static str,
+    pub name: &'static str,
+}
+
+/// ECO code, name and the moves from the start position that define it. Positions are
+/// matched by hash, so transpositions are recognized.
+const OPENINGS: &[(&str, &str, &str)] = &[
+    ("A00", "Polish Opening", "b4"),
+    ("A01", "Nimzo-Larsen Attack", "b3"),
+    ("A02", "Bird's Opening", "f4"),
+    ("A04", "Reti Opening", "Nf3"),
+    ("A06", "Reti Opening", "Nf3 d5"),
+    ("A10", "English Opening", "c4"),
+    ("A20", "English Opening: King's English", "c4 e5"),
+    ("A30", "English Opening: Symmetrical Variation", "c4 c5"),
+    ("A40", "Queen's Pawn Game", "d4"),
+    ("A45", "Indian Defense", "d4 Nf6"),
+    ("A50", "Indian Defense", "d4 Nf6 c4"),
+    ("A56", "Benoni Defense", "d4 Nf6 c4 c5"),
+    ("A57", "Benko Gambit", "d4 Nf6 c4 c5 d5 b5"),
+    ("A80", "Dutch Defense", "d4 f5"),
+    ("B00", "King's Pawn Opening", "e4"),
+    ("B01", "Scandinavian Defense", "e4 d5"),
+    ("B02", "Alekhine's Defense", "e4 Nf6"),
+    ("B06", "Modern Defense", "e4 g6"),
+    ("B07", "Pirc Defense", "e4 d6 d4 Nf6"),
+    ("B10", "Caro-Kann Defense", "e4 c6"),
+    (
+        "B12",
+        "Caro-Kann Defense: Advance Variation",
+        "e4 c6 d4 d5 e5",
+    ),
+    ("B20", "Sicilian Defense", "e4 c5"),
+    ("B22", "Sicilian Defense: Alapin Variation", "e4 c5 c3"),
+    ("B23", "Sicilian Defense: Closed", "e4 c5 Nc3"),
+    ("B27", "Sicilian Defense", "e4 c5 Nf3"),
+    ("B30", "Sicilian Defense: Old Sicilian", "e4 c5 Nf3 Nc6"),
+    ("B40", "Sicilian Defense: French Variation", "e4 c5 Nf3 e6"),
+    ("B50", "Sicilian Defense", "e4 c5 Nf3 d6"),
+    (
+        "B70",
+        "Sicilian Defense: Dragon Variation",
+        "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 g6",
+    ),
+    (
+        "B90",
+        "Sicilian Defense: Najdorf Variation",
+        "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 a6",
+    ),
+    ("C00", "French Defense", "e4 e6"),
+    (
+        "C01",
+        "French Defense: Exchange Variation",
+        "e4 e6 d4 d5 exd5",
+    ),
+    ("C02", "French Defense: Advance Variation", "e4 e6 d4 d5 e5"),
+    (
+        "C03",
+        "French Defense: Tarrasch Variation",
+        "e4 e6 d4 d5 Nd2",
+    ),
+    ("C20", "King's Pawn Game", "e4 e5"),
+    ("C21", "Center Game", "e4 e5 d4"),
+    ("C23", "Bishop's Opening", "e4 e5 Bc4"),
+    ("C25", "Vienna Game", "e4 e5 Nc3"),
+    ("C30", "King's Gambit", "e4 e5 f4"),
+    ("C33", "King's Gambit Accepted", "e4 e5 f4 exf4"),
+    ("C40", "King's Knight Opening", "e4 e5 Nf3"),
+    ("C41", "Philidor Defense", "e4 e5 Nf3 d6"),
+    ("C42", "Petrov's Defense", "e4 e5 Nf3 Nf6"),
+    (
+        "C44",
+        "King's Knight Opening: Normal Variation",
+        "e4 e5 Nf3 Nc6",
+    ),
+    ("C44", "Scotch Game", "e4 e5 Nf3 Nc6 d4"),
+    ("C46", "Three Knights Opening", "e4 e5 Nf3 Nc6 Nc3"),
+    ("C47", "Four Knights Game", "e4 e5 Nf3 Nc6 Nc3 Nf6"),
+    ("C50", "Italian Game", "e4 e5 Nf3 Nc6 Bc4"),
+    ("C50", "Italian Game: Giuoco Piano", "e4 e5 Nf3 Nc6 Bc4 Bc5"),
+    (
+        "C51",
+        "Italian Game: Evans Gambit",
+        "e4 e5 Nf3 Nc6 Bc4 Bc5 b4",
+    ),
+    (
+        "C55",
+        "Italian Game: Two Knights Defense",
+        "e4 e5 Nf3 Nc6 Bc4 Nf6",
+    ),
+    ("C60", "Ruy Lopez", "e4 e5 Nf3 Nc6 Bb5"),
+    ("C65", "Ruy Lopez: Berlin Defense", "e4 e5 Nf3 Nc6 Bb5 Nf6"),
+    (
+        "C68",
+        "Ruy Lopez: Exchange Variation",
+        "e4 e5 Nf3 Nc6 Bb5 a6 Bxc6",
+    ),
+    (
+        "C70",
+        "Ruy Lopez: Morphy Defense",
+        "e4 e5 Nf3 Nc6 Bb5 a6 Ba4",
+    ),
+    ("D00", "Queen's Pawn Game", "d4 d5"),
+    ("D06", "Queen's Gambit", "d4 d5 c4"),
+    ("D10", "Slav Defense", "d4 d5 c4 c6"),
+    ("D20", "Queen's Gambit Accepted", "d4 d5 c4 dxc4"),
+    ("D30", "Queen's Gambit Declined", "d4 d5 c4 e6"),
+    ("D80", "Grunfeld Defense", "d4 Nf6 c4 g6 Nc3 d5"),
+    ("E12", "Queen's Indian Defense", "d4 Nf6 c4 e6 Nf3 b6"),
+    ("E20", "Nimzo-Indian Defense", "d4 Nf6 c4 e6 Nc3 Bb4"),
+    ("E60", "King's Indian Defense", "d4 Nf6 c4 g6"),
+];
+
+/// The openings by the hash of the position their moves lead to.
+fn table() -> &'static (Arc<AttackTable>, HashMap<u64, Opening>) {
+    static TABLE: OnceLock<(Arc<AttackTable>, HashMap<u64, Opening>)> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let attack_table = Arc::new(AttackTable::init());
+        let mut engine = Engine::with_attack_table(Arc::clone(&attack_table), START_POSITION)
+            .expect("Invalid start position");
+        engine.set_output(Output::sink());
+        let mut openings = HashMap::new();
+        for &(eco, name, moves) in OPENINGS {
+            engine.set_position(START_POSITION).unwrap();
+            for san in moves.split_whitespace() {
+                let move_ = engine.parse_san(san).expect("Illegal move in ECO table");
+                engine.make_move(move_);
+            }
+            openings.insert(engine.position_key(), Opening { eco, name });
+        }
+        (attack_table, openings)
+    })
+}
+
+/// The opening of the position with this hash, if it is one the table names.
+pub fn lookup(hash: u64) -> Option<Opening> {
+    table().1.get(&hash).copied()
+}
+
+impl PgnGame {
+    /// The opening of the deepest position of the main line that has an ECO code.
+    pub fn eco(&self) -> Option<Opening> {
+        let (attack_table, _) = table();
+        let mut engine =
+            Engine::with_attack_table(Arc::clone(attack_table), self.start_position()).ok()?;
+        let mut opening = lookup(engine.position_key());
+        for pgn_move in self.moves.iter() {
+            let Some(move_) = engine.parse_san(&pgn_move.san) else {
+                break;
+            };
+            engine.make_move(move_);
+            opening = lookup(engine.position_key()).or(opening);
+        }
+        opening
+    }
+}
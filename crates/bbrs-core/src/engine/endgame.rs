@@ -0,0 +1,179 @@
+#[cfg(all(target_arch = "x86_64", not(feature = "native")))]
+use super::cpu;
+use super::{
+    board::distance,
+    evaluate,
+    fen::parse_piece,
+    piece::{pieces::*, side},
+    EngineState,
+};
+
+/// A specialized evaluation for a material configuration.
+#[derive(Debug, Clone, Copy)]
+pub enum Handler {
+    /// Replaces the evaluation. Returns the score for the strong side.
+    Evaluate(fn(&EngineState, u8) -> i32),
+    /// Scales the evaluation by the returned factor out of `SCALE_NORMAL`.
+    Scale(fn(&EngineState, u8) -> i32),
+}
+
+pub const SCALE_NORMAL: i32 = 64;
+pub const SCALE_DRAW: i32 = 0;
+
+/// Packs the piece counts of both sides into 4 bits per piece, kings excluded.
+pub fn material_key(bitboards: &[u64; 12]) -> u64 {
+    #[cfg(all(target_arch = "x86_64", not(feature = "native")))]
+    if cpu::has_popcnt() {
+        // SAFETY: the CPU supports popcnt
+        return unsafe { material_key_popcnt(bitboards) };
+    }
+    material_key_generic(bitboards)
+}
+
+/// Evaluation computes the key on every call, so it is worth a hardware popcount.
+#[cfg(all(target_arch = "x86_64", not(feature = "native")))]
+#[target_feature(enable = "popcnt")]
+fn material_key_popcnt(bitboards: &[u64; 12]) -> u64 {
+    material_key_generic(bitboards)
+}
+
+#[inline(always)]
+fn material_key_generic(bitboards: &[u64; 12]) -> u64 {
+    [
+        WHITE_PAWN,
+        WHITE_KNIGHT,
+        WHITE_BISHOP,
+        WHITE_ROOK,
+        WHITE_QUEEN,
+        BLACK_PAWN,
+        BLACK_KNIGHT,
+        BLACK_BISHOP,
+        BLACK_ROOK,
+        BLACK_QUEEN,
+    ]
+    .iter()
+    .enumerate()
+    .fold(0, |key, (index, &piece)| {
+        let count = (count_bits!(bitboards[piece as usize]) as u64).min(15);
+        key | (count << (index * 4))
+    })
+}
+
+/// Builds the material key for a code such as `KBNvK`, the white pieces listed first.
+pub fn key_from_code(code: &str) -> Option<u64> {
+    let (white, black) = code.split_once('v')?;
+    let mut bitboards = [0u64; 12];
+    for (pieces, side) in [(white, side::WHITE), (black, side::BLACK)] {
+        for (square, ch) in pieces.chars().enumerate() {
+            let piece = parse_piece(ch.to_ascii_uppercase())? + side * 6;
+            set_bit!(bitboards[piece as usize], square);
+        }
+    }
+    Some(material_key(&bitboards))
+}
+
+/// Mirrors a material key so White's pieces become Black's and vice versa.
+fn flip_key(key: u64) -> u64 {
+    (key >> 20) | ((key & 0xFFFFF) << 20)
+}
+
+/// The table of specialized endgame handlers, keyed by material signature.
+#[derive(Debug, Clone)]
+pub struct Endgames {
+    entries: Vec<(u64, u8, Handler)>,
+}
+
+impl Default for Endgames {
+    fn default() -> Self {
+        Endgames::new()
+    }
+}
+
+impl Endgames {
+    pub fn new() -> Self {
+        let mut endgames = Endgames { entries: vec![] };
+        ["KvK", "KNvK", "KBvK", "KNNvK"]
+            .iter()
+            .for_each(|code| endgames.add(code, Handler::Scale(scale_draw)));
+        ["KQvK", "KRvK", "KQQvK", "KQRvK", "KRRvK", "KBBvK"]
+            .iter()
+            .for_each(|code| endgames.add(code, Handler::Evaluate(evaluate_kxk)));
+        endgames.add("KBNvK", Handler::Evaluate(evaluate_kbnk));
+        endgames
+    }
+
+    /// Registers a handler for `code` with White as the strong side, and its mirror for Black.
+    pub fn add(&mut self, code: &str, handler: Handler) {
+        let key = key_from_code(code).expect("Invalid endgame code");
+        self.entries.push((key, side::WHITE, handler));
+        self.entries.push((flip_key(key), side::BLACK, handler));
+    }
+
+    /// The handler and strong side for a material key, if one is registered.
+    pub fn probe(&self, key: u64) -> Option<(u8, Handler)> {
+        self.entries
+            .iter()
+            .find(|(entry_key, _, _)| *entry_key == key)
+            .map(|&(_, strong_side, handler)| (strong_side, handler))
+    }
+}
+
+fn king_square(state: &EngineState, side: u8) -> u8 {
+    let king = if side == side::WHITE {
+        WHITE_KING
+    } else {
+        BLACK_KING
+    };
+    get_lsb!(state.bitboards[king as usize]) as u8
+}
+
+/// Manhattan distance of a square from the four centre squares.
+fn center_distance(square: u8) -> i32 {
+    let (rank, file) = ((square >> 3) as i32, (square & 7) as i32);
+    (3 - file).max(file - 4) + (3 - rank).max(rank - 4)
+}
+
+fn strong_material(state: &EngineState, strong_side: u8) -> i32 {
+    let score = state
+        .bitboards
+        .iter()
+        .enumerate()
+        .filter(|(piece, _)| (*piece as u8) / 6 == strong_side && *piece as u8 % 6 != 5)
+        .map(|(piece, &bitboard)| evaluate::MATERIAL_SCORES[piece] * count_bits!(bitboard) as i32)
+        .sum::<i32>();
+    score.abs()
+}
+
+fn scale_draw(_: &EngineState, _: u8) -> i32 {
+    SCALE_DRAW
+}
+
+/// Mating material against a lone king: drive the king to the edge and bring ours closer.
+fn evaluate_kxk(state: &EngineState, strong_side: u8) -> i32 {
+    let strong_king = king_square(state, strong_side);
+    let weak_king = king_square(state, strong_side ^ 1);
+    strong_material(state, strong_side) + 20 * center_distance(weak_king)
+        - 10 * distance(strong_king, weak_king)
+}
+
+/// Bishop and knight: the lone king can only be mated in a corner of the bishop's colour.
+fn evaluate_kbnk(state: &EngineState, strong_side: u8) -> i32 {
+    let strong_king = king_square(state, strong_side);
+    let weak_king = king_square(state, strong_side ^ 1);
+    let bishop = if strong_side == side::WHITE {
+        WHITE_BISHOP
+    } else {
+        BLACK_BISHOP
+    };
+    let bishop_square = get_lsb!(state.bitboards[bishop as usize]) as u8;
+    let light_squares = (bishop_square >> 3) % 2 == (bishop_square & 7) % 2;
+    // a8 and h1 are light squares, h8 and a1 are dark ones
+    let corners: [u8; 2] = if light_squares { [0, 63] } else { [7, 56] };
+    let corner_distance = corners
+        .iter()
+        .map(|&corner| distance(weak_king, corner))
+        .min()
+        .unwrap_or(0);
+    strong_material(state, strong_side) + 40 * (7 - corner_distance)
+        - 10 * distance(strong_king, weak_king)
+}
@@ -0,0 +1,139 @@
+pub const MATERIAL_SCORES: [i32; 12] = [
+    100, 300, 325, 500, 1_000, 10_000, -100, -300, -325, -500, -1_000, -10_000,
+];
+
+#[rustfmt::skip]
+// Pawn positional score
+pub const PAWN_SCORE: [i8; 64] = [
+    90,  90,  90,  90,  90,  90,  90,  90,
+    30,  30,  30,  40,  40,  30,  30,  30,
+    20,  20,  20,  30,  30,  30,  20,  20,
+    10,  10,  10,  20,  20,  10,  10,  10,
+     5,   5,  10,  20,  20,   5,   5,   5,
+     0,   0,   0,   5,   5,   0,   0,   0,
+     0,   0,   0, -10, -10,   0,   0,   0,
+     0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+// Knight positional score
+pub const KNIGHT_SCORE: [i8; 64] = [
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,  10,  10,   0,   0,  -5,
+    -5,   5,  20,  20,  20,  20,   5,  -5,
+    -5,  10,  20,  30,  30,  20,  10,  -5,
+    -5,  10,  20,  30,  30,  20,  10,  -5,
+    -5,   5,  20,  10,  10,  20,   5,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5, -10,   0,   0,   0,   0, -10,  -5,
+];
+
+#[rustfmt::skip]
+// Bishop positional score
+pub const BISHOP_SCORE: [i8; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+     0,   0,   0,   0,   0,   0,   0,   0,
+     0,   0,   0,  10,  10,   0,   0,   0,
+     0,   0,  10,  20,  20,  10,   0,   0,
+     0,   0,  10,  20,  20,  10,   0,   0,
+     0,  10,   0,   0,   0,   0,  10,   0,
+     0,  30,   0,   0,   0,   0,  30,   0,
+     0,   0, -10,   0,   0, -10,   0,   0,
+];
+
+#[rustfmt::skip]
+// Rook positional score
+pub const ROOK_SCORE: [i8; 64] = [
+    50,  50,  50,  50,  50,  50,  50,  50,
+    50,  50,  50,  50,  50,  50,  50,  50,
+     0,   0,  10,  20,  20,  10,   0,   0,
+     0,   0,  10,  20,  20,  10,   0,   0,
+     0,   0,  10,  20,  20,  10,   0,   0,
+     0,   0,  10,  20,  20,  10,   0,   0,
+     0,   0,  10,  20,  20,  10,   0,   0,
+     0,   0,   0,  20,  20,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+// King positional score
+pub const KING_SCORE: [i8; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+     0,   0,   5,   5,   5,   5,   0,   0,
+     0,   5,   5,  10,  10,   5,   5,   0,
+     0,   5,  10,  20,  20,  10,   5,   0,
+     0,   5,  10,  20,  20,  10,   5,   0,
+     0,   0,   5,  10,  10,   5,   0,   0,
+     0,   5,   5,  -5,  -5,   0,   5,   0,
+     0,   0,   5,   0, -15,   0,  10,   0,
+];
+
+/// Passed pawn bonus by rank, counted from the pawn's own first rank.
+pub const PASSED_PAWN_BONUS: [i32; 8] = [0, 10, 15, 25, 45, 75, 120, 0];
+/// Per square of distance from the enemy king to the square in front of a passed pawn.
+pub const PASSED_PAWN_ENEMY_KING: i32 = 5;
+/// Per square of distance from our own king to the square in front of a passed pawn.
+pub const PASSED_PAWN_OWN_KING: i32 = 2;
+/// When no piece stands on the passed pawn's path to promotion.
+pub const PASSED_PAWN_FREE_PATH: i32 = 5;
+/// When the enemy attacks no square on the passed pawn's path to promotion.
+pub const PASSED_PAWN_SAFE_PATH: i32 = 10;
+
+/// Per square on a side's third and fourth ranks that none of its pawns can ever defend.
+pub const HOLE_PENALTY: i32 = 4;
+/// Per hole on a colour complex the side no longer has a bishop for.
+pub const COLOR_COMPLEX_PENALTY: i32 = 6;
+
+/// Per ply of remaining depth, how far the static eval must exceed beta to prune the node.
+pub const FUTILITY_MARGIN: i32 = 120;
+
+/// Indexed by remaining depth, how much a quiet move may gain at most at a frontier node.
+pub const FRONTIER_FUTILITY_MARGINS: [i32; 4] = [0, 200, 300, 500];
+
+pub const MAX_SCORE: i32 = 50_000;
+pub const MATE_SCORE: i32 = MAX_SCORE - 1_000;
+
+/// Static evals stay within this, well clear of mate scores even once corrected.
+pub const MAX_EVAL: i32 = MATE_SCORE / 2;
+/// Static evals are multiples of this, so differences of a few centipawns, below what the
+/// eval can tell apart, don't decide between moves.
+pub const EVAL_GRAIN: i32 = 4;
+
+/// From this halfmove clock on, evals fade towards a draw, reaching it at the fifty-move
+/// limit, so a side that is better scores progress that resets the clock above shuffling.
+pub const CONVERSION_HALF_MOVES: u16 = 40;
+
+/// Scales `score` down for how close the halfmove clock is to the fifty-move limit.
+pub fn fade_for_fifty_moves(score: i32, half_moves: u16) -> i32 {
+    let left = 100 - half_moves.clamp(CONVERSION_HALF_MOVES, 100) as i32;
+    score * left / (100 - CONVERSION_HALF_MOVES as i32)
+}
+
+/// Clamps an eval and rounds it towards zero to the grain, so it stays the negation of
+/// the same eval for the other side.
+pub fn to_grain(score: i32) -> i32 {
+    score.clamp(-MAX_EVAL, MAX_EVAL) / EVAL_GRAIN * EVAL_GRAIN
+}
+
+/// Whether the score is a forced mate for either side rather than an evaluation.
+pub fn is_mate_score(score: i32) -> bool {
+    score.abs() >= MATE_SCORE - 64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{Engine, BENCH_POSITIONS};
+
+    #[test]
+    fn test_eval_grain() {
+        for fen in BENCH_POSITIONS {
+            let eval = Engine::new(fen).unwrap().evaluate();
+            assert_eq!(eval % EVAL_GRAIN, 0, "{}", fen);
+            assert!(eval.abs() <= MAX_EVAL, "{}", fen);
+        }
+        // Rounded towards zero, so both sides see the same eval
+        assert_eq!(to_grain(7), EVAL_GRAIN);
+        assert_eq!(to_grain(-7), -EVAL_GRAIN);
+        assert_eq!(to_grain(-MATE_SCORE), -MAX_EVAL);
+    }
+}
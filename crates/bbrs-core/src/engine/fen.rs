@@ -0,0 +1,321 @@
+use super::{
+    attacks,
+    board::{index_to_algebraic, parse_square},
+    castling,
+    piece::{pieces::*, side},
+    zobrist, EngineState,
+};
+
+#[cfg(feature = "crazyhouse")]
+use super::piece::types;
+
+pub const START_POSITION: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+pub fn parse_piece(fen: char) -> Option<u8> {
+    match fen {
+        'P' => Some(WHITE_PAWN),
+        'N' => Some(WHITE_KNIGHT),
+        'B' => Some(WHITE_BISHOP),
+        'R' => Some(WHITE_ROOK),
+        'Q' => Some(WHITE_QUEEN),
+        'K' => Some(WHITE_KING),
+        'p' => Some(BLACK_PAWN),
+        'n' => Some(BLACK_KNIGHT),
+        'b' => Some(BLACK_BISHOP),
+        'r' => Some(BLACK_ROOK),
+        'q' => Some(BLACK_QUEEN),
+        'k' => Some(BLACK_KING),
+        _ => None,
+    }
+}
+
+/// Find the outermost rook of `side` on its back rank on the given wing of the king.
+fn find_castling_rook(bitboards: &[u64; 12], side: u8, king_side: bool) -> Option<u8> {
+    let (king, rook, rank_start) = if side == side::WHITE {
+        (WHITE_KING, WHITE_ROOK, 56)
+    } else {
+        (BLACK_KING, BLACK_ROOK, 0)
+    };
+    let king_file = (get_lsb!(bitboards[king as usize]) % 8) as u8;
+    let rook_on = |file: &u8| get_bit!(bitboards[rook as usize], rank_start + file);
+    if king_side {
+        ((king_file + 1)..8).rev().find(rook_on)
+    } else {
+        (0..king_file).find(rook_on)
+    }
+    .map(|file| rank_start + file)
+}
+
+/// Convert castling rights from a FEN string to a bitmask and the squares of the castling rooks.
+///
+/// Accepts standard `KQkq` (and X-FEN, where they refer to the outermost rooks) as well as
+/// Shredder-FEN rook files (`HAha`) for Chess960.
+fn parse_castle_rights<'a>(
+    rights: &'a str,
+    bitboards: &[u64; 12],
+) -> Result<(u8, [u8; 4]), &'a str> {
+    let mut mask = 0;
+    let mut rooks = castling::DEFAULT_ROOKS;
+    for ch in rights.chars() {
+        let (side, rook) = match ch {
+            '-' => continue,
+            'K' | 'Q' | 'k' | 'q' => {
+                let side = if ch.is_ascii_uppercase() {
+                    side::WHITE
+                } else {
+                    side::BLACK
+                };
+                let king_side = ch.eq_ignore_ascii_case(&'k');
+                let index = castling::index(side, king_side);
+                let rook = find_castling_rook(bitboards, side, king_side)
+                    .unwrap_or(castling::DEFAULT_ROOKS[index]);
+                (side, rook)
+            }
+            'A'..='H' => (side::WHITE, 56 + (ch as u8 - b'A')),
+            'a'..='h' => (side::BLACK, ch as u8 - b'a'),
+            _ => return Err("Invalid FEN: Unexpected character in castling rights"),
+        };
+        let king = if side == side::WHITE {
+            WHITE_KING
+        } else {
+            BLACK_KING
+        };
+        let king_side = rook % 8 > (get_lsb!(bitboards[king as usize]) % 8) as u8;
+        let index = castling::index(side, king_side);
+        mask |= castling::RIGHTS[index];
+        rooks[index] = rook;
+    }
+    Ok((mask, rooks))
+}
+
+/// Parse the en passant square from a FEN string.
+fn parse_en_passant(square: &str) -> Result<Option<u8>, &str> {
+    if square == "-" {
+        return Ok(None);
+    }
+    parse_square(square)
+        .map(Some)
+        .ok_or("Invalid FEN: En passant square must be in algebraic notation")
+}
+
+/// Split a Crazyhouse piece placement into the board and the pieces in hand,
+/// written either as `board[Qn]` or as a ninth rank `board/Qn`.
+#[cfg(feature = "crazyhouse")]
+fn split_pockets(placement: &str) -> (&str, &str) {
+    if let Some((board, pockets)) = placement.split_once('[') {
+        return (board, pockets.trim_end_matches(']'));
+    }
+    if placement.matches('/').count() == 8 {
+        if let Some(split) = placement.rsplit_once('/') {
+            return split;
+        }
+    }
+    (placement, "")
+}
+
+/// Parse the pieces in hand into per-side counts of pawns, knights, bishops, rooks and queens.
+#[cfg(feature = "crazyhouse")]
+fn parse_pockets(pockets: &str) -> Result<[[u8; 5]; 2], &str> {
+    let mut counts = [[0; 5]; 2];
+    for ch in pockets.chars() {
+        match parse_piece(ch) {
+            Some(piece) if piece % 6 != types::KING => {
+                counts[(piece / 6) as usize][(piece % 6) as usize] += 1
+            }
+            None if ch == '-' => (),
+            _ => return Err("Invalid FEN: Unexpected character in pockets"),
+        }
+    }
+    Ok(counts)
+}
+
+pub fn parse(fen: &str) -> Result<EngineState, &str> {
+    let sections: Vec<&str> = fen.split_whitespace().collect();
+
+    // The move counters are often left out, e.g. in EPD, so default them
+    if !(4..=6).contains(&sections.len()) {
+        return Err("Invalid FEN: Incorrect number of sections");
+    }
+
+    let (piece_placement, side, castling, en_passant, half_moves, full_moves) = (
+        sections[0],
+        sections[1],
+        sections[2],
+        sections[3],
+        sections
+            .get(4)
+            .map_or(Ok(0), |clock| clock.parse::<u16>())
+            .map_err(|_| "Invalid halfmove clock")?,
+        sections
+            .get(5)
+            .map_or(Ok(1), |number| number.parse::<u16>())
+            .map_err(|_| "Invalid fullmove number")?,
+    );
+
+    #[cfg(feature = "crazyhouse")]
+    let (piece_placement, pockets) = split_pockets(piece_placement);
+    #[cfg(feature = "crazyhouse")]
+    let pockets = parse_pockets(pockets)?;
+    #[cfg(feature = "crazyhouse")]
+    let mut promoted = 0u64;
+
+    // Reset the board state
+    let mut bitboards = [0u64; 12];
+
+    // Parse piece placement
+    let mut index = 0;
+    for ch in piece_placement.chars() {
+        match ch {
+            '/' => continue,
+            // Marks the preceding piece as promoted, so it reverts to a pawn when captured
+            #[cfg(feature = "crazyhouse")]
+            '~' if index > 0 => set_bit!(promoted, index - 1),
+            ch if ch.is_ascii_digit() => {
+                index += ch.to_digit(10).unwrap() as u64;
+                continue;
+            }
+            _ => {
+                if let Some(piece) = parse_piece(ch) {
+                    set_bit!(bitboards[piece as usize], index);
+                    index += 1;
+                } else {
+                    return Err("Invalid FEN: Unexpected character");
+                }
+            }
+        };
+    }
+
+    // Parse active color
+    let side = match side {
+        "w" => side::WHITE,
+        "b" => side::BLACK,
+        _ => return Err("Invalid FEN: Active color must be 'w' or 'b'"),
+    };
+
+    // Parse castling rights
+    let (castling, castling_rooks) = parse_castle_rights(castling, &bitboards)?;
+    let kings = [WHITE_KING, BLACK_KING].map(|king| match bitboards[king as usize] {
+        0 => None,
+        bitboard => Some(get_lsb!(bitboard) as u8),
+    });
+    let castling_masks = castling::rights_masks(kings, castling_rooks);
+
+    // Parse en passant square
+    let en_passant = parse_en_passant(en_passant)?;
+
+    let mut state = EngineState {
+        non_pawn_pieces: EngineState::count_non_pawn_pieces(&bitboards),
+        checkers: attacks::checkers(&bitboards, side),
+        bitboards,
+        side,
+        castling,
+        castling_rooks,
+        castling_masks,
+        #[cfg(feature = "crazyhouse")]
+        pockets,
+        #[cfg(feature = "crazyhouse")]
+        promoted,
+        en_passant,
+        half_moves,
+        full_moves,
+        hash: 0,
+    };
+    state.hash = zobrist::hash(&state);
+    Ok(state)
+}
+
+/// Writes the castling rights as `KQkq`, using Shredder-style rook files only for
+/// rooks that `KQkq` would not pick, i.e. that are not the outermost ones.
+fn format_castle_rights(state: &EngineState) -> String {
+    let rights = castling::RIGHTS
+        .iter()
+        .enumerate()
+        .filter(|(_, &right)| state.castling & right != 0)
+        .map(|(index, _)| {
+            let (side, king_side) = ((index / 2) as u8, index % 2 == 0);
+            let rook = state.castling_rooks[index];
+            let ch = if find_castling_rook(&state.bitboards, side, king_side) == Some(rook) {
+                if king_side {
+                    'k'
+                } else {
+                    'q'
+                }
+            } else {
+                (b'a' + rook % 8) as char
+            };
+            if side == side::WHITE {
+                ch.to_ascii_uppercase()
+            } else {
+                ch
+            }
+        })
+        .collect::<String>();
+    if rights.is_empty() {
+        "-".to_string()
+    } else {
+        rights
+    }
+}
+
+/// Writes the state back out as a FEN string.
+pub fn format(state: &EngineState) -> String {
+    let mut placement = String::new();
+    for rank in 0..8 {
+        let mut empty = 0;
+        for file in 0..8 {
+            let square = rank * 8 + file;
+            let piece = state
+                .bitboards
+                .iter()
+                .position(|&bitboard| get_bit!(bitboard, square));
+            match piece {
+                Some(piece) => {
+                    if empty > 0 {
+                        placement.push_str(&empty.to_string());
+                        empty = 0;
+                    }
+                    placement.push(ASCII_PIECES[piece]);
+                    #[cfg(feature = "crazyhouse")]
+                    if get_bit!(state.promoted, square) {
+                        placement.push('~');
+                    }
+                }
+                None => empty += 1,
+            }
+        }
+        if empty > 0 {
+            placement.push_str(&empty.to_string());
+        }
+        if rank < 7 {
+            placement.push('/');
+        }
+    }
+
+    #[cfg(feature = "crazyhouse")]
+    if state.pockets.iter().flatten().any(|&count| count > 0) {
+        placement.push('[');
+        for (side, pocket) in state.pockets.iter().enumerate() {
+            for (piece_type, &count) in pocket.iter().enumerate() {
+                placement.extend(std::iter::repeat_n(
+                    ASCII_PIECES[piece_type + side * 6],
+                    count as usize,
+                ));
+            }
+        }
+        placement.push(']');
+    }
+
+    format!(
+        "{} {} {} {} {} {}",
+        placement,
+        if state.side == side::WHITE { 'w' } else { 'b' },
+        format_castle_rights(state),
+        state
+            .en_passant
+            .map_or("-".to_string(), |square| index_to_algebraic(
+                square as usize
+            )),
+        state.half_moves,
+        state.full_moves
+    )
+}
@@ -0,0 +1,80 @@
+use std::time::Duration;
+
+use super::tt::{TranspositionTable, MAX_HASH_MEGABYTES};
+
+/// How long each calibration search runs.
+pub const CALIBRATION_TIME: Duration = Duration::from_millis(500);
+/// Hash sizes, in megabytes, whose fill after a calibration search is reported.
+pub const HASH_CURVE: [usize; 5] = [1, 4, 16, 64, 256];
+/// More threads are only recommended while they add at least this share of the best
+/// speed measured, as hyperthreads add little.
+const THREAD_EFFICIENCY: f64 = 0.9;
+
+/// What calibration searches measured on this machine, and the settings they suggest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HardwareTuning {
+    /// The time a search gets for the first move under the time control.
+    pub move_time: Duration,
+    pub cores: usize,
+    /// Nodes per second by thread count.
+    pub nps: Vec<(usize, u64)>,
+    /// Permille of the table filled by one calibration search, by hash size in megabytes.
+    pub hashfull: Vec<(usize, u32)>,
+    pub threads: usize,
+    pub hash: usize,
+}
+
+/// The thread counts to measure: powers of two up to the number of cores, and that number.
+pub fn thread_counts(cores: usize) -> Vec<usize> {
+    let mut counts = std::iter::successors(Some(1), |&threads| Some(threads * 2))
+        .take_while(|&threads| threads < cores)
+        .collect::<Vec<usize>>();
+    counts.push(cores.max(1));
+    counts
+}
+
+/// The fewest threads that reach `THREAD_EFFICIENCY` of the fastest count measured.
+pub fn recommend_threads(nps: &[(usize, u64)]) -> usize {
+    let best = nps.iter().map(|&(_, nps)| nps).max().unwrap_or(0);
+    nps.iter()
+        .find(|&&(_, nps)| nps as f64 >= best as f64 * THREAD_EFFICIENCY)
+        .map_or(1, |&(threads, _)| threads)
+}
+
+/// The smallest power of two of megabytes with an entry for every node searched in
+/// `move_time` at `nps`, so one move's search does not overwrite its own results.
+pub fn recommend_hash(nps: u64, move_time: Duration) -> usize {
+    let bytes = nps as f64 * move_time.as_secs_f64() * TranspositionTable::ENTRY_SIZE as f64;
+    ((bytes / (1024.0 * 1024.0)).ceil() as usize)
+        .next_power_of_two()
+        .clamp(1, MAX_HASH_MEGABYTES)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recommendations() {
+        assert_eq!(thread_counts(1), vec![1]);
+        assert_eq!(thread_counts(6), vec![1, 2, 4, 6]);
+        assert_eq!(thread_counts(8), vec![1, 2, 4, 8]);
+        // Hyperthreads beyond four cores add under a tenth
+        assert_eq!(
+            recommend_threads(&[
+                (1, 1_000_000),
+                (2, 1_900_000),
+                (4, 3_600_000),
+                (8, 3_900_000)
+            ]),
+            4
+        );
+        assert_eq!(recommend_hash(0, Duration::from_secs(1)), 1);
+        // A million nodes a second for ten seconds takes about 229 MiB of entries
+        assert_eq!(recommend_hash(1_000_000, Duration::from_secs(10)), 256);
+        assert_eq!(
+            recommend_hash(u64::MAX / 2, Duration::from_secs(100)),
+            MAX_HASH_MEGABYTES
+        );
+    }
+}
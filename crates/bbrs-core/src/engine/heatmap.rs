@@ -0,0 +1,142 @@
+use super::{
+    board::index_to_algebraic,
+    pgn::{self, PgnGame},
+    piece::{side, types},
+    Engine, Output, ASCII_PIECES, START_POSITION,
+};
+
+/// How often each square was occupied and attacked, counted per piece over many positions.
+#[derive(Debug, Clone)]
+pub struct Heatmaps {
+    occupancy: [[u32; 64]; 12],
+    attacks: [[u32; 64]; 12],
+    positions: u32,
+}
+
+impl Default for Heatmaps {
+    fn default() -> Self {
+        Heatmaps::new()
+    }
+}
+
+impl Heatmaps {
+    pub fn new() -> Self {
+        Heatmaps {
+            occupancy: [[0; 64]; 12],
+            attacks: [[0; 64]; 12],
+            positions: 0,
+        }
+    }
+
+    /// Builds heatmaps over the main lines of every game in a PGN file.
+    pub fn from_pgn(text: &str) -> Result<Self, String> {
+        let mut heatmaps = Heatmaps::new();
+        let mut engine = Engine::new(START_POSITION).expect("Invalid start position");
+        engine.set_output(Output::sink());
+        for game in pgn::parse(text)? {
+            heatmaps.add_game(&mut engine, &game)?;
+        }
+        Ok(heatmaps)
+    }
+
+    /// Counts every position of the game's main line, starting position included.
+    pub fn add_game(&mut self, engine: &mut Engine, game: &PgnGame) -> Result<(), String> {
+        let fen = game.start_position();
+        engine
+            .set_position(fen)
+            .map_err(|err| format!("{}: {}", err, fen))?;
+        self.add_position(engine);
+        for pgn_move in game.moves.iter() {
+            let move_ = engine
+                .parse_san(&pgn_move.san)
+                .ok_or_else(|| format!("Illegal move: {}", pgn_move.san))?;
+            engine.make_move(move_);
+            self.add_position(engine);
+        }
+        Ok(())
+    }
+
+    pub fn add_position(&mut self, engine: &Engine) {
+        let bitboards = &engine.state.bitboards;
+        let occupancy = bitboards.iter().fold(0, |acc, bitboard| acc | bitboard);
+        let attack_table = &engine.attack_table;
+        for (piece, &bitboard) in bitboards.iter().enumerate() {
+            let mut bitboard = bitboard;
+            while bitboard != 0 {
+                let square = get_lsb!(bitboard) as usize;
+                self.occupancy[piece][square] += 1;
+                let mut attacks = match piece as u8 % 6 {
+                    types::PAWN => {
+                        let side = if piece < 6 { side::WHITE } else { side::BLACK };
+                        attack_table.get_pawn_attacks(side, square)
+                    }
+                    types::KNIGHT => attack_table.get_knight_attacks(square),
+                    types::BISHOP => attack_table.get_bishop_attacks(square, occupancy),
+                    types::ROOK => attack_table.get_rook_attacks(square, occupancy),
+                    types::QUEEN => attack_table.get_queen_attacks(square, occupancy),
+                    _ => attack_table.get_king_attacks(square),
+                };
+                while attacks != 0 {
+                    self.attacks[piece][get_lsb!(attacks) as usize] += 1;
+                    clear_lsb!(attacks);
+                }
+                clear_lsb!(bitboard);
+            }
+        }
+        self.positions += 1;
+    }
+
+    pub fn positions(&self) -> u32 {
+        self.positions
+    }
+
+    /// How many positions had `piece` (0-11, white pawn first) on each square, a8 first.
+    pub fn occupancy(&self, piece: usize) -> &[u32; 64] {
+        &self.occupancy[piece]
+    }
+
+    /// How many times `piece` attacked each square, a8 first.
+    pub fn attacks(&self, piece: usize) -> &[u32; 64] {
+        &self.attacks[piece]
+    }
+
+    /// One `map,piece,square,count` row per piece and square.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("map,piece,square,count\n");
+        for (name, maps) in [("occupancy", &self.occupancy), ("attacks", &self.attacks)] {
+            for (piece, map) in maps.iter().enumerate() {
+                for (square, count) in map.iter().enumerate() {
+                    csv += &format!(
+                        "{},{},{},{}\n",
+                        name,
+                        ASCII_PIECES[piece],
+                        index_to_algebraic(square),
+                        count
+                    );
+                }
+            }
+        }
+        csv
+    }
+
+    /// An object with the position count and both maps, each keyed by piece letter and
+    /// holding 64 counts from a8 to h1.
+    pub fn to_json(&self) -> String {
+        let format_maps = |maps: &[[u32; 64]; 12]| {
+            maps.iter()
+                .enumerate()
+                .map(|(piece, map)| {
+                    let counts = map.iter().map(u32::to_string).collect::<Vec<String>>();
+                    format!("\"{}\":[{}]", ASCII_PIECES[piece], counts.join(","))
+                })
+                .collect::<Vec<String>>()
+                .join(",")
+        };
+        format!(
+            "{{\"positions\":{},\"occupancy\":{{{}}},\"attacks\":{{{}}}}}",
+            self.positions,
+            format_maps(&self.occupancy),
+            format_maps(&self.attacks)
+        )
+    }
+}
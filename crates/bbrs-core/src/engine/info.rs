@@ -0,0 +1,217 @@
+//! UCI `info` lines, parsed into an `InfoMessage` and formatted back.
+//!
+//! The engine formats its own output through `format`, so anything it prints can be read
+//! back with `parse`, and lines from other engines can be handled the same way.
+
+use std::fmt;
+
+use super::evaluate;
+
+/// A score as reported by an engine, from the point of view of the side to move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Score {
+    /// Centipawns.
+    Cp(i32),
+    /// Mate in this many moves, negative when the side to move is getting mated.
+    Mate(i32),
+}
+
+impl Score {
+    /// Converts a search score, where a mate is `MATE_SCORE` less the plies it takes.
+    pub fn from_search(score: i32) -> Score {
+        if !evaluate::is_mate_score(score) {
+            return Score::Cp(score);
+        }
+        let moves = (evaluate::MATE_SCORE - score.abs() + 1) / 2;
+        Score::Mate(if score > 0 { moves } else { -moves })
+    }
+}
+
+/// As in an `info` line: `cp 34` or `mate -2`.
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Score::Cp(cp) => write!(f, "cp {}", cp),
+            Score::Mate(moves) => write!(f, "mate {}", moves),
+        }
+    }
+}
+
+/// Set when the score is only a bound because the search failed high or low.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    Lower,
+    Upper,
+}
+
+/// The fields of one `info` line. Moves are kept as the engine wrote them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InfoMessage {
+    pub depth: Option<u32>,
+    pub seldepth: Option<u32>,
+    pub multipv: Option<u32>,
+    pub score: Option<Score>,
+    pub bound: Option<Bound>,
+    pub nodes: Option<u64>,
+    pub nps: Option<u64>,
+    pub hashfull: Option<u32>,
+    pub tbhits: Option<u64>,
+    /// Milliseconds since the search started.
+    pub time: Option<u64>,
+    pub currmove: Option<String>,
+    pub currmovenumber: Option<u32>,
+    pub pv: Vec<String>,
+    /// Free text, which runs to the end of the line.
+    pub string: Option<String>,
+}
+
+fn parse_value<T: std::str::FromStr>(value: Option<&str>) -> Result<T, &'static str> {
+    value
+        .and_then(|value| value.parse().ok())
+        .ok_or("Invalid info value")
+}
+
+/// Parses an `info` line. Unknown fields are skipped, as the UCI protocol asks of GUIs.
+pub fn parse(line: &str) -> Result<InfoMessage, &'static str> {
+    let mut tokens = line.split_whitespace().peekable();
+    if tokens.next() != Some("info") {
+        return Err("Not an info line");
+    }
+
+    let mut info = InfoMessage::default();
+    while let Some(token) = tokens.next() {
+        match token {
+            "depth" => info.depth = Some(parse_value(tokens.next())?),
+            "seldepth" => info.seldepth = Some(parse_value(tokens.next())?),
+            "multipv" => info.multipv = Some(parse_value(tokens.next())?),
+            "score" => {
+                info.score = Some(match tokens.next() {
+                    Some("cp") => Score::Cp(parse_value(tokens.next())?),
+                    Some("mate") => Score::Mate(parse_value(tokens.next())?),
+                    _ => return Err("Invalid info score"),
+                });
+                info.bound = match tokens.peek() {
+                    Some(&"lowerbound") => Some(Bound::Lower),
+                    Some(&"upperbound") => Some(Bound::Upper),
+                    _ => None,
+                };
+                if info.bound.is_some() {
+                    tokens.next();
+                }
+            }
+            "nodes" => info.nodes = Some(parse_value(tokens.next())?),
+            "nps" => info.nps = Some(parse_value(tokens.next())?),
+            "hashfull" => info.hashfull = Some(parse_value(tokens.next())?),
+            "tbhits" => info.tbhits = Some(parse_value(tokens.next())?),
+            "time" => info.time = Some(parse_value(tokens.next())?),
+            "currmove" => info.currmove = Some(parse_value(tokens.next())?),
+            "currmovenumber" => info.currmovenumber = Some(parse_value(tokens.next())?),
+            "pv" => {
+                // The moves run up to the next field name, or the end of the line
+                while let Some(&move_) = tokens.peek() {
+                    if !is_move(move_) {
+                        break;
+                    }
+                    info.pv.push(move_.to_string());
+                    tokens.next();
+                }
+            }
+            "string" => info.string = Some(tokens.by_ref().collect::<Vec<&str>>().join(" ")),
+            _ => {}
+        }
+    }
+    Ok(info)
+}
+
+/// Whether a token looks like a move in coordinate notation, `e2e4`, `e7e8q` or `N@f3`.
+fn is_move(token: &str) -> bool {
+    let bytes = token.as_bytes();
+    let is_square =
+        |square: &[u8]| (b'a'..=b'h').contains(&square[0]) && (b'1'..=b'8').contains(&square[1]);
+    match bytes.len() {
+        4 if bytes[1] == b'@' => is_square(&bytes[2..]),
+        4 | 5 => is_square(&bytes[..2]) && is_square(&bytes[2..4]),
+        _ => token == "0000",
+    }
+}
+
+/// Formats an `info` line with the fields in the order engines conventionally use.
+pub fn format(info: &InfoMessage) -> String {
+    let mut line = String::from("info");
+    let mut field = |name: &str, value: Option<String>| {
+        if let Some(value) = value {
+            line.push_str(&format!(" {} {}", name, value));
+        }
+    };
+    field("depth", info.depth.map(|value| value.to_string()));
+    field("seldepth", info.seldepth.map(|value| value.to_string()));
+    field("multipv", info.multipv.map(|value| value.to_string()));
+    field(
+        "score",
+        info.score.map(|score| {
+            let score = score.to_string();
+            match info.bound {
+                Some(Bound::Lower) => format!("{} lowerbound", score),
+                Some(Bound::Upper) => format!("{} upperbound", score),
+                None => score,
+            }
+        }),
+    );
+    field("nodes", info.nodes.map(|value| value.to_string()));
+    field("nps", info.nps.map(|value| value.to_string()));
+    field("hashfull", info.hashfull.map(|value| value.to_string()));
+    field("tbhits", info.tbhits.map(|value| value.to_string()));
+    field("time", info.time.map(|value| value.to_string()));
+    field("currmove", info.currmove.clone());
+    field(
+        "currmovenumber",
+        info.currmovenumber.map(|value| value.to_string()),
+    );
+    field("pv", (!info.pv.is_empty()).then(|| info.pv.join(" ")));
+    field("string", info.string.clone());
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_info() {
+        let info = parse(
+            "info depth 12 seldepth 18 multipv 2 score mate -3 upperbound nodes 12345 nps 678 \
+             hashfull 42 time 17 pv e2e4 e7e5 N@f3 string hello  world",
+        )
+        .unwrap();
+        assert_eq!(info.depth, Some(12));
+        assert_eq!(info.seldepth, Some(18));
+        assert_eq!(info.multipv, Some(2));
+        assert_eq!(info.score, Some(Score::Mate(-3)));
+        assert_eq!(info.bound, Some(Bound::Upper));
+        assert_eq!(info.nodes, Some(12345));
+        assert_eq!(info.nps, Some(678));
+        assert_eq!(info.hashfull, Some(42));
+        assert_eq!(info.time, Some(17));
+        assert_eq!(info.pv, vec!["e2e4", "e7e5", "N@f3"]);
+        assert_eq!(info.string.as_deref(), Some("hello world"));
+
+        assert!(parse("info depth x").is_err());
+        assert!(parse("info score").is_err());
+        assert!(parse("bestmove e2e4").is_err());
+        assert_eq!(parse("info wdl 1 2 3 depth 4").unwrap().depth, Some(4));
+    }
+
+    #[test]
+    fn test_info_round_trip() {
+        for line in [
+            "info depth 5 score cp 34 nodes 9876 nps 123456 time 80 pv e2e4 e7e5 g1f3",
+            "info depth 9 seldepth 14 multipv 1 score cp -12 lowerbound nodes 1 tbhits 3",
+            "info currmove e7e8q currmovenumber 7",
+            "info string nodes per move e2e4:123 d2d4:45",
+        ] {
+            let info = parse(line).unwrap();
+            assert_eq!(format(&info), line);
+            assert_eq!(parse(&format(&info)).unwrap(), info);
+        }
+    }
+}
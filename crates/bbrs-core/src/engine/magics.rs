@@ -1,3 +1,6 @@
+//! Finds the magic numbers the attack tables use, printing them, with the `debug` feature.
+#![allow(clippy::print_stdout)]
+
 use std::time::Instant;
 
 use super::attacks::*;
@@ -0,0 +1,4082 @@
+use std::{
+    cmp::Reverse,
+    fs,
+    ops::Range,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use attacks::{masks, AttackTable};
+use board::{distance, index_to_algebraic, parse_square};
+use correction::CorrectionHistory;
+use pawns::PawnTable;
+use pgn::PgnGame;
+use piece::{pieces::*, side};
+use tt::TranspositionTable;
+
+#[macro_use]
+mod bits;
+#[macro_use]
+pub mod moves;
+
+mod adjudication;
+mod analysis;
+mod arena;
+mod attacks;
+mod bench;
+mod board;
+mod castling;
+mod checkpoint;
+mod clock;
+mod correction;
+mod cpu;
+mod database;
+#[cfg(feature = "debug")]
+pub mod debug;
+pub mod eco;
+mod endgame;
+mod evaluate;
+mod fen;
+mod hardware;
+mod heatmap;
+pub mod info;
+#[cfg(feature = "debug")]
+mod magics;
+mod output;
+mod pawns;
+mod perft;
+pub mod pgn;
+mod piece;
+mod playout;
+mod pool;
+mod repertoire;
+mod rules;
+mod search;
+mod selfcheck;
+mod time;
+mod training;
+mod tt;
+mod zobrist;
+
+pub use adjudication::{Adjudication, Decision};
+pub use analysis::{Classification, GameAnalysis, PlyAnalysis};
+pub use arena::{AnalysisJob, Arena};
+pub use bench::{BENCH_DEPTH, BENCH_POSITIONS, BENCH_SIGNATURE};
+pub use board::{file_of, flip, rank_of, Square};
+pub use checkpoint::{Checkpoint, Checkpointing};
+pub use clock::{Clock, Stage, TimeControl};
+pub use database::{Database, Transposition};
+pub use eco::Opening;
+pub use endgame::{Endgames, Handler};
+pub use fen::START_POSITION;
+pub use hardware::HardwareTuning;
+pub use heatmap::Heatmaps;
+pub use info::InfoMessage;
+pub use moves::{IllegalMove, Move};
+pub use output::{Output, Tee};
+pub use perft::{parse_divide, parse_perft_epd, PerftAudit, PerftDiff, PerftEstimate, PerftTable};
+pub use piece::Color;
+pub use playout::{PlayoutPolicy, Rng};
+pub use pool::{EnginePool, PooledEngine};
+pub use repertoire::Repertoire;
+#[cfg(feature = "crazyhouse")]
+pub use rules::Crazyhouse;
+pub use rules::{Chess960, GameState, Horde, Outcome, RacingKings, Rules, Standard};
+use search::SearchState;
+pub use search::{
+    CancellationToken, IterationStats, MoveOrdering, ReplyAnalysis, RootMove, RootMoves,
+    SearchLimits, SearchResult, SearchStats, MAX_PLY,
+};
+pub use selfcheck::SelfCheck;
+pub use time::TimeManager;
+pub use training::announce;
+pub use tt::{DEFAULT_HASH_MEGABYTES, MAX_HASH_MEGABYTES};
+
+#[derive(Debug, Clone)]
+pub struct HistoryItem {
+    move_: u32,
+    captured: u8,
+    side: u8,
+    castling: u8,
+    en_passant: Option<u8>,
+    half_moves: u16,
+    hash: u64,
+    checkers: u64,
+    #[cfg(feature = "crazyhouse")]
+    promoted: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct EngineState {
+    bitboards: [u64; 12],
+    side: u8,
+    castling: u8,
+    castling_rooks: [u8; 4],
+    castling_masks: [u8; 64],
+    /// Pieces in hand per side, indexed by piece type (pawn to queen).
+    #[cfg(feature = "crazyhouse")]
+    pockets: [[u8; 5]; 2],
+    /// Promoted pieces, which revert to pawns when captured.
+    #[cfg(feature = "crazyhouse")]
+    promoted: u64,
+    /// Knights, bishops, rooks and queens on the board per side.
+    non_pawn_pieces: [u8; 2],
+    half_moves: u16,
+    full_moves: u16,
+    en_passant: Option<u8>,
+    /// The Zobrist hash of the position, see `zobrist::hash`.
+    hash: u64,
+    /// The enemy pieces giving check to the side to move, kept up to date by `make_move`.
+    checkers: u64,
+}
+
+impl EngineState {
+    /// A compact signature of the material on the board, see `Endgames`.
+    pub fn material_key(&self) -> u64 {
+        endgame::material_key(&self.bitboards)
+    }
+
+    /// The material of both sides together in centipawns, kings left out.
+    pub fn material(&self) -> i32 {
+        self.bitboards
+            .iter()
+            .enumerate()
+            .filter(|&(piece, _)| piece % 6 != piece::types::KING as usize)
+            .map(|(piece, &bitboard)| {
+                evaluate::MATERIAL_SCORES[piece].abs() * count_bits!(bitboard) as i32
+            })
+            .sum()
+    }
+
+    /// Whether neither side has the material to mate: bare kings, a single minor piece,
+    /// or bishops that all stand on squares of one color.
+    pub fn has_insufficient_material(&self) -> bool {
+        let bitboards = &self.bitboards;
+        let majors_and_pawns = [WHITE_PAWN, WHITE_ROOK, WHITE_QUEEN]
+            .iter()
+            .chain(&[BLACK_PAWN, BLACK_ROOK, BLACK_QUEEN])
+            .any(|&piece| bitboards[piece as usize] != 0);
+        if majors_and_pawns {
+            return false;
+        }
+        let knights = bitboards[WHITE_KNIGHT as usize] | bitboards[BLACK_KNIGHT as usize];
+        let bishops = bitboards[WHITE_BISHOP as usize] | bitboards[BLACK_BISHOP as usize];
+        count_bits!(knights | bishops) <= 1
+            || (knights == 0
+                && (bishops & masks::LIGHT_SQUARES == 0 || bishops & masks::DARK_SQUARES == 0))
+    }
+
+    /// Counts the knights, bishops, rooks and queens of each side from scratch.
+    fn count_non_pawn_pieces(bitboards: &[u64; 12]) -> [u8; 2] {
+        [side::WHITE, side::BLACK].map(|side| {
+            bitboards[side::range(side)][1..5]
+                .iter()
+                .map(|&bitboard| count_bits!(bitboard) as u8)
+                .sum()
+        })
+    }
+}
+
+/// Bytes taken by each of the engine's tables, see `Engine::memory`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// Shared by all engines built from the same tables, see `EnginePool`.
+    pub attack_table: usize,
+    pub transposition_table: usize,
+    pub pawn_table: usize,
+    pub correction_history: usize,
+}
+
+impl MemoryUsage {
+    pub fn total(&self) -> usize {
+        self.attack_table + self.transposition_table + self.pawn_table + self.correction_history
+    }
+}
+
+/// Whether a piece is a knight, bishop, rook or queen.
+fn is_non_pawn_piece(piece: u8) -> bool {
+    !matches!(piece % 6, piece::types::PAWN | piece::types::KING)
+}
+
+/// Whether a move promotes to anything but a queen.
+fn is_underpromotion(move_: u32) -> bool {
+    let (_, _, _, promoted, _) = decode_move!(move_);
+    promoted != 0 && promoted % 6 != piece::types::QUEEN
+}
+
+/// A mix of openings, middlegames and endgames for `Engine::speedtest`.
+const SPEEDTEST_POSITIONS: [&str; 5] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+];
+
+pub struct Engine {
+    attack_table: Arc<AttackTable>,
+    pub state: EngineState,
+    pub history: Vec<HistoryItem>,
+    search_ply: u8,
+    search_nodes: u64,
+    search_state: SearchState,
+    root_depth: u8,
+    /// The deepest ply reached in the current iteration, quiescence search included.
+    sel_depth: u8,
+    null_move_verification_depth: Option<u8>,
+    null_move_min_ply: usize,
+    cancellation: Option<CancellationToken>,
+    stop: Arc<AtomicBool>,
+    stopped: bool,
+    /// Whether the current or last search was stopped by the stop flag, rather than a limit.
+    stop_received: bool,
+    ponderhit: Arc<AtomicBool>,
+    time: TimeManager,
+    node_limit: Option<u64>,
+    /// The root moves the next search is restricted to, all of them if empty.
+    search_moves: Vec<u32>,
+    /// The root moves the next search leaves out, to find the best alternative to them.
+    excluded_moves: Vec<u32>,
+    /// Whether the search waits for a stop before it reports its result.
+    infinite: bool,
+    rules: Arc<dyn Rules>,
+    endgames: Endgames,
+    pawn_table: PawnTable,
+    correction: CorrectionHistory,
+    /// Shared with the helper threads of a parallel search.
+    tt: Arc<TranspositionTable>,
+    /// Threads a search runs on, the main thread included.
+    threads: usize,
+    /// Where a helper thread adds up the nodes it searches, for the main thread to report.
+    helper_nodes: Option<Arc<AtomicU64>>,
+    /// While the main thread searches: when it last printed an info line, and where its
+    /// helpers add up their nodes.
+    progress: Option<(Instant, Arc<AtomicU64>)>,
+    /// What the last search saw, when collected.
+    stats: Option<SearchStats>,
+    quiescence_depth: u8,
+    ordering: MoveOrdering,
+    /// Whether the search leaves out underpromotions, which are rarely best.
+    queen_promotions_only: bool,
+    /// How many best lines the search reports, each excluding the moves of the ones before.
+    multi_pv: usize,
+    checkpointing: Option<Checkpointing>,
+    /// Root moves of a resumed checkpoint, for the next search of the position with this hash.
+    resumed: Option<(u64, RootMoves)>,
+    output: Output,
+    debug: bool,
+}
+
+impl Engine {
+    pub fn new(fen: &str) -> Result<Self, &str> {
+        Engine::with_attack_table(Arc::new(AttackTable::init()), fen)
+    }
+
+    /// Builds an engine on attack tables shared with other engines, which are read-only
+    /// once initialized.
+    pub(crate) fn with_attack_table(
+        attack_table: Arc<AttackTable>,
+        fen: &str,
+    ) -> Result<Self, &str> {
+        let state = fen::parse(fen)?;
+        Ok(Engine {
+            attack_table,
+            state,
+            history: vec![],
+            search_ply: 0,
+            search_nodes: 0,
+            search_state: SearchState::default(),
+            root_depth: 0,
+            sel_depth: 0,
+            null_move_verification_depth: Some(search::NULL_MOVE_VERIFICATION_DEPTH),
+            null_move_min_ply: 0,
+            cancellation: None,
+            stop: Arc::new(AtomicBool::new(false)),
+            stopped: false,
+            stop_received: false,
+            ponderhit: Arc::new(AtomicBool::new(false)),
+            time: TimeManager::default(),
+            node_limit: None,
+            search_moves: vec![],
+            excluded_moves: vec![],
+            infinite: false,
+            rules: Arc::new(Standard),
+            endgames: Endgames::new(),
+            pawn_table: PawnTable::new(1 << 14),
+            correction: CorrectionHistory::new(1 << 14),
+            tt: Arc::new(TranspositionTable::new(tt::DEFAULT_HASH_MEGABYTES)),
+            threads: 1,
+            helper_nodes: None,
+            progress: None,
+            stats: None,
+            quiescence_depth: search::QUIESCENCE_DEPTH,
+            ordering: MoveOrdering::default(),
+            queen_promotions_only: false,
+            multi_pv: 1,
+            checkpointing: None,
+            resumed: None,
+            output: Output::default(),
+            debug: false,
+        })
+    }
+
+    pub fn set_position<'a>(&mut self, fen: &'a str) -> Result<(), &'a str> {
+        self.history.clear();
+        self.state = fen::parse(fen)?;
+        self.print();
+        writeln!(self.output);
+        Ok(())
+    }
+
+    /// The current position as a FEN string.
+    pub fn fen(&self) -> String {
+        fen::format(&self.state)
+    }
+
+    /// Reloads the position from its own FEN and compares it with the incrementally
+    /// updated state: the fields, the evaluation and the legal moves must all match.
+    pub fn check_consistency(&mut self) -> Result<(), String> {
+        let fen = self.fen();
+        let reloaded = fen::parse(&fen).map_err(|err| format!("{} ({})", err, fen))?;
+        let mut mismatches = vec![];
+        {
+            let (state, other) = (&self.state, &reloaded);
+            let rooks = |state: &EngineState| {
+                castling::RIGHTS
+                    .iter()
+                    .zip(state.castling_rooks)
+                    .filter(|(&right, _)| state.castling & right != 0)
+                    .map(|(_, rook)| rook)
+                    .collect::<Vec<u8>>()
+            };
+            // Masks only have to agree on the rights that are still available
+            let masks_differ = (0..64).any(|square| {
+                (state.castling_masks[square] ^ other.castling_masks[square]) & state.castling != 0
+            });
+            let fields = [
+                ("pieces", state.bitboards != other.bitboards),
+                ("side", state.side != other.side),
+                ("castling rights", state.castling != other.castling),
+                (
+                    "castling rooks",
+                    rooks(state) != rooks(other) || masks_differ,
+                ),
+                ("en passant", state.en_passant != other.en_passant),
+                ("halfmove clock", state.half_moves != other.half_moves),
+                ("fullmove number", state.full_moves != other.full_moves),
+                ("material key", state.material_key() != other.material_key()),
+                (
+                    "non-pawn pieces",
+                    state.non_pawn_pieces != other.non_pawn_pieces,
+                ),
+                ("hash", state.hash != other.hash),
+                ("checkers", state.checkers != other.checkers),
+            ];
+            mismatches.extend(
+                fields
+                    .iter()
+                    .filter(|(_, differ)| *differ)
+                    .map(|(name, _)| *name),
+            );
+            #[cfg(feature = "crazyhouse")]
+            if state.pockets != other.pockets || state.promoted != other.promoted {
+                mismatches.push("pockets");
+            }
+        }
+
+        let evaluation = self.evaluate();
+        let mut legal_moves = self.generate_legal_moves();
+        let incremental = std::mem::replace(&mut self.state, reloaded);
+        let history = std::mem::take(&mut self.history);
+        let reloaded_evaluation = self.evaluate();
+        let mut reloaded_moves = self.generate_legal_moves();
+        self.state = incremental;
+        self.history = history;
+
+        if evaluation != reloaded_evaluation {
+            mismatches.push("evaluation");
+        }
+        legal_moves.sort_unstable();
+        reloaded_moves.sort_unstable();
+        if legal_moves != reloaded_moves {
+            mismatches.push("legal moves");
+        }
+
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "{} differ after reloading {}",
+                mismatches.join(", "),
+                fen
+            ))
+        }
+    }
+
+    /// Panics if the board is corrupted: two pieces on a square, a side with more than one
+    /// king, a pawn left on its promotion rank, or a piece count out of step with the board.
+    /// Run after every make and unmake with the `invariant-checks` feature.
+    #[cfg(feature = "invariant-checks")]
+    fn assert_invariants(&self, action: &str, move_: u32) {
+        let bitboards = &self.state.bitboards;
+        let mut occupied = 0;
+        let mut problems = vec![];
+        if bitboards.iter().any(|&bitboard| {
+            let overlap = occupied & bitboard != 0;
+            occupied |= bitboard;
+            overlap
+        }) {
+            problems.push("pieces overlap".to_string());
+        }
+        for side in [side::WHITE, side::BLACK] {
+            let king = bitboards[side::range(side)][piece::types::KING as usize];
+            if king.count_ones() > 1 {
+                problems.push(format!("side {} has {} kings", side, king.count_ones()));
+            }
+        }
+        if bitboards[WHITE_PAWN as usize] & masks::RANK_8 != 0
+            || bitboards[BLACK_PAWN as usize] & masks::RANK_1 != 0
+        {
+            problems.push("a pawn is on its promotion rank".to_string());
+        }
+        if EngineState::count_non_pawn_pieces(bitboards) != self.state.non_pawn_pieces {
+            problems.push(format!(
+                "non-pawn piece counts are {:?}",
+                self.state.non_pawn_pieces
+            ));
+        }
+        assert!(
+            problems.is_empty(),
+            "Board corrupted after {} {}: {} ({})",
+            action,
+            moves::format(move_),
+            problems.join(", "),
+            self.fen()
+        );
+    }
+
+    /// The depth from which null move cutoffs are verified, or `None` to trust them outright.
+    pub fn set_null_move_verification_depth(&mut self, depth: Option<u8>) {
+        self.null_move_verification_depth = depth;
+    }
+
+    /// Redirects everything the engine prints, e.g. to tee it into a log file.
+    pub fn set_output(&mut self, output: Output) {
+        self.output = output;
+    }
+
+    /// In debug mode the search reports extra `info string` lines.
+    pub fn set_debug(&mut self, debug: bool) {
+        self.debug = debug;
+    }
+
+    pub fn rules(&self) -> &dyn Rules {
+        self.rules.as_ref()
+    }
+
+    /// Switches the rule set. Results stored under the old rules are discarded.
+    pub fn set_rules(&mut self, rules: Arc<dyn Rules>) {
+        self.rules = rules;
+        self.tt.clear();
+        self.correction.clear();
+    }
+
+    /// Replaces the transposition table with an empty one of about `megabytes`, between
+    /// 1 and `MAX_HASH_MEGABYTES`.
+    pub fn set_hash_size(&mut self, megabytes: usize) {
+        let megabytes = megabytes.clamp(1, tt::MAX_HASH_MEGABYTES);
+        self.tt = Arc::new(TranspositionTable::new(megabytes));
+    }
+
+    /// The size of the transposition table in megabytes.
+    pub fn hash_size(&self) -> usize {
+        self.tt.megabytes()
+    }
+
+    /// How full the transposition table is, in permille.
+    pub fn hashfull(&self) -> u32 {
+        self.tt.hashfull()
+    }
+
+    /// Makes searches run on `threads` threads. The helper threads search the same
+    /// position and only share what they find through the transposition table, which
+    /// steers the main thread, whose result is the one reported.
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+
+    /// Saves the transposition table, so a later session can start from its results.
+    pub fn save_hash(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.tt.to_bytes()).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    /// Replaces the transposition table with one saved by `save_hash`, size included.
+    pub fn load_hash(&mut self, path: &Path) -> Result<(), String> {
+        let bytes = fs::read(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        self.tt = Arc::new(
+            TranspositionTable::from_bytes(&bytes)
+                .map_err(|err| format!("{}: {}", path.display(), err))?,
+        );
+        Ok(())
+    }
+
+    /// Caps quiescence search at `depth` plies of captures, after which it takes the static
+    /// eval as it stands.
+    pub fn set_quiescence_depth(&mut self, depth: u8) {
+        self.quiescence_depth = depth;
+    }
+
+    /// Turns move ordering heuristics on or off, for experiments.
+    pub fn set_move_ordering(&mut self, ordering: MoveOrdering) {
+        self.ordering = ordering;
+    }
+
+    /// Makes searches only promote to queens, which saves a little time at the risk of
+    /// missing the rare position where a knight or rook is the right choice.
+    pub fn set_queen_promotions_only(&mut self, only: bool) {
+        self.queen_promotions_only = only;
+    }
+
+    /// Makes searches collect `SearchStats`, or stop collecting them.
+    pub fn set_collect_stats(&mut self, collect: bool) {
+        self.stats = collect.then(SearchStats::default);
+    }
+
+    /// What the last search saw, if stats are collected.
+    pub fn search_stats(&self) -> Option<&SearchStats> {
+        self.stats.as_ref()
+    }
+
+    fn count(&mut self, update: impl FnOnce(&mut SearchStats)) {
+        if let Some(stats) = &mut self.stats {
+            update(stats);
+        }
+    }
+
+    /// Makes the next search hold back its result until it is stopped, even once it has
+    /// reached its depth, as `go infinite` requires.
+    pub fn set_infinite(&mut self, infinite: bool) {
+        self.infinite = infinite;
+    }
+
+    /// Makes searches find the best `lines` moves instead of only the best one, at the cost
+    /// of searching the root that many times.
+    pub fn set_multi_pv(&mut self, lines: usize) {
+        self.multi_pv = lines.max(1);
+    }
+
+    /// Makes searches save a checkpoint every `interval` and once they finish.
+    pub fn set_checkpointing(&mut self, checkpointing: Option<Checkpointing>) {
+        self.checkpointing = checkpointing;
+    }
+
+    /// Loads a checkpoint's position and transposition table. The next search of the
+    /// position starts from the checkpoint's root moves, node counts included.
+    pub fn resume(&mut self, path: &Path) -> Result<Checkpoint, String> {
+        let (checkpoint, tt) = Checkpoint::load(path)?;
+        let state =
+            fen::parse(&checkpoint.fen).map_err(|err| format!("{}: {}", err, checkpoint.fen))?;
+        let previous = (
+            std::mem::replace(&mut self.state, state),
+            std::mem::take(&mut self.history),
+        );
+        let mut legal_moves = self.generate_legal_moves();
+        let mut saved_moves = checkpoint
+            .root_moves
+            .iter()
+            .map(|root_move| root_move.move_)
+            .collect::<Vec<u32>>();
+        legal_moves.sort_unstable();
+        saved_moves.sort_unstable();
+        if legal_moves != saved_moves {
+            (self.state, self.history) = previous;
+            return Err(format!(
+                "{}: the root moves do not match the position",
+                path.display()
+            ));
+        }
+        self.tt = Arc::new(tt);
+        self.resumed = Some((self.state.hash, checkpoint.root_moves.clone()));
+        Ok(checkpoint)
+    }
+
+    fn save_checkpoint(&mut self, result: &SearchResult) {
+        let Some(checkpointing) = &self.checkpointing else {
+            return;
+        };
+        let checkpoint = Checkpoint {
+            fen: self.fen(),
+            depth: result.depth,
+            root_moves: result.root_moves.clone(),
+        };
+        if let Err(err) = checkpoint.save(&self.tt, &checkpointing.path) {
+            writeln!(self.output, "info string checkpoint failed: {}", err);
+        }
+    }
+
+    /// Forgets all stored search results and learned eval corrections.
+    pub fn clear_hash(&mut self) {
+        self.tt.clear();
+        self.correction.clear();
+    }
+
+    /// Forgets everything learned from earlier searches, as positions from another game
+    /// say nothing about this one.
+    pub fn new_game(&mut self) {
+        self.clear_hash();
+        self.search_state.clear();
+    }
+
+    /// The specialized endgame evaluations, keyed by material signature.
+    pub fn endgames_mut(&mut self) -> &mut Endgames {
+        &mut self.endgames
+    }
+
+    /// Returns the flag that aborts a running search when set.
+    ///
+    /// The search only honours the flag once the first iteration has completed,
+    /// so there is always a best move to report. The flag is cleared when the search returns.
+    pub fn stop_handle(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.stop)
+    }
+
+    /// Returns the flag that converts a ponder search into a normal one when set.
+    pub fn ponderhit_handle(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.ponderhit)
+    }
+
+    /// Sets the time limits for the next search.
+    pub fn set_time_manager(&mut self, time: TimeManager) {
+        self.time = time;
+    }
+
+    /// Stops the next search once it has searched `nodes` nodes. Like the time manager,
+    /// the limit only applies to one search.
+    pub fn set_node_limit(&mut self, nodes: Option<u64>) {
+        self.node_limit = nodes;
+    }
+
+    /// Restricts the next search to the given root moves, as `go searchmoves` does. Moves
+    /// that are not legal are left out, and if none is legal the search considers them all.
+    pub fn set_search_moves(&mut self, moves: Vec<u32>) {
+        self.search_moves = moves;
+    }
+
+    /// Leaves the given root moves out of the next search, as the non-standard
+    /// `go excludemoves` does. If that would leave no legal move, none is left out.
+    pub fn set_excluded_moves(&mut self, moves: Vec<u32>) {
+        self.excluded_moves = moves;
+    }
+
+    /// The legal moves at the root the search may choose from.
+    fn root_legal_moves(&mut self) -> Vec<u32> {
+        let mut legal_moves = self.generate_legal_moves();
+        if self.queen_promotions_only {
+            legal_moves.retain(|&move_| !is_underpromotion(move_));
+        }
+        if legal_moves
+            .iter()
+            .any(|move_| !self.excluded_moves.contains(move_))
+        {
+            legal_moves.retain(|move_| !self.excluded_moves.contains(move_));
+        }
+        let restricted = legal_moves
+            .iter()
+            .copied()
+            .filter(|move_| self.search_moves.contains(move_))
+            .collect::<Vec<u32>>();
+        if restricted.is_empty() {
+            legal_moves
+        } else {
+            restricted
+        }
+    }
+
+    /// Sets the node and time limits for the next search at once.
+    pub fn set_limits(&mut self, limits: SearchLimits) {
+        self.node_limit = limits.nodes;
+        self.time = TimeManager::with_limits(limits.soft_time, limits.hard_time);
+    }
+
+    /// Polls the stop and ponderhit flags and the clock every `STOP_POLL_NODES` nodes, and
+    /// the node limit at every node.
+    fn check_stop(&mut self) -> bool {
+        if self
+            .node_limit
+            .is_some_and(|limit| self.search_nodes >= limit)
+        {
+            self.stopped = true;
+        }
+        if self.stopped || !self.search_nodes.is_multiple_of(search::STOP_POLL_NODES) {
+            return self.stopped;
+        }
+        if let Some(nodes) = &self.helper_nodes {
+            nodes.fetch_add(search::STOP_POLL_NODES, Ordering::Relaxed);
+        }
+        if self.time.is_pondering() && self.ponderhit.swap(false, Ordering::Relaxed) {
+            self.time.ponderhit();
+        }
+        if self.stop.load(Ordering::Relaxed) {
+            self.stopped = true;
+            self.stop_received = true;
+        }
+        if self.time.is_time_up() {
+            self.stopped = true;
+        }
+        if self.is_cancelled() {
+            self.stopped = true;
+        }
+        if self
+            .progress
+            .as_ref()
+            .is_some_and(|(last, _)| last.elapsed() >= search::INFO_INTERVAL)
+        {
+            self.print_progress();
+        }
+        self.stopped
+    }
+
+    /// Reports the nodes, speed and hash usage so far, so that a long iteration is not
+    /// silent until it completes.
+    fn print_progress(&mut self) {
+        let Some((last, helper_nodes)) = &mut self.progress else {
+            return;
+        };
+        *last = Instant::now();
+        let nodes = self.search_nodes + helper_nodes.load(Ordering::Relaxed);
+        let elapsed = self.time.search_elapsed();
+        let info = InfoMessage {
+            nodes: Some(nodes),
+            nps: Some((nodes as f64 / elapsed.as_secs_f64().max(1e-9)) as u64),
+            hashfull: Some(self.tt.hashfull()),
+            time: Some(elapsed.as_millis() as u64),
+            ..InfoMessage::default()
+        };
+        writeln!(self.output, "{}", info::format(&info));
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+
+    /// Like `search_position`, but returns as soon as `token` is cancelled or its deadline
+    /// passes. Every move made during the search is taken back before returning, so the
+    /// position and history are the same as before the call.
+    pub fn search(&mut self, depth: u8, token: &CancellationToken) -> SearchResult {
+        self.cancellation = Some(token.clone());
+        let result = self.search_position(depth);
+        self.cancellation = None;
+        result
+    }
+
+    fn get_occupancy(&self, range: Range<usize>) -> u64 {
+        self.state.bitboards[range]
+            .iter()
+            .fold(0, |mut acc, bitboard| {
+                acc |= bitboard;
+                acc
+            })
+    }
+
+    pub fn is_square_attacked(&self, square: usize, side: u8) -> bool {
+        let EngineState { bitboards, .. } = self.state;
+        let enemy = side ^ 1;
+
+        // Select the appropriate piece types for the enemy
+        let (pawn, knight, bishop, rook, queen, king) = if enemy == side::WHITE {
+            (
+                WHITE_PAWN,
+                WHITE_KNIGHT,
+                WHITE_BISHOP,
+                WHITE_ROOK,
+                WHITE_QUEEN,
+                WHITE_KING,
+            )
+        } else {
+            (
+                BLACK_PAWN,
+                BLACK_KNIGHT,
+                BLACK_BISHOP,
+                BLACK_ROOK,
+                BLACK_QUEEN,
+                BLACK_KING,
+            )
+        };
+
+        // Check non-sliding pieces (pawn, knight, king)
+        if self.attack_table.get_pawn_attacks(side, square) & bitboards[pawn as usize] != 0
+            || self.attack_table.get_knight_attacks(square) & bitboards[knight as usize] != 0
+            || self.attack_table.get_king_attacks(square) & bitboards[king as usize] != 0
+        {
+            return true;
+        }
+
+        // Occupancy is only needed for sliding pieces
+        let occupancy = self.get_occupancy(piece::range::ALL);
+
+        // Check sliding pieces (bishop, rook, queen)
+        if self.attack_table.get_bishop_attacks(square, occupancy) & bitboards[bishop as usize] != 0
+            || self.attack_table.get_rook_attacks(square, occupancy) & bitboards[rook as usize] != 0
+            || self.attack_table.get_queen_attacks(square, occupancy) & bitboards[queen as usize]
+                != 0
+        {
+            return true;
+        }
+
+        false
+    }
+
+    /// Every piece of either side attacking `square`, with sliders blocked by `occupancy`.
+    fn attackers_to(&self, square: usize, occupancy: u64) -> u64 {
+        let bitboards = &self.state.bitboards;
+        let bishops = bitboards[WHITE_BISHOP as usize]
+            | bitboards[BLACK_BISHOP as usize]
+            | bitboards[WHITE_QUEEN as usize]
+            | bitboards[BLACK_QUEEN as usize];
+        let rooks = bitboards[WHITE_ROOK as usize]
+            | bitboards[BLACK_ROOK as usize]
+            | bitboards[WHITE_QUEEN as usize]
+            | bitboards[BLACK_QUEEN as usize];
+        (self.attack_table.get_pawn_attacks(side::BLACK, square) & bitboards[WHITE_PAWN as usize])
+            | (self.attack_table.get_pawn_attacks(side::WHITE, square)
+                & bitboards[BLACK_PAWN as usize])
+            | (self.attack_table.get_knight_attacks(square)
+                & (bitboards[WHITE_KNIGHT as usize] | bitboards[BLACK_KNIGHT as usize]))
+            | (self.attack_table.get_king_attacks(square)
+                & (bitboards[WHITE_KING as usize] | bitboards[BLACK_KING as usize]))
+            | (self.attack_table.get_bishop_attacks(square, occupancy) & bishops)
+            | (self.attack_table.get_rook_attacks(square, occupancy) & rooks)
+    }
+
+    /// Static exchange evaluation: the material the side to move gains by playing `move_`
+    /// when both sides keep recapturing on the target square with their least valuable
+    /// attacker, and either may stop whenever that is better. Sliders lined up behind a
+    /// capturing piece join in as it leaves. Pins and checks are not considered.
+    pub fn see(&self, move_: u32) -> i32 {
+        let (source, target, piece, promotion, (capture, _, en_passant, castle)) =
+            decode_move!(move_);
+        #[cfg(feature = "crazyhouse")]
+        if moves::is_drop(move_) {
+            return 0;
+        }
+        if castle {
+            return 0;
+        }
+        let value = |piece: u8| evaluate::MATERIAL_SCORES[(piece % 6) as usize];
+        let (source, target) = (source as usize, target as usize);
+        let mover = piece / 6;
+
+        let mut occupancy = self.get_occupancy(piece::range::ALL) & !bitboard!(source);
+        let mut gains = [0; 32];
+        gains[0] = if en_passant {
+            let captured_square = if mover == side::WHITE {
+                target + 8
+            } else {
+                target - 8
+            };
+            occupancy &= !bitboard!(captured_square);
+            value(WHITE_PAWN)
+        } else if capture {
+            self.get_piece(mover ^ 1, target as u8).map_or(0, value)
+        } else {
+            0
+        };
+        // The piece standing on the target square, next in line to be captured
+        let mut on_target = if promotion != 0 {
+            gains[0] += value(promotion) - value(WHITE_PAWN);
+            value(promotion)
+        } else {
+            value(piece)
+        };
+
+        let mut side = mover ^ 1;
+        let mut depth = 0;
+        while depth + 1 < gains.len() {
+            let attackers = self.attackers_to(target, occupancy) & occupancy;
+            let Some(attacker) =
+                side::range(side).find(|&attacker| attackers & self.state.bitboards[attacker] != 0)
+            else {
+                break;
+            };
+            depth += 1;
+            gains[depth] = on_target - gains[depth - 1];
+            on_target = value(attacker as u8);
+            // A pawn recapturing on the last rank promotes
+            if attacker as u8 % 6 == piece::types::PAWN
+                && bitboard!(target) & (masks::RANK_1 | masks::RANK_8) != 0
+            {
+                gains[depth] += value(WHITE_QUEEN) - value(WHITE_PAWN);
+                on_target = value(WHITE_QUEEN);
+            }
+            let square = get_lsb!(attackers & self.state.bitboards[attacker]) as usize;
+            occupancy &= !bitboard!(square);
+            side ^= 1;
+        }
+
+        // Each side only recaptures when that is better than stopping
+        while depth > 0 {
+            gains[depth - 1] = -(-gains[depth - 1]).max(gains[depth]);
+            depth -= 1;
+        }
+        gains[0]
+    }
+
+    pub fn generate_moves(&self) -> Vec<u32> {
+        let mut moves: Vec<u32> = Vec::new();
+
+        let EngineState {
+            bitboards,
+            side,
+            en_passant,
+            ..
+        } = self.state;
+        let all_pieces = self.get_occupancy(piece::range::ALL);
+        let friendly_pieces = self.get_occupancy(side::range(side));
+        let enemy_pieces = self.get_occupancy(side::range(side ^ 1));
+
+        bitboards[side::range(side)]
+            .iter()
+            .enumerate()
+            .for_each(|(piece_type, &bitboard)| {
+                let mut bitboard = bitboard;
+                let piece_type = piece_type as u8;
+                let piece = (piece_type + side * 6) as usize;
+                if piece_type == piece::types::PAWN {
+                    let (start_rank, end_rank, promotion_rank, push) = if side == side::WHITE {
+                        (masks::RANK_2, masks::RANK_8, masks::RANK_7, -8)
+                    } else {
+                        (masks::RANK_7, masks::RANK_1, masks::RANK_2, 8)
+                    };
+                    // A pawn on the last rank has nowhere to go. Legal play never leaves one
+                    // there, but a hand-made position can, and it must not hide the others.
+                    bitboard &= !end_rank;
+                    while bitboard != 0 {
+                        let source = get_lsb!(bitboard) as usize;
+                        let source_bitboard = bitboard!(source);
+                        // Quiet moves
+                        let target = source.wrapping_add_signed(push);
+                        if !get_bit!(all_pieces, target) {
+                            if source_bitboard & promotion_rank != 0 {
+                                // Promotions
+                                piece::types::PROMOTION_PIECES
+                                    .iter()
+                                    .for_each(|&promotion| {
+                                        let promotion_piece = promotion + self.state.side * 6;
+                                        moves.push(encode_move!(
+                                            source,
+                                            target,
+                                            piece,
+                                            promotion_piece as usize,
+                                            0
+                                        ));
+                                    });
+                            } else {
+                                // Single push
+                                moves.push(encode_move!(source, target, piece));
+                            }
+
+                            // Double push
+                            if source_bitboard & start_rank != 0 {
+                                let double = target.wrapping_add_signed(push);
+                                if !get_bit!(all_pieces, double) {
+                                    moves.push(encode_move!(
+                                        source,
+                                        double,
+                                        piece,
+                                        moves::flags::DOUBLE as usize
+                                    ));
+                                }
+                            }
+                        }
+
+                        // Attacks
+                        let mut attacks = self.attack_table.get_pawn_attacks(side, source);
+
+                        while attacks != 0 {
+                            let target = get_lsb!(attacks) as usize;
+                            let target_bitboard = bitboard!(target);
+
+                            // Captures
+                            if target_bitboard & enemy_pieces != 0 {
+                                if source_bitboard & promotion_rank != 0 {
+                                    // Promotions
+                                    piece::types::PROMOTION_PIECES
+                                        .iter()
+                                        .for_each(|&promotion| {
+                                            let promotion_piece = promotion + self.state.side * 6;
+                                            moves.push(encode_move!(
+                                                source,
+                                                target,
+                                                piece,
+                                                promotion_piece as usize,
+                                                moves::flags::CAPTURE as usize
+                                            ));
+                                        });
+                                } else {
+                                    moves.push(encode_move!(
+                                        source,
+                                        target,
+                                        piece,
+                                        moves::flags::CAPTURE as usize
+                                    ));
+                                }
+                            }
+
+                            // En passant
+                            if let Some(en_passant) = en_passant {
+                                if target_bitboard & bitboard!(en_passant) != 0 {
+                                    moves.push(encode_move!(
+                                        source,
+                                        target,
+                                        piece,
+                                        (moves::flags::CAPTURE | moves::flags::EN_PASSANT) as usize
+                                    ));
+                                }
+                            }
+                            clear_lsb!(attacks);
+                        }
+
+                        clear_lsb!(bitboard);
+                    }
+                    return;
+                }
+                if piece_type == piece::types::KING && self.rules.allows_castling() {
+                    // Castling
+                    let king_square = get_lsb!(bitboard) as u8;
+                    for king_side in [true, false] {
+                        let index = castling::index(side, king_side);
+                        if !self.can_castle(castling::RIGHTS[index]) {
+                            continue;
+                        }
+                        let rook_square = self.state.castling_rooks[index];
+                        let (king_target, rook_target) = castling::targets(side, king_side);
+                        let blockers =
+                            all_pieces & !bitboard!(king_square) & !bitboard!(rook_square);
+                        let path = castling::span(king_square, king_target)
+                            | castling::span(rook_square, rook_target);
+                        if path & blockers != 0 {
+                            continue;
+                        }
+                        // The king may not castle out of or through check: every square from
+                        // its own up to the one before its destination must be safe, however
+                        // far it travels in Chess960. The destination is verified by
+                        // `make_move` like any other king move.
+                        let mut transit = castling::transit(king_square, king_target);
+                        let mut attacked = false;
+                        while transit != 0 {
+                            if self.is_square_attacked(get_lsb!(transit) as usize, side) {
+                                attacked = true;
+                                break;
+                            }
+                            clear_lsb!(transit);
+                        }
+                        if !attacked {
+                            moves.push(encode_move!(
+                                king_square as usize,
+                                king_target as usize,
+                                piece,
+                                moves::flags::CASTLE as usize
+                            ));
+                        }
+                    }
+                }
+
+                while bitboard != 0 {
+                    let source = get_lsb!(bitboard) as usize;
+                    let mut attacks = match piece_type {
+                        piece::types::KNIGHT => self.attack_table.get_knight_attacks(source),
+                        piece::types::KING => self.attack_table.get_king_attacks(source),
+                        piece::types::BISHOP => {
+                            self.attack_table.get_bishop_attacks(source, all_pieces)
+                        }
+                        piece::types::ROOK => {
+                            self.attack_table.get_rook_attacks(source, all_pieces)
+                        }
+                        piece::types::QUEEN => {
+                            self.attack_table.get_queen_attacks(source, all_pieces)
+                        }
+                        _ => unreachable!(),
+                    } & !friendly_pieces;
+                    while attacks != 0 {
+                        let target = get_lsb!(attacks) as usize;
+                        let target_bitboard = bitboard!(target);
+
+                        // Captures
+                        if target_bitboard & enemy_pieces != 0 {
+                            moves.push(encode_move!(
+                                source,
+                                target,
+                                piece,
+                                moves::flags::CAPTURE as usize
+                            ));
+                        } else {
+                            moves.push(encode_move!(source, target, piece));
+                        }
+                        clear_lsb!(attacks);
+                    }
+                    clear_lsb!(bitboard);
+                }
+            });
+
+        #[cfg(feature = "crazyhouse")]
+        self.generate_drops(&mut moves, all_pieces);
+
+        self.rules.generate_extra_moves(self, &mut moves);
+        moves.retain(|&move_| self.rules.is_move_allowed(self, move_));
+        moves
+    }
+
+    #[cfg(feature = "crazyhouse")]
+    fn generate_drops(&self, moves: &mut Vec<u32>, all_pieces: u64) {
+        let side = self.state.side;
+        for (piece_type, &count) in self.state.pockets[side as usize].iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+            let mut empty = !all_pieces;
+            if piece_type as u8 == piece::types::PAWN {
+                empty &= !(masks::RANK_1 | masks::RANK_8);
+            }
+            let piece = piece_type + side as usize * 6;
+            while empty != 0 {
+                let target = get_lsb!(empty) as usize;
+                moves.push(encode_move!(
+                    target,
+                    target,
+                    piece,
+                    moves::flags::DROP as usize
+                ));
+                clear_lsb!(empty);
+            }
+        }
+    }
+
+    fn can_castle(&self, mask: u8) -> bool {
+        let EngineState { castling, .. } = self.state;
+        match castling {
+            0 => false,
+            _ => castling & mask != 0,
+        }
+    }
+
+    fn get_piece(&self, side: u8, target: u8) -> Option<u8> {
+        let board = self.state.bitboards[side::range(side)]
+            .iter()
+            .enumerate()
+            .find(|(_, &bitboard)| get_bit!(bitboard, target));
+        if let Some((index, _)) = board {
+            let captured = index + (side as usize * 6);
+            Some(captured as u8)
+        } else {
+            None
+        }
+    }
+
+    pub fn make_move(&mut self, move_: u32) -> bool {
+        let mut history_item = HistoryItem {
+            move_,
+            captured: 0,
+            side: self.state.side,
+            castling: self.state.castling,
+            en_passant: self.state.en_passant,
+            half_moves: self.state.half_moves,
+            hash: self.state.hash,
+            checkers: self.state.checkers,
+            #[cfg(feature = "crazyhouse")]
+            promoted: self.state.promoted,
+        };
+        let (source, target, piece, promotion, flags) = decode_move!(move_);
+        clear_bit!(self.state.bitboards[piece as usize], source);
+        set_bit!(self.state.bitboards[piece as usize], target);
+        self.state.hash ^= zobrist::piece(piece, source) ^ zobrist::piece(piece, target);
+        // Drops share the source and target square but, unlike a king that stays put
+        // when castling in Chess960, still add a piece to the board
+        #[cfg(feature = "crazyhouse")]
+        if moves::is_drop(move_) {
+            self.state.hash ^= zobrist::piece(piece, target);
+        }
+        let (capture, double, en_passant, castle) = flags;
+        if capture {
+            if let Some(captured) = self.get_piece(self.state.side ^ 1, target) {
+                #[cfg(feature = "invariant-checks")]
+                assert!(
+                    captured % 6 != piece::types::KING,
+                    "King captured by {} ({})",
+                    moves::format(move_),
+                    self.fen()
+                );
+                history_item.captured = captured;
+                clear_bit!(self.state.bitboards[captured as usize], target);
+                self.state.hash ^= zobrist::piece(captured, target);
+                if is_non_pawn_piece(captured) {
+                    self.state.non_pawn_pieces[(self.state.side ^ 1) as usize] -= 1;
+                }
+            };
+        };
+
+        #[cfg(feature = "crazyhouse")]
+        if self.rules.uses_pockets() {
+            let side = self.state.side as usize;
+            self.state.hash ^= zobrist::pocket(side, &self.state.pockets[side]);
+            self.update_pockets(move_, capture.then_some(history_item.captured));
+            self.state.hash ^= zobrist::pocket(side, &self.state.pockets[side]);
+        }
+
+        self.history.push(history_item);
+
+        if promotion != 0 {
+            clear_bit!(self.state.bitboards[piece as usize], target);
+            set_bit!(self.state.bitboards[promotion as usize], target);
+            self.state.hash ^= zobrist::piece(piece, target) ^ zobrist::piece(promotion, target);
+            self.state.non_pawn_pieces[self.state.side as usize] += 1;
+        }
+        let (enemy_pawn, pawn_offset) = if self.state.side == side::WHITE {
+            (BLACK_PAWN, 8)
+        } else {
+            (WHITE_PAWN, -8)
+        };
+
+        if en_passant {
+            let square = (target as i8 + pawn_offset) as u8;
+            clear_bit!(self.state.bitboards[enemy_pawn as usize], square);
+            self.state.hash ^= zobrist::piece(enemy_pawn, square);
+        }
+        if let Some(square) = self.state.en_passant {
+            self.state.hash ^= zobrist::en_passant(square);
+        }
+        self.state.en_passant = if double {
+            Some((target as i8 + pawn_offset) as u8)
+        } else {
+            None
+        };
+        if let Some(square) = self.state.en_passant {
+            self.state.hash ^= zobrist::en_passant(square);
+        }
+
+        if castle {
+            let (rook, rook_source, rook_target) = self.castling_rook_move(self.state.side, target);
+            clear_bit!(self.state.bitboards[rook], rook_source);
+            set_bit!(self.state.bitboards[rook], rook_target);
+            self.state.hash ^=
+                zobrist::piece(rook as u8, rook_source) ^ zobrist::piece(rook as u8, rook_target);
+        }
+
+        self.state.hash ^= zobrist::castling(self.state.castling);
+        self.state.castling &= self.state.castling_masks[source as usize];
+        self.state.castling &= self.state.castling_masks[target as usize];
+        self.state.hash ^= zobrist::castling(self.state.castling);
+        let king = if self.state.side == side::WHITE {
+            self.state.bitboards[WHITE_KING as usize]
+        } else {
+            self.state.bitboards[BLACK_KING as usize]
+        };
+        if capture || piece % 6 == piece::types::PAWN {
+            self.state.half_moves = 0;
+        } else {
+            // A game where nobody claims the draw can run past any clock
+            self.state.half_moves = self.state.half_moves.saturating_add(1);
+        }
+        if self.state.side == side::BLACK {
+            self.state.full_moves += 1;
+        }
+        self.state.side ^= 1;
+        self.state.hash ^= zobrist::side();
+        if king != 0 && self.is_square_attacked(get_lsb!(king) as usize, self.state.side ^ 1) {
+            self.take_back();
+            return false;
+        }
+        self.state.checkers = self.find_checkers();
+        if !self.rules.is_position_legal(self) {
+            self.take_back();
+            return false;
+        }
+        #[cfg(feature = "invariant-checks")]
+        self.assert_invariants("making", move_);
+        true
+    }
+
+    /// Moves captured pieces into the capturer's pocket, takes dropped pieces out
+    /// of it, and keeps track of promoted pieces.
+    #[cfg(feature = "crazyhouse")]
+    fn update_pockets(&mut self, move_: u32, captured: Option<u8>) {
+        let (source, target, piece, promotion, (_, _, en_passant, _)) = decode_move!(move_);
+        if moves::is_drop(move_) && is_non_pawn_piece(piece) {
+            self.state.non_pawn_pieces[self.state.side as usize] += 1;
+        }
+        let pocket = &mut self.state.pockets[self.state.side as usize];
+        if moves::is_drop(move_) {
+            pocket[(piece % 6) as usize] -= 1;
+            return;
+        }
+        if en_passant {
+            pocket[piece::types::PAWN as usize] += 1;
+        } else if let Some(captured) = captured {
+            let captured_type = if get_bit!(self.state.promoted, target) {
+                piece::types::PAWN
+            } else {
+                captured % 6
+            };
+            pocket[captured_type as usize] += 1;
+        }
+        let moved_promoted = get_bit!(self.state.promoted, source);
+        clear_bit!(self.state.promoted, source);
+        clear_bit!(self.state.promoted, target);
+        if moved_promoted || promotion != 0 {
+            set_bit!(self.state.promoted, target);
+        }
+    }
+
+    /// The rook bitboard index and the rook's (source, target) squares when
+    /// `side` castles with its king landing on `king_target`.
+    fn castling_rook_move(&self, side: u8, king_target: u8) -> (usize, u8, u8) {
+        let king_side = king_target % 8 == Square::G1 as u8 % 8;
+        let rook = if side == side::WHITE {
+            WHITE_ROOK
+        } else {
+            BLACK_ROOK
+        };
+        let (_, rook_target) = castling::targets(side, king_side);
+        (
+            rook as usize,
+            self.state.castling_rooks[castling::index(side, king_side)],
+            rook_target,
+        )
+    }
+
+    pub fn take_back(&mut self) {
+        let HistoryItem {
+            move_,
+            captured,
+            side,
+            castling,
+            en_passant,
+            half_moves,
+            hash,
+            checkers,
+            #[cfg(feature = "crazyhouse")]
+            promoted,
+        } = self
+            .history
+            .pop()
+            .expect("Engine history is empty. This should never happen.");
+        let (source, target, piece, promotion, flags) = decode_move!(move_);
+        clear_bit!(self.state.bitboards[piece as usize], target);
+        set_bit!(self.state.bitboards[piece as usize], source);
+
+        #[cfg(feature = "crazyhouse")]
+        if self.rules.uses_pockets() {
+            let pocket = &mut self.state.pockets[side as usize];
+            if moves::is_drop(move_) {
+                clear_bit!(self.state.bitboards[piece as usize], target);
+                pocket[(piece % 6) as usize] += 1;
+                if is_non_pawn_piece(piece) {
+                    self.state.non_pawn_pieces[side as usize] -= 1;
+                }
+            } else if flags.2 {
+                pocket[piece::types::PAWN as usize] -= 1;
+            } else if flags.0 {
+                let captured_type = if get_bit!(promoted, target) {
+                    piece::types::PAWN
+                } else {
+                    captured % 6
+                };
+                pocket[captured_type as usize] -= 1;
+            }
+            self.state.promoted = promoted;
+        }
+
+        if promotion != 0 {
+            clear_bit!(self.state.bitboards[promotion as usize], target);
+            self.state.non_pawn_pieces[side as usize] -= 1;
+        }
+
+        let (capture_flag, _, en_passant_flag, castle_flag) = flags;
+
+        if en_passant_flag {
+            let (pawn, restore_square) = if self.state.side == side::WHITE {
+                (WHITE_PAWN, target - 8)
+            } else {
+                (BLACK_PAWN, target + 8)
+            };
+            set_bit!(self.state.bitboards[pawn as usize], restore_square);
+        } else if capture_flag {
+            set_bit!(self.state.bitboards[captured as usize], target);
+            if is_non_pawn_piece(captured) {
+                self.state.non_pawn_pieces[(side ^ 1) as usize] += 1;
+            }
+        };
+
+        if castle_flag {
+            let (rook, rook_source, rook_target) = self.castling_rook_move(side, target);
+            clear_bit!(self.state.bitboards[rook], rook_target);
+            set_bit!(self.state.bitboards[rook], rook_source);
+        }
+
+        self.state.side = side;
+        self.state.castling = castling;
+        self.state.en_passant = en_passant;
+        self.state.half_moves = half_moves;
+        self.state.hash = hash;
+        self.state.checkers = checkers;
+        if side == side::BLACK {
+            self.state.full_moves -= 1;
+        }
+        #[cfg(feature = "invariant-checks")]
+        self.assert_invariants("taking back", move_);
+    }
+
+    /// Passes the turn. Only the side to move, the en passant square and the clocks change.
+    fn make_null_move(&mut self) {
+        self.history.push(HistoryItem {
+            move_: 0,
+            captured: 0,
+            side: self.state.side,
+            castling: self.state.castling,
+            en_passant: self.state.en_passant,
+            half_moves: self.state.half_moves,
+            hash: self.state.hash,
+            checkers: self.state.checkers,
+            #[cfg(feature = "crazyhouse")]
+            promoted: self.state.promoted,
+        });
+        if let Some(square) = self.state.en_passant.take() {
+            self.state.hash ^= zobrist::en_passant(square);
+        }
+        self.state.hash ^= zobrist::side();
+        self.state.half_moves = self.state.half_moves.saturating_add(1);
+        if self.state.side == side::BLACK {
+            self.state.full_moves += 1;
+        }
+        self.state.side ^= 1;
+        self.state.checkers = self.find_checkers();
+    }
+
+    fn take_back_null_move(&mut self) {
+        let item = self
+            .history
+            .pop()
+            .expect("Engine history is empty. This should never happen.");
+        self.state.side = item.side;
+        self.state.en_passant = item.en_passant;
+        self.state.half_moves = item.half_moves;
+        self.state.hash = item.hash;
+        self.state.checkers = item.checkers;
+        if item.side == side::BLACK {
+            self.state.full_moves -= 1;
+        }
+    }
+
+    /// Whether `side` has any pieces besides pawns and its king. Without them,
+    /// zugzwang is common and pruning that assumes a move can always improve is unsound.
+    pub fn has_non_pawn_material(&self, side: u8) -> bool {
+        self.state.non_pawn_pieces[side as usize] != 0
+    }
+
+    /// The legal move written `move_` in UCI notation, if there is one. See `find_move`.
+    pub fn parse_move(&mut self, move_: &str) -> Option<u32> {
+        self.find_move(move_)
+            .ok()
+            .filter(|&move_| move_ != moves::NULL_MOVE)
+    }
+
+    /// Finds the legal move written `text` in UCI notation, or tells why there is none.
+    ///
+    /// Castling is accepted both as the king's move, `e1g1`, and as the king taking its
+    /// rook, `e1h1`, whatever the rules write. `0000` is a null move, `moves::NULL_MOVE`,
+    /// as analysis protocols send to pass the turn.
+    pub fn find_move(&mut self, text: &str) -> Result<u32, IllegalMove> {
+        if text == "0000" {
+            return if self.in_check() {
+                Err(IllegalMove::NullMoveInCheck)
+            } else {
+                Ok(moves::NULL_MOVE)
+            };
+        }
+        #[cfg(feature = "crazyhouse")]
+        if let Some((piece, square)) = text.split_once('@') {
+            let piece_type = piece
+                .chars()
+                .next()
+                .and_then(|piece| fen::parse_piece(piece.to_ascii_uppercase()));
+            let (Some(piece_type), Some(target)) = (piece_type, parse_square(square)) else {
+                return Err(IllegalMove::Drop);
+            };
+            return self
+                .generate_legal_moves()
+                .into_iter()
+                .find(|&move_| {
+                    let (_, target_, piece_, _, _) = decode_move!(move_);
+                    moves::is_drop(move_) && target == target_ && piece_type == piece_ % 6
+                })
+                .ok_or(IllegalMove::IllegalDrop);
+        }
+
+        let source = text
+            .get(0..2)
+            .and_then(parse_square)
+            .ok_or(IllegalMove::Notation)?;
+        let target = text
+            .get(2..4)
+            .and_then(parse_square)
+            .ok_or(IllegalMove::Notation)?;
+        let promotion = match text.get(4..).ok_or(IllegalMove::Notation)? {
+            "" => None,
+            promotion => match fen::parse_piece(promotion.chars().next().unwrap()) {
+                Some(piece) if promotion.len() == 1 && (1..5).contains(&(piece % 6)) => {
+                    Some(piece % 6)
+                }
+                _ => return Err(IllegalMove::PromotionPiece),
+            },
+        };
+        if self.get_piece(self.state.side, source).is_none() {
+            return Err(match self.get_piece(self.state.side ^ 1, source) {
+                Some(_) => IllegalMove::WrongSide {
+                    square: source,
+                    to_move: Color::from_side(self.state.side),
+                },
+                None => IllegalMove::NoPiece(source),
+            });
+        }
+
+        // A castle written as the king's move might also be an ordinary king move in
+        // Chess960, which then takes precedence
+        let candidates = self
+            .generate_legal_moves()
+            .into_iter()
+            .filter(|&move_| {
+                let (source_, target_, piece_, _, (_, _, _, castle)) = decode_move!(move_);
+                source == source_
+                    && (target == target_
+                        || castle && target == self.castling_rook_move(piece_ / 6, target_).1)
+            })
+            .collect::<Vec<u32>>();
+        let Some(move_) = candidates.iter().copied().min_by_key(|&move_| {
+            let (_, _, _, _, (_, _, _, castle)) = decode_move!(move_);
+            castle
+        }) else {
+            return Err(self.explain_illegal(source, target));
+        };
+        let (_, _, _, promotion_, _) = decode_move!(move_);
+        if promotion_ == 0 {
+            return match promotion {
+                Some(_) => Err(IllegalMove::NotAPromotion),
+                None => Ok(move_),
+            };
+        }
+        let promotion = promotion.ok_or(IllegalMove::MissingPromotion)?;
+        Ok(candidates
+            .into_iter()
+            .find(|&move_| decode_move!(move_).3 % 6 == promotion)
+            .expect("All four promotions are legal when one is"))
+    }
+
+    /// Why the piece of the side to move on `source` has no legal move to `target`.
+    fn explain_illegal(&mut self, source: u8, target: u8) -> IllegalMove {
+        let side = self.state.side;
+        let king = self.state.bitboards[(piece::types::KING + side * 6) as usize];
+        let own = self.get_occupancy(side::range(side));
+        let is_king = get_bit!(king, source);
+        let own_rook = get_bit!(
+            self.state.bitboards[(piece::types::ROOK + side * 6) as usize],
+            target
+        );
+        if is_king && source / 8 == target / 8 && (source.abs_diff(target) == 2 || own_rook) {
+            return IllegalMove::Castling;
+        }
+        if get_bit!(own, target) {
+            return IllegalMove::OwnPiece(target);
+        }
+
+        let pseudo_legal = self
+            .generate_moves()
+            .into_iter()
+            .filter(|&move_| {
+                let (source_, target_, _, _, _) = decode_move!(move_);
+                (source_, target_) == (source, target)
+            })
+            .collect::<Vec<u32>>();
+        if pseudo_legal.is_empty() {
+            return IllegalMove::Unreachable { source, target };
+        }
+        for move_ in pseudo_legal {
+            if self.make_move(move_) {
+                self.take_back();
+                return IllegalMove::Variant;
+            }
+        }
+        if self.in_check() {
+            return IllegalMove::InCheck;
+        }
+        if is_king || king == 0 {
+            return IllegalMove::LeavesKingInCheck;
+        }
+        // A pinner attacks the king once the piece is gone, en passant aside
+        let king_square = get_lsb!(king) as usize;
+        let occupancy = self.get_occupancy(piece::range::ALL);
+        let exposed = self.attackers_to(king_square, occupancy & !bitboard!(source))
+            & !self.attackers_to(king_square, occupancy)
+            & self.get_occupancy(side::range(side ^ 1));
+        if exposed == 0 {
+            return IllegalMove::LeavesKingInCheck;
+        }
+        IllegalMove::Pinned {
+            square: source,
+            pinner: get_lsb!(exposed) as u8,
+        }
+    }
+
+    /// Formats a move in UCI notation, writing castles the way the rules expect.
+    pub fn format_move(&self, move_: u32) -> String {
+        let (source, target, piece, _, (_, _, _, castle)) = decode_move!(move_);
+        if castle && self.rules.king_captures_rook() {
+            let (_, rook_source, _) = self.castling_rook_move(piece / 6, target);
+            return format!(
+                "{}{}",
+                index_to_algebraic(source as usize),
+                index_to_algebraic(rook_source as usize)
+            );
+        }
+        moves::format(move_)
+    }
+
+    pub fn is_white_to_move(&self) -> bool {
+        self.state.side == side::WHITE
+    }
+
+    pub fn side_to_move(&self) -> Color {
+        Color::from_side(self.state.side)
+    }
+
+    /// The squares a knight passes through on one of its shortest routes between two squares.
+    pub fn knight_path(&self, from: Square, to: Square) -> Vec<Square> {
+        training::knight_path(&self.attack_table, from, to)
+    }
+
+    pub fn memory(&self) -> MemoryUsage {
+        MemoryUsage {
+            attack_table: self.attack_table.memory(),
+            transposition_table: self.tt.memory(),
+            pawn_table: self.pawn_table.memory(),
+            correction_history: self.correction.memory(),
+        }
+    }
+
+    /// The Zobrist hash of the current position.
+    pub fn hash(&self) -> u64 {
+        self.state.hash
+    }
+
+    /// The hash without the en passant file when no pawn can actually capture en passant,
+    /// so a move order ending in a double push matches the same position reached otherwise.
+    pub fn position_key(&self) -> u64 {
+        let Some(square) = self.state.en_passant else {
+            return self.state.hash;
+        };
+        let side = self.state.side;
+        let pawns = self.state.bitboards[(WHITE_PAWN + side * 6) as usize];
+        if self
+            .attack_table
+            .get_pawn_attacks(side ^ 1, square as usize)
+            & pawns
+            != 0
+        {
+            self.state.hash
+        } else {
+            self.state.hash ^ zobrist::en_passant(square)
+        }
+    }
+
+    /// Formats a legal move in Standard Algebraic Notation, e.g. `Nbd7`, `exd5`, `e8=Q+` or `O-O#`.
+    pub fn format_san(&mut self, move_: u32) -> String {
+        let legal_moves = self.generate_legal_moves();
+        let mut san = self.san_without_suffix(move_, &legal_moves);
+        if self.make_move(move_) {
+            if self.in_check() {
+                san.push(if self.generate_legal_moves().is_empty() {
+                    '#'
+                } else {
+                    '+'
+                });
+            }
+            self.take_back();
+        }
+        san
+    }
+
+    /// Formats a line of moves starting from the current position in SAN.
+    pub fn format_san_line(&mut self, moves: &[u32]) -> Vec<String> {
+        let mut line = vec![];
+        for &move_ in moves {
+            line.push(self.format_san(move_));
+            if !self.make_move(move_) {
+                line.pop();
+                break;
+            }
+        }
+        for _ in 0..line.len() {
+            self.take_back();
+        }
+        line
+    }
+
+    /// Finds the legal move written in Standard Algebraic Notation. Check marks and
+    /// annotations are ignored, and castling may be written with zeros.
+    pub fn parse_san(&mut self, san: &str) -> Option<u32> {
+        let normalize = |san: &str| {
+            san.trim_end_matches(['+', '#', '!', '?'])
+                .replace('0', "O")
+                .replace('=', "")
+        };
+        let san = normalize(san);
+        let legal_moves = self.generate_legal_moves();
+        legal_moves
+            .iter()
+            .copied()
+            .find(|&move_| normalize(&self.san_without_suffix(move_, &legal_moves)) == san)
+    }
+
+    fn san_without_suffix(&self, move_: u32, legal_moves: &[u32]) -> String {
+        let (source, target, piece, promotion, (capture, _, _, castle)) = decode_move!(move_);
+        #[cfg(feature = "crazyhouse")]
+        if moves::is_drop(move_) {
+            return format!(
+                "{}@{}",
+                ASCII_PIECES[(piece % 6) as usize],
+                index_to_algebraic(target as usize)
+            );
+        }
+        if castle {
+            // The king always ends up on the g-file or the c-file, even in Chess960
+            return if target % 8 == 6 { "O-O" } else { "O-O-O" }.to_string();
+        }
+
+        let square = index_to_algebraic(target as usize);
+        let capture = if capture { "x" } else { "" };
+        if piece % 6 == piece::types::PAWN {
+            let file = if capture.is_empty() {
+                String::new()
+            } else {
+                index_to_algebraic(source as usize)[..1].to_string()
+            };
+            let promotion = if promotion != 0 {
+                format!("={}", ASCII_PIECES[(promotion % 6) as usize])
+            } else {
+                String::new()
+            };
+            return format!("{}{}{}{}", file, capture, square, promotion);
+        }
+
+        // Name the source file, rank or both when another piece of the same kind can go there
+        let others = legal_moves
+            .iter()
+            .map(|&other| decode_move!(other))
+            .filter(
+                |&(other_source, other_target, other_piece, _, (_, _, _, other_castle))| {
+                    other_piece == piece
+                        && other_target == target
+                        && other_source != source
+                        && !other_castle
+                },
+            )
+            .map(|(other_source, ..)| other_source)
+            .collect::<Vec<u8>>();
+        let source_square = index_to_algebraic(source as usize);
+        let disambiguation = if others.is_empty() {
+            ""
+        } else if others.iter().all(|&other| other % 8 != source % 8) {
+            &source_square[..1]
+        } else if others.iter().all(|&other| other / 8 != source / 8) {
+            &source_square[1..]
+        } else {
+            &source_square
+        };
+        format!(
+            "{}{}{}{}",
+            ASCII_PIECES[(piece % 6) as usize],
+            disambiguation,
+            capture,
+            square
+        )
+    }
+
+    pub fn load_moves(&mut self, moves: Vec<&str>) {
+        self.history.clear();
+        for move_ in moves {
+            match self.find_move(move_) {
+                Ok(moves::NULL_MOVE) => self.make_null_move(),
+                Ok(move_) => {
+                    self.make_move(move_);
+                    self.print();
+                }
+                Err(err) => {
+                    writeln!(self.output, "Invalid move: {} ({})", move_, err);
+                    return;
+                }
+            }
+            writeln!(self.output);
+        }
+    }
+
+    fn get_positional_score(&self, piece: u8, square: u8) -> i8 {
+        let piece_side = piece / 6;
+        let piece_type = piece % 6;
+        let index = if piece_side == side::WHITE {
+            square
+        } else {
+            square ^ 0x38
+        } as usize;
+        let score = match piece_type {
+            piece::types::PAWN => evaluate::PAWN_SCORE[index],
+            piece::types::KNIGHT => evaluate::KNIGHT_SCORE[index],
+            piece::types::BISHOP => evaluate::BISHOP_SCORE[index],
+            piece::types::ROOK => evaluate::ROOK_SCORE[index],
+            piece::types::KING => evaluate::KING_SCORE[index],
+            _ => 0,
+        };
+        if piece_side == side::WHITE {
+            score
+        } else {
+            -score
+        }
+    }
+
+    /// Whether the side to move is in check.
+    pub fn in_check(&self) -> bool {
+        self.state.checkers != 0
+    }
+
+    /// The enemy pieces giving check to the side to move.
+    pub fn checkers(&self) -> u64 {
+        self.state.checkers
+    }
+
+    fn find_checkers(&self) -> u64 {
+        let (king, enemies) = if self.state.side == side::WHITE {
+            (WHITE_KING, piece::range::BLACK)
+        } else {
+            (BLACK_KING, piece::range::WHITE)
+        };
+        // Variants such as Horde have a side without a king
+        let king_bitboard = self.state.bitboards[king as usize];
+        if king_bitboard == 0 {
+            return 0;
+        }
+        let occupancy = self.get_occupancy(piece::range::ALL);
+        self.attackers_to(get_lsb!(king_bitboard) as usize, occupancy) & self.get_occupancy(enemies)
+    }
+
+    /// The hashes of the earlier positions the current one could repeat: those with the same
+    /// side to move since the last capture or pawn move, not counting positions from before
+    /// a null move.
+    fn earlier_hashes(&self) -> impl Iterator<Item = u64> + '_ {
+        self.history
+            .iter()
+            .rev()
+            .take(self.state.half_moves as usize)
+            .take_while(|item| item.move_ != 0)
+            .skip(1)
+            .step_by(2)
+            .map(|item| item.hash)
+    }
+
+    /// Whether the position repeats one since the last capture or pawn move.
+    fn is_repetition(&self) -> bool {
+        self.earlier_hashes().any(|hash| hash == self.state.hash)
+    }
+
+    /// Whether the game is drawn by repetition or the fifty-move rule. A single repetition
+    /// counts, as whatever could be achieved from it could have been achieved the first time.
+    fn is_draw(&mut self) -> bool {
+        if self.is_repetition() {
+            return true;
+        }
+        // Checkmate on the hundredth half move still wins
+        self.state.half_moves >= 100
+            && (!self.in_check() || !self.generate_legal_moves().is_empty())
+    }
+
+    /// Whether the game is over by the rules, unlike the search, which already scores a
+    /// single repetition as a draw.
+    pub fn game_state(&mut self) -> GameState {
+        if let Some(outcome) = self.rules.terminal_outcome(self) {
+            return GameState::VariantEnd(outcome);
+        }
+        if self.generate_legal_moves().is_empty() {
+            return if self.in_check() {
+                GameState::Checkmate(Color::from_side(self.state.side ^ 1))
+            } else {
+                GameState::Stalemate
+            };
+        }
+        if self.state.half_moves >= 100 {
+            GameState::DrawByFiftyMove
+        } else if self
+            .earlier_hashes()
+            .filter(|&hash| hash == self.state.hash)
+            .count()
+            >= 2
+        {
+            GameState::DrawByRepetition
+        } else if self.rules.draws_by_insufficient_material()
+            && self.state.has_insufficient_material()
+        {
+            GameState::DrawByInsufficientMaterial
+        } else {
+            GameState::Ongoing
+        }
+    }
+
+    /// Searches every position of the game's main line to `depth`. The position and
+    /// history are restored afterwards, and nothing is printed.
+    pub fn analyze_game(&mut self, game: &PgnGame, depth: u8) -> Result<GameAnalysis, String> {
+        let output = std::mem::replace(&mut self.output, Output::sink());
+        let state = self.state.clone();
+        let history = std::mem::take(&mut self.history);
+        let analysis = self.analyze_main_line(game, depth);
+        self.state = state;
+        self.history = history;
+        self.output = output;
+        analysis
+    }
+
+    fn analyze_main_line(&mut self, game: &PgnGame, depth: u8) -> Result<GameAnalysis, String> {
+        let fen = game.start_position();
+        self.set_position(fen)
+            .map_err(|err| format!("{}: {}", err, fen))?;
+        let white_score = |engine: &Engine, score: i32| {
+            if engine.state.side == side::WHITE {
+                score
+            } else {
+                -score
+            }
+        };
+        let white_starts = self.state.side == side::WHITE;
+        let mut result = self.search_position(depth);
+        let mut scores = vec![white_score(self, result.score)];
+        let mut plies: Vec<PlyAnalysis> = vec![];
+        for pgn_move in game.moves.iter() {
+            let move_ = self
+                .parse_san(&pgn_move.san)
+                .ok_or_else(|| format!("Illegal move: {}", pgn_move.san))?;
+            let pv = self.format_san_line(&result.pv);
+            let san = self.format_san(move_);
+            self.make_move(move_);
+            // Each side's previous clock is two plies back
+            let clock = pgn_move.clock();
+            let previous_clock = plies
+                .len()
+                .checked_sub(2)
+                .and_then(|index| plies[index].clock);
+            let clock_used = clock
+                .zip(previous_clock)
+                .map(|(clock, previous)| previous.saturating_sub(clock));
+            plies.push(PlyAnalysis {
+                san,
+                pv,
+                clock,
+                clock_used,
+            });
+            result = self.search_position(depth);
+            scores.push(white_score(self, result.score));
+        }
+        Ok(GameAnalysis {
+            depth,
+            white_starts,
+            scores,
+            plies,
+        })
+    }
+
+    /// Plays random moves until the game ends or `max_plies` have been played, then takes
+    /// them all back. Returns the outcome for the side to move, `None` if undecided.
+    pub fn random_playout(
+        &mut self,
+        rng: &mut Rng,
+        max_plies: usize,
+        policy: PlayoutPolicy,
+    ) -> Option<Outcome> {
+        let mut plies = 0;
+        let outcome = loop {
+            if let Some(outcome) = self.rules.terminal_outcome(self) {
+                break Some(outcome);
+            }
+            let moves = self.generate_legal_moves();
+            if moves.is_empty() {
+                break Some(if self.in_check() {
+                    Outcome::Loss
+                } else {
+                    Outcome::Draw
+                });
+            }
+            if self.is_draw() {
+                break Some(Outcome::Draw);
+            }
+            if plies == max_plies {
+                break None;
+            }
+            let captures = moves
+                .iter()
+                .copied()
+                .filter(|&move_| decode_move!(move_).4 .0)
+                .collect::<Vec<u32>>();
+            let move_ = if policy == PlayoutPolicy::CaptureBiased
+                && !captures.is_empty()
+                && rng.below(4) < playout::CAPTURE_BIAS
+            {
+                captures[rng.below(captures.len())]
+            } else {
+                moves[rng.below(moves.len())]
+            };
+            self.make_move(move_);
+            plies += 1;
+        };
+        for _ in 0..plies {
+            self.take_back();
+        }
+        // The outcome was found with the other side to move after an odd number of plies
+        outcome.map(|outcome| match (outcome, plies % 2) {
+            (Outcome::Win, 1) => Outcome::Loss,
+            (Outcome::Loss, 1) => Outcome::Win,
+            (outcome, _) => outcome,
+        })
+    }
+
+    /// The average result of `playouts` random playouts for the side to move, from 0 for
+    /// all losses to 1 for all wins. Undecided playouts count as draws.
+    pub fn playout_score(
+        &mut self,
+        rng: &mut Rng,
+        playouts: usize,
+        max_plies: usize,
+        policy: PlayoutPolicy,
+    ) -> f64 {
+        let total = (0..playouts)
+            .map(|_| match self.random_playout(rng, max_plies, policy) {
+                Some(Outcome::Win) => 1.0,
+                Some(Outcome::Loss) => 0.0,
+                _ => 0.5,
+            })
+            .sum::<f64>();
+        total / playouts.max(1) as f64
+    }
+
+    /// The variant's own end of game at the current ply, with wins and losses as mate scores.
+    fn terminal_score(&self) -> Option<i32> {
+        let ply = self.search_ply as i32;
+        self.rules
+            .terminal_outcome(self)
+            .map(|outcome| match outcome {
+                Outcome::Win => evaluate::MATE_SCORE - ply,
+                Outcome::Loss => -evaluate::MATE_SCORE + ply,
+                Outcome::Draw => 0,
+            })
+    }
+
+    pub fn get_mvv_lva(&self, attacker: u8, victim: u8) -> i32 {
+        let attacker_value = 5 - (attacker as i32 % 6);
+        let victim_value = 1 + (victim as i32 % 6);
+        victim_value * 100 + attacker_value
+    }
+
+    pub fn score_move(&self, move_: u32) -> i32 {
+        let (source, target, source_piece, promotion, (capture, _, _, _)) = decode_move!(move_);
+        if capture {
+            let target_piece = self.get_piece(self.state.side ^ 1, target).unwrap_or(0);
+            // Only a capture by a more valuable piece can lose material. Those that do are
+            // tried after all quiet moves, least bad first
+            let value = |piece: u8| evaluate::MATERIAL_SCORES[(piece % 6) as usize];
+            if self.ordering.see && value(source_piece) > value(target_piece) {
+                let see = self.see(move_);
+                if see < 0 {
+                    return see;
+                }
+            }
+            return self.get_mvv_lva(source_piece, target_piece) + 10_000;
+        }
+        // Queen promotions are tried before killers, underpromotions are left to history
+        if promotion != 0 && promotion % 6 == piece::types::QUEEN {
+            return 9_500;
+        }
+        let ply_index = self.search_ply as usize;
+        if self.ordering.killers {
+            if self.search_state.killer_moves[0][ply_index] == move_ {
+                return 9_000;
+            }
+            if self.search_state.killer_moves[1][ply_index] == move_ {
+                return 8_000;
+            }
+        }
+        // Pushes of passed pawns to the sixth and seventh ranks
+        if self.ordering.passed_pawns && source_piece % 6 == piece::types::PAWN && promotion == 0 {
+            let side = source_piece / 6;
+            let rank = pawns::relative_rank(side, target);
+            let enemy_pawns = self.state.bitboards[(piece::types::PAWN + (side ^ 1) * 6) as usize];
+            if rank >= 5
+                && self
+                    .attack_table
+                    .get_passed_pawn_mask(side, source as usize)
+                    & enemy_pawns
+                    == 0
+            {
+                return 7_000 + rank as i32;
+            }
+        }
+        if self.ordering.conversion
+            && source_piece % 6 == piece::types::PAWN
+            && promotion == 0
+            && self.converting()
+        {
+            return 6_500;
+        }
+        if !self.ordering.history {
+            return search::MAX_HISTORY;
+        }
+        // Shifted so quiet moves with a bad history still come before losing captures
+        self.search_state.history_moves[source_piece as usize][target as usize]
+            + search::MAX_HISTORY
+    }
+
+    /// Whether the side to move is better by `CONVERSION_MARGIN` at the current ply with
+    /// the fifty-move rule drawing near, and should look for progress first.
+    fn converting(&self) -> bool {
+        self.state.half_moves >= evaluate::CONVERSION_HALF_MOVES
+            && self.search_state.stack[self.search_ply as usize]
+                .static_eval
+                .is_some_and(|eval| eval >= search::CONVERSION_MARGIN)
+    }
+
+    /// Applies `bonus`, or a malus if negative, to the history of a quiet move with gravity:
+    /// the entry moves less the closer it already is to `MAX_HISTORY` in that direction.
+    fn update_history(&mut self, move_: u32, bonus: i32) {
+        let (_, target, piece, _, _) = decode_move!(move_);
+        let entry = &mut self.search_state.history_moves[piece as usize][target as usize];
+        let bonus = bonus.clamp(-search::MAX_HISTORY, search::MAX_HISTORY);
+        *entry += bonus - *entry * bonus.abs() / search::MAX_HISTORY;
+    }
+
+    pub fn sort_moves(&self, moves: &[u32]) -> Vec<u32> {
+        let mut moves = moves.to_vec(); // Convert slice to Vec for sorting
+        moves.sort_by_key(|&move_| Reverse(self.score_move(move_)));
+        moves
+    }
+
+    /// The moves the search tries, without underpromotions if it only promotes to queens.
+    fn generate_search_moves(&self) -> Vec<u32> {
+        let mut moves = self.generate_moves();
+        if self.queen_promotions_only {
+            moves.retain(|&move_| !is_underpromotion(move_));
+        }
+        moves
+    }
+
+    fn generate_captures(&self) -> Vec<u32> {
+        self.generate_search_moves()
+            .into_iter()
+            .filter(|&move_| {
+                let (_, _, _, _, (capture, _, _, _)) = decode_move!(move_);
+                capture
+            })
+            .collect()
+    }
+
+    pub fn evaluate(&mut self) -> i32 {
+        let eval = self.evaluate_unfaded();
+        self.fade(eval)
+    }
+
+    /// The eval for the side to move before it fades for the fifty-move rule. This is what
+    /// the transposition table stores, as the hash leaves the halfmove clock out.
+    fn evaluate_unfaded(&mut self) -> i32 {
+        let mut score = 0;
+        self.state
+            .bitboards
+            .iter()
+            .enumerate()
+            .for_each(|(piece, &bitboard)| {
+                let piece = piece as u8;
+                let mut copy = bitboard;
+                while copy != 0 {
+                    let square = get_lsb!(copy);
+                    score += evaluate::MATERIAL_SCORES[piece as usize];
+                    score += self.get_positional_score(piece, square as u8) as i32;
+
+                    clear_lsb!(copy);
+                }
+            });
+
+        score += self.evaluate_pawns();
+
+        #[cfg(feature = "crazyhouse")]
+        self.state
+            .pockets
+            .iter()
+            .enumerate()
+            .for_each(|(side, pocket)| {
+                pocket.iter().enumerate().for_each(|(piece_type, &count)| {
+                    score += evaluate::MATERIAL_SCORES[piece_type + side * 6] * count as i32;
+                });
+            });
+
+        if let Some((strong_side, handler)) = self.endgames.probe(self.state.material_key()) {
+            match handler {
+                Handler::Evaluate(evaluate) => {
+                    let strong_score = evaluate(&self.state, strong_side);
+                    score = if strong_side == side::WHITE {
+                        strong_score
+                    } else {
+                        -strong_score
+                    };
+                }
+                Handler::Scale(scale) => {
+                    score = score * scale(&self.state, strong_side) / endgame::SCALE_NORMAL;
+                }
+            }
+        }
+
+        if self.state.side == side::WHITE {
+            score
+        } else {
+            -score
+        }
+    }
+
+    /// Fades an eval from `evaluate_unfaded` for the current halfmove clock.
+    fn fade(&self, eval: i32) -> i32 {
+        evaluate::to_grain(evaluate::fade_for_fifty_moves(eval, self.state.half_moves))
+    }
+
+    /// Pawn structure terms from White's point of view. The pawn-only part is cached in the
+    /// pawn table, the terms depending on kings and pieces are computed here.
+    fn evaluate_pawns(&mut self) -> i32 {
+        let pawns = [
+            self.state.bitboards[WHITE_PAWN as usize],
+            self.state.bitboards[BLACK_PAWN as usize],
+        ];
+        let attack_table = &self.attack_table;
+        let entry = self
+            .pawn_table
+            .probe(pawns[0], pawns[1], || pawns::evaluate(attack_table, pawns));
+        entry.score
+            + self.evaluate_passed_pawns(entry.passed)
+            + self.evaluate_color_complexes(entry.holes)
+    }
+
+    /// Holes weigh more on a colour complex whose bishop is gone.
+    fn evaluate_color_complexes(&self, holes: [u64; 2]) -> i32 {
+        [side::WHITE, side::BLACK]
+            .iter()
+            .map(|&side| {
+                let bishop = if side == side::WHITE {
+                    WHITE_BISHOP
+                } else {
+                    BLACK_BISHOP
+                };
+                let bishops = self.state.bitboards[bishop as usize];
+                let penalty = [masks::LIGHT_SQUARES, masks::DARK_SQUARES]
+                    .iter()
+                    .filter(|&&color| bishops & color == 0)
+                    .map(|&color| count_bits!(holes[side as usize] & color) as i32)
+                    .sum::<i32>()
+                    * evaluate::COLOR_COMPLEX_PENALTY;
+                if side == side::WHITE {
+                    -penalty
+                } else {
+                    penalty
+                }
+            })
+            .sum()
+    }
+
+    /// King proximity, blockade and path control terms for passed pawns.
+    fn evaluate_passed_pawns(&self, passed: [u64; 2]) -> i32 {
+        let all_pieces = self.get_occupancy(piece::range::ALL);
+        let kings = [WHITE_KING, BLACK_KING].map(|king| self.state.bitboards[king as usize]);
+        let mut score = 0;
+        for side in [side::WHITE, side::BLACK] {
+            let enemy_pieces = self.get_occupancy(side::range(side ^ 1));
+            let mut bitboard = passed[side as usize];
+            let mut side_score = 0;
+            while bitboard != 0 {
+                let square = get_lsb!(bitboard) as u8;
+                clear_lsb!(bitboard);
+                let rank = pawns::relative_rank(side, square);
+                if rank < 3 {
+                    continue;
+                }
+                let weight = rank as i32 - 2;
+                let push = if side == side::WHITE { -8 } else { 8 };
+                let block_square = square.wrapping_add_signed(push);
+
+                let (own_king, enemy_king) = (kings[side as usize], kings[(side ^ 1) as usize]);
+                if enemy_king != 0 {
+                    side_score += distance(get_lsb!(enemy_king) as u8, block_square)
+                        * evaluate::PASSED_PAWN_ENEMY_KING
+                        * weight;
+                }
+                if own_king != 0 {
+                    side_score -= distance(get_lsb!(own_king) as u8, block_square)
+                        * evaluate::PASSED_PAWN_OWN_KING
+                        * weight;
+                }
+
+                if get_bit!(enemy_pieces, block_square) {
+                    // Blockaded passers are worth much less
+                    side_score -= evaluate::PASSED_PAWN_BONUS[rank] / 2;
+                    continue;
+                }
+                let path = self.attack_table.get_forward_file(side, square as usize);
+                if path & all_pieces == 0 {
+                    side_score += evaluate::PASSED_PAWN_FREE_PATH * weight;
+                }
+                let mut squares = path;
+                let mut safe = true;
+                while squares != 0 {
+                    if self.is_square_attacked(get_lsb!(squares) as usize, side) {
+                        safe = false;
+                        break;
+                    }
+                    clear_lsb!(squares);
+                }
+                if safe {
+                    side_score += evaluate::PASSED_PAWN_SAFE_PATH * weight;
+                }
+            }
+            score += if side == side::WHITE {
+                side_score
+            } else {
+                -side_score
+            };
+        }
+        score
+    }
+
+    pub fn quiescence(&mut self, alpha: i32, beta: i32) -> i32 {
+        self.quiescence_to(alpha, beta, self.quiescence_depth)
+    }
+
+    /// Quiescence search that follows at most `depth` more plies of captures. In check it
+    /// searches every evasion instead, without standing pat.
+    fn quiescence_to(&mut self, alpha: i32, beta: i32, depth: u8) -> i32 {
+        self.search_nodes += 1;
+        self.count(|stats| stats.quiescence_nodes += 1);
+        self.sel_depth = self.sel_depth.max(self.search_ply);
+        if self.check_stop() {
+            return 0;
+        }
+        if let Some(score) = self.terminal_score() {
+            return score;
+        }
+        let hash = self.state.hash;
+        let ply = self.search_ply as usize;
+        let entry = self.tt.probe(hash);
+        self.count(|stats| {
+            stats.tt_probes += 1;
+            stats.tt_hits += u64::from(entry.is_some());
+        });
+        let tt_score = entry.map(|entry| tt::score_from_tt(entry.score, ply));
+        let tt_cutoffs = self.state.half_moves < search::TT_CUTOFF_MAX_HALF_MOVES;
+        if let (Some(entry), Some(tt_score), true) = (entry, tt_score, tt_cutoffs) {
+            match entry.flag {
+                tt::flags::EXACT => return tt_score,
+                tt::flags::ALPHA if tt_score <= alpha => return alpha,
+                tt::flags::BETA if tt_score >= beta => return beta,
+                _ => {}
+            }
+        }
+
+        let original_alpha = alpha;
+        let mut alpha = alpha;
+        let in_check = self.in_check();
+        let raw_eval = (!in_check).then(|| {
+            entry
+                .and_then(|entry| entry.eval())
+                .unwrap_or_else(|| self.evaluate_unfaded())
+        });
+        if let Some(raw_eval) = raw_eval {
+            let faded = self.fade(raw_eval);
+            let static_eval = self.corrected_eval(faded);
+            // A stored bound on the search score is a better guess than the static eval
+            // whenever it points the same way
+            let score = match (entry, tt_score) {
+                (Some(entry), Some(tt_score)) if !evaluate::is_mate_score(tt_score) => {
+                    match entry.flag {
+                        tt::flags::BETA if tt_score > static_eval => tt_score,
+                        tt::flags::ALPHA if tt_score < static_eval => tt_score,
+                        _ => static_eval,
+                    }
+                }
+                _ => static_eval,
+            };
+            if score >= beta {
+                return beta; // Beta cutoff
+            }
+
+            if score > alpha {
+                alpha = score;
+            }
+            if depth == 0 || ply >= search::MAX_PLY - 1 {
+                return alpha;
+            }
+        } else if ply >= search::MAX_PLY - 1 {
+            return self.evaluate();
+        }
+
+        let moves = if in_check {
+            self.generate_search_moves()
+        } else {
+            self.generate_captures()
+        };
+        let mut legal_moves = 0;
+        for &move_ in self.sort_moves(&moves).iter() {
+            if !self.make_move(move_) {
+                continue;
+            }
+            legal_moves += 1;
+
+            self.search_ply += 1;
+
+            // Evasions use up depth like captures, but a check at depth 0 is still answered,
+            // hence the saturating subtraction
+            let score = -self.quiescence_to(-beta, -alpha, depth.saturating_sub(1));
+            self.take_back();
+            self.search_ply -= 1;
+
+            if self.stopped {
+                return 0;
+            }
+
+            if score >= beta {
+                self.tt.store(
+                    hash,
+                    0,
+                    tt::flags::BETA,
+                    tt::score_to_tt(beta, ply),
+                    raw_eval,
+                    move_,
+                );
+                return beta; // Beta cutoff
+            }
+
+            if score > alpha {
+                alpha = score;
+            }
+        }
+        if in_check && legal_moves == 0 {
+            return -evaluate::MATE_SCORE + ply as i32; // Checkmate
+        }
+
+        let flag = if alpha > original_alpha {
+            tt::flags::EXACT
+        } else {
+            tt::flags::ALPHA
+        };
+        self.tt
+            .store(hash, 0, flag, tt::score_to_tt(alpha, ply), raw_eval, 0);
+        alpha
+    }
+
+    fn pawns(&self) -> [u64; 2] {
+        [
+            self.state.bitboards[WHITE_PAWN as usize],
+            self.state.bitboards[BLACK_PAWN as usize],
+        ]
+    }
+
+    fn corrected_eval(&mut self, eval: i32) -> i32 {
+        let pawns = self.pawns();
+        evaluate::to_grain(self.correction.correct(self.state.side, pawns, eval))
+    }
+
+    /// Teaches the correction history how far the static eval was from the search score,
+    /// when a quiet move or none decided the node and the bound says which way it erred.
+    fn update_correction(
+        &mut self,
+        depth: u8,
+        static_eval: Option<i32>,
+        score: i32,
+        flag: u8,
+        best_move: u32,
+    ) {
+        let Some(eval) = static_eval else {
+            return;
+        };
+        let (_, _, _, _, (capture, _, _, _)) = decode_move!(best_move);
+        if capture
+            || score.abs() >= evaluate::MATE_SCORE - 64
+            || (flag == tt::flags::BETA && score <= eval)
+            || (flag == tt::flags::ALPHA && score >= eval)
+        {
+            return;
+        }
+        let pawns = self.pawns();
+        self.correction
+            .update(self.state.side, pawns, depth, score - eval);
+    }
+
+    /// Whether the static eval is better than it was on our previous move, which makes
+    /// pruning margins safe to tighten.
+    fn is_improving(&self, ply: usize) -> bool {
+        let Some(eval) = self.search_state.stack[ply].static_eval else {
+            return false;
+        };
+        [2, 4]
+            .iter()
+            .filter(|&&back| ply >= back)
+            .find_map(|&back| self.search_state.stack[ply - back].static_eval)
+            .is_none_or(|previous| eval > previous)
+    }
+
+    /// Decides how many plies to extend the node at `ply` by and records it in the stack.
+    /// Every extension goes through here so the line's total stays within `MAX_EXTENSIONS`
+    /// and its depth within `MAX_PLY`: one ply when in check, or after a pawn push to the
+    /// seventh rank.
+    fn extend(&mut self, ply: usize, depth: u8, in_check: bool) -> u8 {
+        let previous = if ply > 0 {
+            self.search_state.stack[ply - 1].extensions
+        } else {
+            0
+        };
+        let pawn_push = self.history.last().is_some_and(|item| {
+            let (source, target, piece, _, _) = decode_move!(item.move_);
+            item.move_ != 0
+                && source != target
+                && ((piece == WHITE_PAWN && target >> 3 == 1)
+                    || (piece == BLACK_PAWN && target >> 3 == 6))
+        });
+        let room = ply + (depth as usize) < search::MAX_PLY - 1;
+        let extension =
+            u8::from((in_check || pawn_push) && previous < search::MAX_EXTENSIONS && room);
+        self.search_state.stack[ply].extensions = previous + extension;
+        extension
+    }
+
+    pub fn negamax(&mut self, depth: u8, mut alpha: i32, beta: i32) -> i32 {
+        let mut depth = depth;
+        let ply_index = self.search_ply as usize;
+        self.search_state.pv_length[ply_index] = ply_index as u32;
+        if let Some(score) = self.terminal_score() {
+            return score;
+        }
+        if ply_index > 0 && self.is_draw() {
+            return 0;
+        }
+        if ply_index >= search::MAX_PLY - 1 {
+            return self.evaluate();
+        }
+        if depth == 0 {
+            return self.quiescence(alpha, beta);
+        }
+
+        let in_check = self.in_check();
+        depth += self.extend(ply_index, depth, in_check);
+
+        self.search_nodes += 1;
+        self.sel_depth = self.sel_depth.max(self.search_ply);
+        if self.check_stop() {
+            return 0;
+        }
+
+        // Only trust stored bounds outside the principal variation, so the PV stays complete
+        let pv_node = beta - alpha > 1;
+        let hash = self.state.hash;
+        let mut tt_move = 0;
+        let mut tt_eval = None;
+        let entry = self.tt.probe(hash);
+        self.count(|stats| {
+            stats.tt_probes += 1;
+            stats.tt_hits += u64::from(entry.is_some());
+        });
+        if let Some(entry) = entry {
+            tt_move = entry.best_move;
+            tt_eval = entry.eval();
+            let tt_score = tt::score_from_tt(entry.score, ply_index);
+            if !pv_node
+                && entry.depth >= depth
+                && self.state.half_moves < search::TT_CUTOFF_MAX_HALF_MOVES
+            {
+                match entry.flag {
+                    tt::flags::EXACT => return tt_score,
+                    tt::flags::ALPHA if tt_score <= alpha => return alpha,
+                    tt::flags::BETA if tt_score >= beta => return beta,
+                    _ => {}
+                }
+            }
+        }
+
+        let raw_eval = (!in_check).then(|| tt_eval.unwrap_or_else(|| self.evaluate_unfaded()));
+        let static_eval = raw_eval.map(|eval| {
+            let faded = self.fade(eval);
+            self.corrected_eval(faded)
+        });
+        self.search_state.stack[ply_index].static_eval = static_eval;
+        let improving = self.is_improving(ply_index);
+
+        // Reverse futility pruning: far enough above beta that a shallow search won't drop below it
+        if let Some(eval) = static_eval {
+            let margin = evaluate::FUTILITY_MARGIN * depth as i32
+                - if improving {
+                    evaluate::FUTILITY_MARGIN / 2
+                } else {
+                    0
+                };
+            if depth <= 3
+                && !pv_node
+                && beta.abs() < evaluate::MATE_SCORE - 64
+                && self.has_non_pawn_material(self.state.side)
+                && eval - margin >= beta
+            {
+                return eval;
+            }
+        }
+
+        // Null move pruning: if passing still fails high, a real move almost certainly would too
+        if let Some(eval) = static_eval {
+            let previous_null = self.history.last().is_some_and(|item| item.move_ == 0);
+            if depth >= search::NULL_MOVE_MIN_DEPTH
+                && eval >= beta
+                && beta.abs() < evaluate::MATE_SCORE - 64
+                && ply_index >= self.null_move_min_ply
+                && !previous_null
+                && self.has_non_pawn_material(self.state.side)
+            {
+                let reduced_depth = depth - 1 - search::NULL_MOVE_REDUCTION;
+                self.count(|stats| stats.null_move_tries += 1);
+                self.make_null_move();
+                self.search_ply += 1;
+                let score = -self.negamax(reduced_depth, -beta, -beta + 1);
+                self.take_back_null_move();
+                self.search_ply -= 1;
+
+                if self.stopped {
+                    return 0;
+                }
+                if score >= beta {
+                    match self.null_move_verification_depth {
+                        // Guard against zugzwang by searching the same node without null
+                        // moves for the side to move for most of the reduced depth
+                        Some(verification_depth) if depth >= verification_depth => {
+                            let min_ply = self.null_move_min_ply;
+                            self.null_move_min_ply = ply_index + 3 * reduced_depth as usize / 4;
+                            let verified = self.negamax(reduced_depth, beta - 1, beta);
+                            self.null_move_min_ply = min_ply;
+                            if self.stopped {
+                                return 0;
+                            }
+                            if verified >= beta {
+                                self.count(|stats| stats.null_move_cutoffs += 1);
+                                return beta;
+                            }
+                        }
+                        _ => {
+                            self.count(|stats| stats.null_move_cutoffs += 1);
+                            return beta;
+                        }
+                    }
+                }
+            }
+        }
+
+        // Futility pruning: near the horizon, quiet moves cannot lift a hopeless eval to alpha
+        let futile = !pv_node
+            && depth <= 3
+            && alpha.abs() < evaluate::MATE_SCORE - 64
+            && static_eval.is_some_and(|eval| {
+                eval + evaluate::FRONTIER_FUTILITY_MARGINS[depth as usize] <= alpha
+            });
+
+        let original_alpha = alpha;
+        let mut best_move = 0;
+        let mut legal_moves = 0;
+        // Only the first quiets get a malus, which saves allocating per node
+        let mut quiets_searched = [0; 32];
+        let mut quiet_count = 0;
+
+        let mut moves = self.sort_moves(&self.generate_search_moves());
+        if let Some(index) = moves.iter().position(|&move_| move_ == tt_move) {
+            moves[..=index].rotate_right(1);
+        }
+
+        for &move_ in moves.iter() {
+            if !self.make_move(move_) {
+                continue;
+            }
+
+            legal_moves += 1;
+
+            // Always search one move, and never skip captures, promotions or checks
+            let (_, _, _, promotion, (capture, _, _, _)) = decode_move!(move_);
+            if futile && legal_moves > 1 && !capture && promotion == 0 && !self.in_check() {
+                self.take_back();
+                continue;
+            }
+
+            self.search_ply += 1;
+
+            let score = -self.negamax(depth - 1, -beta, -alpha);
+            self.take_back();
+            self.search_ply -= 1;
+
+            if self.stopped {
+                return 0;
+            }
+
+            if score >= beta {
+                let index = (legal_moves as usize - 1).min(search::CUTOFF_INDICES - 1);
+                self.count(|stats| stats.cutoffs[index] += 1);
+                if !capture {
+                    self.search_state.killer_moves[1][ply_index] =
+                        self.search_state.killer_moves[0][ply_index];
+                    self.search_state.killer_moves[0][ply_index] = move_;
+                    // The quiets tried before the cutoff move were worse, so they lose what it gains
+                    let bonus = depth as i32 * depth as i32;
+                    self.update_history(move_, bonus);
+                    for &quiet in quiets_searched[..quiet_count].iter() {
+                        self.update_history(quiet, -bonus);
+                    }
+                }
+                self.tt.store(
+                    hash,
+                    depth,
+                    tt::flags::BETA,
+                    tt::score_to_tt(beta, ply_index),
+                    raw_eval,
+                    move_,
+                );
+                self.update_correction(depth, static_eval, beta, tt::flags::BETA, move_);
+                return beta; // Beta cutoff
+            }
+
+            if !capture && quiet_count < quiets_searched.len() {
+                quiets_searched[quiet_count] = move_;
+                quiet_count += 1;
+            }
+
+            if score > alpha {
+                alpha = score;
+                best_move = move_;
+                self.search_state.pv_table[ply_index][ply_index] = move_;
+                for next_ply in (ply_index + 1)..self.search_state.pv_length[ply_index + 1] as usize
+                {
+                    self.search_state.pv_table[ply_index][next_ply] =
+                        self.search_state.pv_table[ply_index + 1][next_ply];
+                }
+                self.search_state.pv_length[ply_index] = self.search_state.pv_length[ply_index + 1];
+            }
+        }
+
+        // Handle checkmate and stalemate
+        if legal_moves == 0 {
+            if in_check {
+                return -evaluate::MATE_SCORE + self.search_ply as i32; // Checkmate
+            } else {
+                return 0; // Stalemate
+            }
+        }
+
+        let flag = if alpha > original_alpha {
+            tt::flags::EXACT
+        } else {
+            tt::flags::ALPHA
+        };
+        self.tt.store(
+            hash,
+            depth,
+            flag,
+            tt::score_to_tt(alpha, ply_index),
+            raw_eval,
+            best_move,
+        );
+        self.update_correction(depth, static_eval, alpha, flag, best_move);
+        alpha
+    }
+
+    /// Generates the moves that do not leave the king in check.
+    pub fn generate_legal_moves(&mut self) -> Vec<u32> {
+        self.generate_moves()
+            .into_iter()
+            .filter(|&move_| {
+                let legal = self.make_move(move_);
+                if legal {
+                    self.take_back();
+                }
+                legal
+            })
+            .collect()
+    }
+
+    /// Searches the root moves from `first` on, the ones before it being earlier MultiPV lines.
+    fn search_root(&mut self, depth: u8, root_moves: &mut RootMoves, first: usize) -> i32 {
+        let mut alpha = -evaluate::MAX_SCORE;
+        let beta = evaluate::MAX_SCORE;
+        self.search_state.pv_length[0] = 0;
+        self.search_state.stack[0].static_eval = (!self.in_check()).then(|| self.evaluate());
+        self.search_state.stack[0].extensions = 0;
+
+        for (index, root_move) in root_moves.as_mut_slice()[first..].iter_mut().enumerate() {
+            let nodes = self.search_nodes;
+            root_move.previous_score = root_move.score;
+            if depth >= search::CURRMOVE_MIN_DEPTH {
+                let info = InfoMessage {
+                    depth: Some(depth as u32),
+                    currmove: Some(self.format_move(root_move.move_)),
+                    currmovenumber: Some((first + index + 1) as u32),
+                    time: Some(self.time.search_elapsed().as_millis() as u64),
+                    ..InfoMessage::default()
+                };
+                writeln!(self.output, "{}", info::format(&info));
+            }
+
+            self.make_move(root_move.move_);
+            self.search_ply += 1;
+            let score = -self.negamax(depth - 1, -beta, -alpha);
+            self.take_back();
+            self.search_ply -= 1;
+
+            root_move.iteration_nodes = self.search_nodes - nodes;
+            root_move.nodes += root_move.iteration_nodes;
+
+            if self.stopped {
+                break;
+            }
+
+            if score > alpha {
+                alpha = score;
+                root_move.score = score;
+                root_move.pv = std::iter::once(root_move.move_)
+                    .chain(
+                        (1..self.search_state.pv_length[1] as usize)
+                            .map(|ply| self.search_state.pv_table[1][ply]),
+                    )
+                    .collect();
+                self.search_state.pv_table[0][0] = root_move.move_;
+                for next_ply in 1..self.search_state.pv_length[1] as usize {
+                    self.search_state.pv_table[0][next_ply] =
+                        self.search_state.pv_table[1][next_ply];
+                }
+                self.search_state.pv_length[0] = self.search_state.pv_length[1].max(1);
+            } else {
+                root_move.score = -evaluate::MAX_SCORE;
+            }
+        }
+
+        alpha
+    }
+
+    pub fn search_position(&mut self, depth: u8) -> SearchResult {
+        self.search_ply = 0;
+        self.search_nodes = 0;
+        self.search_state.new_search();
+        self.tt.new_search();
+        self.null_move_min_ply = 0;
+        self.stopped = false;
+        self.stop_received = false;
+        self.time.start();
+        let start = Instant::now();
+        if self.stats.is_some() {
+            self.set_collect_stats(true);
+        }
+
+        let legal_moves = self.root_legal_moves();
+        let mut root_moves = match self.resumed.take() {
+            Some((key, root_moves)) if key == self.state.hash => root_moves,
+            _ => RootMoves::new(&self.sort_moves(&legal_moves)),
+        };
+        let mut result = SearchResult::default();
+        let mut last_checkpoint = (start, 0);
+
+        if let Some(score) = self.terminal_score() {
+            result.score = score;
+            self.print_terminal_info(result.score);
+            self.print_bestmove(&[]);
+            self.finish_search();
+            return result;
+        }
+
+        if root_moves.is_empty() {
+            result.score = if self.in_check() {
+                -evaluate::MATE_SCORE
+            } else {
+                0
+            };
+            self.print_terminal_info(result.score);
+            self.print_bestmove(&[]);
+            self.finish_search();
+            return result;
+        }
+
+        let helper_stop = Arc::new(AtomicBool::new(false));
+        let helper_nodes = Arc::new(AtomicU64::new(0));
+        let helpers = (1..self.threads)
+            .map(|index| {
+                let mut helper = self.helper(Arc::clone(&helper_stop), Arc::clone(&helper_nodes));
+                // Half of the helpers search a ply deeper than the others, so they do not all
+                // search the same tree in step
+                thread::spawn(move || helper.search_helper(depth, (index % 2) as u8))
+            })
+            .collect::<Vec<JoinHandle<()>>>();
+        self.progress = Some((Instant::now(), Arc::clone(&helper_nodes)));
+
+        let mut stats = IterationStats::default();
+        // Completed iterations in a row with the same best move, not counting the first
+        let mut stable_iterations = 0;
+        for current_depth in 1..=depth.max(1) {
+            if self.is_cancelled() {
+                break;
+            }
+            self.root_depth = current_depth;
+            self.sel_depth = 0;
+            let mut iteration = root_moves.clone();
+            let iteration_start = Instant::now();
+            let nodes_before = self.search_nodes;
+            let lines = self.multi_pv.min(iteration.len());
+            let mut score = 0;
+            for line in 0..lines {
+                let line_score = self.search_root(current_depth, &mut iteration, line);
+                if self.stopped {
+                    break;
+                }
+                // The best of the moves not chosen yet becomes this line
+                iteration.sort_from(line);
+                if line == 0 {
+                    score = line_score;
+                }
+            }
+
+            // An aborted iteration is discarded so only completed results are reported, unless
+            // it was the first: a stopped search still has to play a move, so it takes the best
+            // of the moves searched so far, or the first one. Cancelled searches need no move.
+            if self.stopped {
+                if result.best_move == 0 && !self.is_cancelled() {
+                    let best = iteration.best().expect("Root moves are not empty");
+                    result = SearchResult {
+                        best_move: best.move_,
+                        score: if best.score == -evaluate::MAX_SCORE {
+                            self.evaluate()
+                        } else {
+                            best.score
+                        },
+                        depth: 0,
+                        seldepth: self.sel_depth,
+                        nodes: self.search_nodes + helper_nodes.load(Ordering::Relaxed),
+                        pv: vec![best.move_],
+                        root_moves: iteration,
+                    };
+                }
+                break;
+            }
+
+            root_moves = iteration;
+            let best = root_moves.best().expect("Root moves are not empty");
+            if result.depth > 0 {
+                stable_iterations = if best.move_ == result.best_move {
+                    stable_iterations + 1
+                } else {
+                    0
+                };
+                self.time
+                    .scale_budget(search::time_scale(stable_iterations, score - result.score));
+            }
+            let nodes = self.search_nodes + helper_nodes.load(Ordering::Relaxed);
+            result = SearchResult {
+                best_move: best.move_,
+                score,
+                depth: current_depth,
+                seldepth: self.sel_depth,
+                nodes,
+                pv: best.pv.clone(),
+                root_moves: root_moves.clone(),
+            };
+
+            let elapsed = start.elapsed();
+            for (line, root_move) in root_moves.iter().take(lines).enumerate() {
+                let info = InfoMessage {
+                    depth: Some(current_depth as u32),
+                    seldepth: Some(self.sel_depth as u32),
+                    multipv: (self.multi_pv > 1).then_some(line as u32 + 1),
+                    score: Some(info::Score::from_search(if line == 0 {
+                        score
+                    } else {
+                        root_move.score
+                    })),
+                    nodes: Some(nodes),
+                    nps: Some((nodes as f64 / elapsed.as_secs_f64().max(1e-9)) as u64),
+                    time: Some(elapsed.as_millis() as u64),
+                    pv: root_move
+                        .pv
+                        .iter()
+                        .map(|&move_| self.format_move(move_))
+                        .collect(),
+                    ..InfoMessage::default()
+                };
+                writeln!(self.output, "{}", info::format(&info));
+            }
+            self.print_root_nodes(&root_moves);
+            if let Some((last, _)) = &mut self.progress {
+                *last = Instant::now();
+            }
+            stats.push(self.search_nodes - nodes_before, iteration_start.elapsed());
+
+            if self
+                .checkpointing
+                .as_ref()
+                .is_some_and(|checkpointing| last_checkpoint.0.elapsed() >= checkpointing.interval)
+            {
+                self.save_checkpoint(&result);
+                last_checkpoint = (Instant::now(), current_depth);
+            }
+
+            let predicted = stats.predict_next();
+            if self.debug {
+                if let (Some(branching_factor), Some(predicted)) =
+                    (stats.branching_factor(), predicted)
+                {
+                    writeln!(
+                        self.output,
+                        "info string ebf {:.2} next iteration {} ms remaining {}",
+                        branching_factor,
+                        predicted.as_millis(),
+                        self.time
+                            .remaining()
+                            .map_or("unlimited".to_string(), |remaining| {
+                                format!("{} ms", remaining.as_millis())
+                            })
+                    );
+                }
+            }
+
+            // Don't start an iteration that would only be aborted and thrown away
+            if self.time.is_soft_time_up()
+                || (current_depth < depth && predicted.is_some_and(|time| !self.time.fits(time)))
+            {
+                break;
+            }
+        }
+
+        helper_stop.store(true, Ordering::Relaxed);
+        for helper in helpers {
+            helper.join().expect("A search thread panicked");
+        }
+        // Counting the nodes of an aborted iteration too
+        result.nodes = self.search_nodes + helper_nodes.load(Ordering::Relaxed);
+        let nodes = self.search_nodes;
+        self.count(|search_stats| {
+            search_stats.nodes = nodes;
+            search_stats.iterations = stats;
+        });
+
+        if result.depth > last_checkpoint.1 {
+            self.save_checkpoint(&result);
+        }
+
+        self.print_bestmove(&result.pv);
+        self.finish_search();
+        result
+    }
+
+    /// Ponders on the opponent's `replies` most likely moves rather than a single predicted
+    /// one. A MultiPV search picks them, then each gets a search restricted to it, with an
+    /// equal share of `time`, which leaves its continuation in the transposition table for
+    /// when it is played. Each reply is reported as it is done, and a stop ends the
+    /// analysis early, leaving out the replies not searched yet.
+    pub fn analyse_replies(&mut self, replies: usize, time: Duration) -> Vec<ReplyAnalysis> {
+        let replies = replies.max(1);
+        let share = time / (replies as u32 + 1);
+        let output = std::mem::replace(&mut self.output, Output::sink());
+        let multi_pv = std::mem::replace(&mut self.multi_pv, replies);
+        self.time = TimeManager::new(Some(share));
+        let candidates = self.search_position(MAX_PLY as u8 - 1);
+        self.multi_pv = multi_pv;
+
+        let mut analyses = vec![];
+        for root_move in candidates.root_moves.iter().take(replies) {
+            if self.stop_received {
+                break;
+            }
+            self.search_moves = vec![root_move.move_];
+            self.time = TimeManager::new(Some(share));
+            let result = self.search_position(MAX_PLY as u8 - 1);
+            writeln!(
+                output,
+                "info string reply {} depth {} score {} pv {}",
+                self.format_move(root_move.move_),
+                result.depth,
+                info::Score::from_search(result.score),
+                result
+                    .pv
+                    .iter()
+                    .map(|&move_| self.format_move(move_))
+                    .collect::<Vec<String>>()
+                    .join(" ")
+            );
+            analyses.push(ReplyAnalysis {
+                reply: root_move.move_,
+                result,
+            });
+        }
+        self.output = output;
+        analyses
+    }
+
+    /// An engine for a helper thread of a parallel search of the current position. It
+    /// shares the tables that are read-only or safe to share, while the per-thread search
+    /// state starts as a copy of this engine's.
+    fn helper(&self, stop: Arc<AtomicBool>, nodes: Arc<AtomicU64>) -> Engine {
+        Engine {
+            attack_table: Arc::clone(&self.attack_table),
+            state: self.state.clone(),
+            history: self.history.clone(),
+            search_ply: 0,
+            search_nodes: 0,
+            search_state: self.search_state.clone(),
+            root_depth: 0,
+            sel_depth: 0,
+            null_move_verification_depth: self.null_move_verification_depth,
+            null_move_min_ply: 0,
+            cancellation: None,
+            stop,
+            stopped: false,
+            stop_received: false,
+            ponderhit: Arc::new(AtomicBool::new(false)),
+            time: TimeManager::default(),
+            node_limit: None,
+            search_moves: self.search_moves.clone(),
+            excluded_moves: self.excluded_moves.clone(),
+            infinite: false,
+            rules: Arc::clone(&self.rules),
+            endgames: self.endgames.clone(),
+            pawn_table: self.pawn_table.clone(),
+            correction: self.correction.clone(),
+            tt: Arc::clone(&self.tt),
+            threads: 1,
+            helper_nodes: Some(nodes),
+            progress: None,
+            stats: None,
+            quiescence_depth: self.quiescence_depth,
+            ordering: self.ordering,
+            queen_promotions_only: self.queen_promotions_only,
+            multi_pv: 1,
+            checkpointing: None,
+            resumed: None,
+            output: Output::sink(),
+            debug: false,
+        }
+    }
+
+    /// The iterative deepening loop of a helper thread, `offset` plies deeper than the
+    /// main thread's, until `depth` or a stop. Its results reach the main thread through
+    /// the transposition table alone.
+    fn search_helper(&mut self, depth: u8, offset: u8) {
+        let legal_moves = self.root_legal_moves();
+        let mut root_moves = RootMoves::new(&self.sort_moves(&legal_moves));
+        for current_depth in (1 + offset)..=depth {
+            self.root_depth = current_depth;
+            self.sel_depth = 0;
+            let mut iteration = root_moves.clone();
+            self.search_root(current_depth, &mut iteration, 0);
+            if self.stopped {
+                break;
+            }
+            iteration.sort_from(0);
+            root_moves = iteration;
+        }
+    }
+
+    /// Reports the first move of `pv`, `(none)` if it is empty, along with the reply to
+    /// ponder on if there is one. An infinite search waits for a stop first, a ponder
+    /// search for a stop or `ponderhit`.
+    fn print_bestmove(&self, pv: &[u32]) {
+        while (self.infinite || self.time.is_pondering() && !self.ponderhit.load(Ordering::Relaxed))
+            && !self.stop.load(Ordering::Relaxed)
+            && !self.is_cancelled()
+        {
+            thread::sleep(Duration::from_millis(1));
+        }
+        match pv {
+            [] => writeln!(self.output, "bestmove (none)"),
+            [move_] => writeln!(self.output, "bestmove {}", self.format_move(*move_)),
+            [move_, reply, ..] => writeln!(
+                self.output,
+                "bestmove {} ponder {}",
+                self.format_move(*move_),
+                self.format_move(*reply)
+            ),
+        }
+    }
+
+    fn print_terminal_info(&self, score: i32) {
+        let info = InfoMessage {
+            depth: Some(0),
+            score: Some(info::Score::from_search(score)),
+            ..InfoMessage::default()
+        };
+        writeln!(self.output, "{}", info::format(&info));
+    }
+
+    /// Prints the killer moves by ply and, for each piece that has any, a board of its
+    /// history scores by target square, as the last search left them, to show why quiet
+    /// moves are ordered the way they are.
+    pub fn print_ordering_tables(&self) {
+        let [first, second] = &self.search_state.killer_moves;
+        writeln!(self.output, "Killer moves:");
+        let killers = (0..MAX_PLY)
+            .filter(|&ply| first[ply] != 0)
+            .collect::<Vec<usize>>();
+        for &ply in killers.iter() {
+            let second = match second[ply] {
+                0 => "-".to_string(),
+                move_ => self.format_move(move_),
+            };
+            writeln!(
+                self.output,
+                "{:>5} │ {:<6} │ {}",
+                ply,
+                self.format_move(first[ply]),
+                second
+            );
+        }
+        if killers.is_empty() {
+            writeln!(self.output, "  none");
+        }
+
+        writeln!(
+            self.output,
+            "History scores, between -{0} and {0}:",
+            search::MAX_HISTORY
+        );
+        for (piece, scores) in self.search_state.history_moves.iter().enumerate() {
+            if scores.iter().all(|&score| score == 0) {
+                continue;
+            }
+            writeln!(self.output, "{}", ASCII_PIECES[piece]);
+            for rank in 0..8 {
+                write!(self.output, "{} ", 8 - rank);
+                for &score in scores[rank * 8..rank * 8 + 8].iter() {
+                    if score == 0 {
+                        write!(self.output, "{:>6}", "•");
+                    } else {
+                        write!(self.output, "{:>6}", score);
+                    }
+                }
+                writeln!(self.output);
+            }
+            writeln!(
+                self.output,
+                "  {}",
+                "abcdefgh"
+                    .chars()
+                    .map(|file| format!("{:>6}", file))
+                    .collect::<String>()
+            );
+        }
+    }
+
+    /// Reports the nodes spent on each root move so far, most searched first.
+    fn print_root_nodes(&self, root_moves: &RootMoves) {
+        let mut moves = root_moves.iter().collect::<Vec<&RootMove>>();
+        moves.sort_by_key(|root_move| Reverse(root_move.nodes));
+        let info = InfoMessage {
+            string: Some(format!(
+                "nodes per move {}",
+                moves
+                    .iter()
+                    .map(|root_move| format!(
+                        "{}:{}",
+                        self.format_move(root_move.move_),
+                        root_move.nodes
+                    ))
+                    .collect::<Vec<String>>()
+                    .join(" ")
+            )),
+            ..InfoMessage::default()
+        };
+        writeln!(self.output, "{}", info::format(&info));
+    }
+
+    fn finish_search(&mut self) {
+        self.root_depth = 0;
+        self.progress = None;
+        self.time = TimeManager::default();
+        self.node_limit = None;
+        self.search_moves.clear();
+        self.excluded_moves.clear();
+        self.infinite = false;
+        self.stop.store(false, Ordering::Relaxed);
+        self.ponderhit.store(false, Ordering::Relaxed);
+    }
+
+    pub fn perft_driver(&mut self, depth: u8) -> u64 {
+        let mut nodes = 0;
+        if depth == 0 {
+            return 1;
+        }
+        for &move_ in self.generate_moves().iter() {
+            if self.make_move(move_) {
+                nodes += self.perft_driver(depth - 1);
+                self.take_back();
+            }
+        }
+        nodes
+    }
+
+    /// Runs calibration searches to recommend Threads and Hash settings for `time_control`
+    /// on this machine: first with each count of `hardware::thread_counts`, then with the
+    /// fastest on tables of the `hardware::HASH_CURVE` sizes. Prints what it measured.
+    /// The position, history, tables and thread count are restored afterwards.
+    pub fn tune_hardware(&mut self, time_control: &TimeControl) -> HardwareTuning {
+        let move_time = Clock::new(time_control.clone()).allotment();
+        let cores = thread::available_parallelism().map_or(1, |cores| cores.get());
+        let position = fen::parse(SPEEDTEST_POSITIONS[2]).expect("Invalid speedtest position");
+        let state = std::mem::replace(&mut self.state, position);
+        let history = std::mem::take(&mut self.history);
+        let tt = std::mem::replace(&mut self.tt, Arc::new(TranspositionTable::new(16)));
+        let correction = std::mem::replace(&mut self.correction, CorrectionHistory::new(1 << 14));
+        let output = std::mem::replace(&mut self.output, Output::sink());
+        let threads = self.threads;
+
+        // A deadline rather than a time limit, which could stop short of it
+        let calibrate = |engine: &mut Engine| {
+            engine.new_game();
+            let token = CancellationToken::with_timeout(hardware::CALIBRATION_TIME);
+            let start = Instant::now();
+            let nodes = engine.search(MAX_PLY as u8 - 1, &token).nodes;
+            (nodes as f64 / start.elapsed().as_secs_f64().max(1e-9)) as u64
+        };
+        let nps = hardware::thread_counts(cores)
+            .into_iter()
+            .map(|count| {
+                self.set_threads(count);
+                (count, calibrate(self))
+            })
+            .collect::<Vec<(usize, u64)>>();
+        let recommended_threads = hardware::recommend_threads(&nps);
+        self.set_threads(recommended_threads);
+        let hashfull = hardware::HASH_CURVE
+            .iter()
+            .map(|&megabytes| {
+                self.set_hash_size(megabytes);
+                calibrate(self);
+                (megabytes, self.hashfull())
+            })
+            .collect::<Vec<(usize, u32)>>();
+        let fastest = nps
+            .iter()
+            .find(|&&(count, _)| count == recommended_threads)
+            .map_or(0, |&(_, nps)| nps);
+
+        self.state = state;
+        self.history = history;
+        self.tt = tt;
+        self.correction = correction;
+        self.output = output;
+        self.threads = threads;
+
+        let tuning = HardwareTuning {
+            move_time,
+            cores,
+            nps,
+            hashfull,
+            threads: recommended_threads,
+            hash: hardware::recommend_hash(fastest, move_time),
+        };
+        writeln!(
+            self.output,
+            "Time control {}: {} ms for the first move, {} cores",
+            time_control,
+            move_time.as_millis(),
+            cores
+        );
+        for (count, nps) in &tuning.nps {
+            writeln!(self.output, "Threads {:>4}: {:>12} nps", count, nps);
+        }
+        for (megabytes, hashfull) in &tuning.hashfull {
+            writeln!(
+                self.output,
+                "Hash {:>5} MB: {:>4} permille full after {} ms",
+                megabytes,
+                hashfull,
+                hardware::CALIBRATION_TIME.as_millis()
+            );
+        }
+        writeln!(
+            self.output,
+            "Recommended: setoption name Threads value {}, setoption name Hash value {}",
+            tuning.threads, tuning.hash
+        );
+        tuning
+    }
+
+    /// Checks that this build plays correct chess before its analysis is trusted: perft
+    /// counts, slider attack lookups, incremental hashing along every line of the perft
+    /// positions, and a short bench. Prints PASS or FAIL for each and returns the results.
+    /// The position, history, rules and tables are restored afterwards.
+    pub fn self_check(&mut self) -> Vec<SelfCheck> {
+        let state = self.state.clone();
+        let history = std::mem::take(&mut self.history);
+        let rules = std::mem::replace(&mut self.rules, Arc::new(Standard));
+        let tt = std::mem::replace(&mut self.tt, Arc::new(TranspositionTable::new(16)));
+        let correction = std::mem::replace(&mut self.correction, CorrectionHistory::new(1 << 14));
+        let output = std::mem::replace(&mut self.output, Output::sink());
+
+        let mut perft = Ok(format!("{} positions", selfcheck::PERFT_CHECKS.len()));
+        for (fen, depth, expected) in selfcheck::PERFT_CHECKS {
+            self.state = fen::parse(fen).expect("Invalid self-check position");
+            let nodes = self.perft_driver(depth);
+            if nodes != expected {
+                perft = Err(format!(
+                    "{} nodes at depth {} of {}, expected {}",
+                    nodes, depth, fen, expected
+                ));
+                break;
+            }
+        }
+
+        let attacks = self
+            .attack_table
+            .verify()
+            .map(|_| format!("{:?} indexing", self.attack_table.indexing()));
+
+        let mut zobrist = Ok(format!("every line of {} plies", selfcheck::ZOBRIST_DEPTH));
+        for (fen, _, _) in selfcheck::PERFT_CHECKS {
+            self.state = fen::parse(fen).expect("Invalid self-check position");
+            self.history.clear();
+            if let Err(err) = self.check_hashes(selfcheck::ZOBRIST_DEPTH) {
+                zobrist = Err(format!("{} ({})", err, fen));
+                break;
+            }
+        }
+
+        let time = selfcheck::BENCH_TIME / BENCH_POSITIONS.len() as u32;
+        let start = Instant::now();
+        let mut nodes = 0;
+        let mut bench = Ok(());
+        for fen in BENCH_POSITIONS {
+            self.state = fen::parse(fen).expect("Invalid bench position");
+            self.history.clear();
+            let legal_moves = self.generate_legal_moves();
+            let result = self.search(MAX_PLY as u8 - 1, &CancellationToken::with_timeout(time));
+            nodes += result.nodes;
+            // A slow build may not finish the first iteration in time, and then has no move
+            if result.depth > 0 && !legal_moves.contains(&result.best_move) {
+                bench = Err(format!("No legal move found in {}", fen));
+                break;
+            }
+        }
+        let bench = bench.map(|_| {
+            let elapsed = start.elapsed();
+            format!(
+                "{} nodes in {} ms, {:.0} nps",
+                nodes,
+                elapsed.as_millis(),
+                nodes as f64 / elapsed.as_secs_f64().max(1e-9)
+            )
+        });
+
+        self.state = state;
+        self.history = history;
+        self.rules = rules;
+        self.tt = tt;
+        self.correction = correction;
+        self.output = output;
+
+        let checks = vec![
+            SelfCheck {
+                name: "perft",
+                result: perft,
+            },
+            SelfCheck {
+                name: "attacks",
+                result: attacks,
+            },
+            SelfCheck {
+                name: "zobrist",
+                result: zobrist,
+            },
+            SelfCheck {
+                name: "bench",
+                result: bench,
+            },
+        ];
+        for check in &checks {
+            match &check.result {
+                Ok(detail) => writeln!(self.output, "PASS {}: {}", check.name, detail),
+                Err(err) => writeln!(self.output, "FAIL {}: {}", check.name, err),
+            };
+        }
+        checks
+    }
+
+    /// Makes every legal line `depth` plies deep, checking the state against a reload
+    /// after each move and that taking the move back restores the hash.
+    fn check_hashes(&mut self, depth: u8) -> Result<(), String> {
+        for move_ in self.generate_legal_moves() {
+            let hash = self.state.hash;
+            self.make_move(move_);
+            let line = self.check_consistency().and_then(|_| match depth {
+                1 => Ok(()),
+                _ => self.check_hashes(depth - 1),
+            });
+            self.take_back();
+            line.map_err(|err| format!("{} after {}", err, self.format_move(move_)))?;
+            if self.state.hash != hash {
+                return Err(format!(
+                    "Taking back {} changed the hash",
+                    self.format_move(move_)
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs movegen, make/unmake, eval and qsearch micro-benchmarks on a fixed set of
+    /// positions for `duration` each and prints their throughput. The current position
+    /// and history are restored afterwards.
+    pub fn speedtest(&mut self, duration: Duration) {
+        let positions = SPEEDTEST_POSITIONS
+            .iter()
+            .map(|fen| fen::parse(fen).expect("Invalid speedtest position"))
+            .collect::<Vec<EngineState>>();
+        let state = std::mem::replace(&mut self.state, positions[0].clone());
+        let history = std::mem::take(&mut self.history);
+        // Repeated searches of the same positions would otherwise be answered by the table
+        let tt = std::mem::replace(&mut self.tt, Arc::new(TranspositionTable::new(0)));
+
+        type Benchmark = fn(&mut Engine) -> u64;
+        let benchmarks: [(&str, Benchmark); 4] = [
+            ("movegen", |engine| engine.generate_moves().len() as u64),
+            ("make/unmake", |engine| {
+                let moves = engine.generate_moves();
+                moves.iter().fold(0, |count, &move_| {
+                    if engine.make_move(move_) {
+                        engine.take_back();
+                    }
+                    count + 1
+                })
+            }),
+            ("eval", |engine| {
+                std::hint::black_box(engine.evaluate());
+                1
+            }),
+            ("qsearch", |engine| {
+                let nodes = engine.search_nodes;
+                engine.quiescence(-evaluate::MAX_SCORE, evaluate::MAX_SCORE);
+                engine.search_nodes - nodes
+            }),
+        ];
+
+        writeln!(self.output, "Speed test ({:?} per benchmark):", duration);
+        writeln!(
+            self.output,
+            "{}, slider indexing: {:?}",
+            cpu::describe(),
+            self.attack_table.indexing()
+        );
+        for (name, benchmark) in benchmarks {
+            let start = Instant::now();
+            let mut operations = 0;
+            while start.elapsed() < duration {
+                for position in positions.iter() {
+                    self.state = position.clone();
+                    operations += benchmark(self);
+                }
+            }
+            writeln!(
+                self.output,
+                "{:<12} {:>14.0} ops/s",
+                name,
+                operations as f64 / start.elapsed().as_secs_f64()
+            );
+        }
+
+        self.state = state;
+        self.history = history;
+        self.tt = tt;
+    }
+
+    /// Searches each position from a new game and returns the total nodes and time. The
+    /// position, history, tables and output are restored afterwards.
+    fn run_suite(&mut self, positions: &[&str], depth: u8) -> Result<(u64, Duration), String> {
+        let state = self.state.clone();
+        let history = std::mem::take(&mut self.history);
+        // A fixed size keeps the node counts independent of the hash setting
+        let tt = std::mem::replace(&mut self.tt, Arc::new(TranspositionTable::new(16)));
+        let correction = std::mem::replace(&mut self.correction, CorrectionHistory::new(1 << 14));
+        let output = std::mem::replace(&mut self.output, Output::sink());
+
+        let start = Instant::now();
+        let mut nodes = Ok(0);
+        for fen in positions {
+            match fen::parse(fen) {
+                Ok(position) => self.state = position,
+                Err(err) => {
+                    nodes = Err(format!("{}: {}", err, fen));
+                    break;
+                }
+            }
+            self.new_game();
+            nodes = nodes.map(|nodes| nodes + self.search_position(depth).nodes);
+        }
+        let elapsed = start.elapsed();
+
+        self.state = state;
+        self.history = history;
+        self.tt = tt;
+        self.correction = correction;
+        self.output = output;
+        nodes.map(|nodes| (nodes, elapsed))
+    }
+
+    /// Searches every bench position to `depth` from a fresh hash table and prints the
+    /// total node count, the bench signature, and the speed. The current position,
+    /// history and hash table are restored afterwards.
+    pub fn bench(&mut self, depth: u8) -> u64 {
+        let (nodes, elapsed) = self
+            .run_suite(&BENCH_POSITIONS, depth)
+            .expect("Invalid bench position");
+        writeln!(
+            self.output,
+            "Bench: {} positions, depth {}, {} nodes, {:.0} nps, {} ms",
+            BENCH_POSITIONS.len(),
+            depth,
+            nodes,
+            nodes as f64 / elapsed.as_secs_f64().max(1e-9),
+            elapsed.as_millis()
+        );
+        nodes
+    }
+
+    /// Searches the positions under each move ordering configuration and prints a table of
+    /// nodes and time, relative to the first configuration. Returns the nodes and time of each.
+    pub fn ordering_experiment(
+        &mut self,
+        positions: &[&str],
+        depth: u8,
+        configurations: &[(&str, MoveOrdering)],
+    ) -> Result<Vec<(u64, Duration)>, String> {
+        let ordering = self.ordering;
+        let results = configurations
+            .iter()
+            .map(|&(_, configuration)| {
+                self.ordering = configuration;
+                self.run_suite(positions, depth)
+            })
+            .collect::<Result<Vec<(u64, Duration)>, String>>();
+        self.ordering = ordering;
+        let results = results?;
+
+        writeln!(
+            self.output,
+            "Move ordering: {} positions, depth {}",
+            positions.len(),
+            depth
+        );
+        writeln!(
+            self.output,
+            "{:<16} {:>12} {:>10} {:>8}",
+            "configuration", "nodes", "ms", "nodes %"
+        );
+        let baseline = results.first().map_or(1, |&(nodes, _)| nodes.max(1));
+        for ((name, _), (nodes, elapsed)) in configurations.iter().zip(&results) {
+            writeln!(
+                self.output,
+                "{:<16} {:>12} {:>10} {:>8.1}",
+                name,
+                nodes,
+                elapsed.as_millis(),
+                *nodes as f64 * 100.0 / baseline as f64
+            );
+        }
+        Ok(results)
+    }
+
+    /// Runs the bench at `BENCH_DEPTH` and compares its node count with `BENCH_SIGNATURE`.
+    pub fn bench_verify(&mut self) -> bool {
+        let nodes = self.bench(BENCH_DEPTH);
+        if nodes == BENCH_SIGNATURE {
+            writeln!(self.output, "Bench signature matches: {}", nodes);
+            true
+        } else {
+            writeln!(
+                self.output,
+                "Bench signature mismatch: expected {}, got {}",
+                BENCH_SIGNATURE, nodes
+            );
+            false
+        }
+    }
+
+    pub fn perft(&mut self, depth: u8) {
+        let mut nodes = 0;
+        let now = Instant::now();
+
+        let output = self.output.clone();
+        let print_divider = || {
+            writeln!(output, "{}", "─".repeat(56));
+        };
+
+        let print_headers = || {
+            writeln!(
+                output,
+                "{:>5} │ {:<6} │ {:<10} │ {:<12} │ {:<10}",
+                "No.", "Move", "Nodes", "Time", "kNPS"
+            );
+        };
+
+        print_divider();
+        writeln!(self.output, "Performance test:");
+        print_divider();
+        print_headers();
+        print_divider();
+
+        for (index, &move_) in self.generate_moves().iter().enumerate() {
+            if self.make_move(move_) {
+                let start = Instant::now();
+                let depth_nodes = self.perft_driver(depth - 1);
+                nodes += depth_nodes;
+                self.take_back();
+
+                let elapsed = start.elapsed();
+                let seconds = elapsed.as_secs_f64();
+                let knps = if seconds > 0.0 {
+                    (depth_nodes as f64 / seconds) / 1000.0
+                } else {
+                    0.0
+                };
+
+                writeln!(
+                    self.output,
+                    "{:>5} │ {:<6} │ {:<10} │ {:<12?} │ {:<10.2}",
+                    index + 1,
+                    moves::format(move_),
+                    depth_nodes,
+                    elapsed,
+                    knps
+                );
+            }
+        }
+
+        print_divider();
+
+        let total_elapsed = now.elapsed();
+        let total_seconds = total_elapsed.as_secs_f64();
+        let total_knps = if total_seconds > 0.0 {
+            (nodes as f64 / total_seconds) / 1000.0
+        } else {
+            0.0
+        };
+
+        writeln!(self.output, "Depth: {}", depth);
+        writeln!(self.output, "Nodes: {}", nodes);
+        writeln!(self.output, "Time: {:?}", total_elapsed);
+        writeln!(self.output, "kNPS: {:.2}", total_knps);
+        print_divider();
+    }
+
+    /// Perft that looks up positions reached by transposition in `table` instead of
+    /// counting them again. If the table audits, sampled hits are recomputed by a plain
+    /// perft, which is also what they count for.
+    pub fn hashed_perft(&mut self, depth: u8, table: &mut PerftTable) -> u64 {
+        // Leaves are cheaper to count than to look up
+        if depth <= 1 {
+            return self.perft_driver(depth);
+        }
+        let key = self.state.hash;
+        if let Some(nodes) = table.probe(key, depth) {
+            if !table.sample() {
+                return nodes;
+            }
+            let actual = self.perft_driver(depth);
+            table.record(nodes, actual);
+            return actual;
+        }
+        let mut nodes = 0;
+        for &move_ in self.generate_moves().iter() {
+            if self.make_move(move_) {
+                nodes += self.hashed_perft(depth - 1, table);
+                self.take_back();
+            }
+        }
+        table.store(key, depth, nodes);
+        nodes
+    }
+
+    /// The perft count at `depth` below each legal move, in UCI notation and sorted by it.
+    pub fn divide(&mut self, depth: u8) -> Vec<(String, u64)> {
+        let mut divide = vec![];
+        for move_ in self.generate_legal_moves() {
+            self.make_move(move_);
+            let nodes = self.perft_driver(depth.saturating_sub(1));
+            self.take_back();
+            divide.push((self.format_move(move_), nodes));
+        }
+        divide.sort();
+        divide
+    }
+
+    /// Compares divided perfts with those of `reference`, called with a FEN and a depth,
+    /// and follows the first move they count differently down to where they disagree on
+    /// the moves themselves, or to depth 1. Returns where that is, `None` if they agree.
+    pub fn find_perft_diff(
+        &mut self,
+        depth: u8,
+        reference: &mut impl FnMut(&str, u8) -> Result<Vec<(String, u64)>, String>,
+    ) -> Result<Option<PerftDiff>, String> {
+        let mut diff: Option<PerftDiff> = None;
+        let mut moves = vec![];
+        let mut depth = depth.max(1);
+        let result = loop {
+            let theirs = match reference(&self.fen(), depth) {
+                Ok(theirs) => theirs,
+                Err(err) => break Err(err),
+            };
+            let differences = perft::compare_divides(&self.divide(depth), &theirs);
+            // Counts that differ above but agree here leave the last difference standing
+            if differences.is_empty() {
+                break Ok(diff);
+            }
+            let next = differences
+                .iter()
+                .all(|(_, ours, theirs)| ours.is_some() && theirs.is_some())
+                .then(|| differences[0].0.clone());
+            diff = Some(PerftDiff {
+                fen: self.fen(),
+                moves: moves.clone(),
+                depth,
+                differences,
+            });
+            match next.and_then(|move_| self.parse_move(&move_).map(|m| (move_, m))) {
+                Some((text, move_)) if depth > 1 => {
+                    self.make_move(move_);
+                    moves.push(text);
+                    depth -= 1;
+                }
+                _ => break Ok(diff),
+            }
+        };
+        for _ in moves.iter() {
+            self.take_back();
+        }
+        result
+    }
+
+    /// Follows one random path `depth` plies deep and returns the product of the legal move
+    /// counts along it, or 0 if the game ends first.
+    fn random_path_weight(&mut self, depth: u8, rng: &mut Rng) -> f64 {
+        let mut weight = 1.0;
+        let mut plies = 0;
+        while plies < depth {
+            let moves = self.generate_legal_moves();
+            if moves.is_empty() {
+                weight = 0.0;
+                break;
+            }
+            weight *= moves.len() as f64;
+            self.make_move(moves[rng.below(moves.len())]);
+            plies += 1;
+        }
+        for _ in 0..plies {
+            self.take_back();
+        }
+        weight
+    }
+
+    /// Estimates the perft count at `depth` from `paths` random paths.
+    pub fn estimate_perft(&mut self, depth: u8, paths: u64, rng: &mut Rng) -> PerftEstimate {
+        let mut estimate = PerftEstimate::default();
+        for _ in 0..paths {
+            estimate.add(self.random_path_weight(depth, rng));
+        }
+        estimate
+    }
+
+    /// Estimates the perft count at `depth` from as many random paths as fit in `time`,
+    /// then times exact perfts of shallow depths to tell how long the exact count would
+    /// take. Prints both.
+    pub fn perft_estimate(&mut self, depth: u8, time: Duration) -> PerftEstimate {
+        let mut rng = Rng::new(self.state.hash);
+        let mut estimate = PerftEstimate::default();
+        let start = Instant::now();
+        while estimate.paths == 0 || start.elapsed() < time {
+            for _ in 0..perft::ESTIMATE_BATCH {
+                estimate.add(self.random_path_weight(depth, &mut rng));
+            }
+        }
+        writeln!(self.output, "Depth: {}", depth);
+        writeln!(self.output, "Paths: {}", estimate.paths);
+        writeln!(
+            self.output,
+            "Estimated nodes: {:.3e} ± {:.1e} at 95% confidence",
+            estimate.nodes(),
+            estimate.margin()
+        );
+
+        let start = Instant::now();
+        let mut counted = 0;
+        for shallow in 1..depth {
+            counted += self.perft_driver(shallow);
+            if start.elapsed() >= perft::SPEED_SAMPLE {
+                break;
+            }
+        }
+        if counted > 0 {
+            let nps = counted as f64 / start.elapsed().as_secs_f64().max(1e-9);
+            writeln!(
+                self.output,
+                "Exact perft: about {} at {:.0} kNPS",
+                perft::rough_duration(estimate.nodes() / nps),
+                nps / 1000.0
+            );
+        }
+        estimate
+    }
+
+    /// Runs a hashed perft with `megabytes` of table and reports the count. With `audit`,
+    /// one in that many hits is checked, and the report tells how many cached counts
+    /// turned out wrong.
+    pub fn perft_hashed(
+        &mut self,
+        depth: u8,
+        megabytes: usize,
+        audit: Option<usize>,
+    ) -> PerftAudit {
+        let mut table = PerftTable::new(megabytes);
+        if let Some(every) = audit {
+            table = table.with_audit(every, self.state.hash);
+        }
+        let start = Instant::now();
+        let nodes = self.hashed_perft(depth, &mut table);
+        let audit = table.audit();
+        writeln!(self.output, "Depth: {}", depth);
+        writeln!(self.output, "Nodes: {}", nodes);
+        writeln!(self.output, "Time: {:?}", start.elapsed());
+        writeln!(
+            self.output,
+            "Hash hits: {}, checked: {}, mismatches: {}",
+            audit.hits, audit.checked, audit.mismatches
+        );
+        if let Some(bound) = audit.collision_rate_bound() {
+            writeln!(
+                self.output,
+                "Collision rate: below {:.2e} per hit at 95% confidence",
+                bound
+            );
+        }
+        audit
+    }
+
+    pub fn print_attacked_squares(&self, side: u8) {
+        for rank in 0..8 {
+            write!(self.output, "{} ", 8 - rank);
+            for file in 0..8 {
+                let square = rank * 8 + file;
+                if self.is_square_attacked(square, side) {
+                    write!(self.output, "X ");
+                } else {
+                    write!(self.output, "• ");
+                }
+            }
+            writeln!(self.output);
+        }
+        writeln!(self.output, "  a b c d e f g h");
+    }
+
+    pub fn print_move_scores(&self, sort: bool) {
+        let print_divider = || {
+            writeln!(self.output, "{}", "─".repeat(25));
+        };
+        let print_headers = || {
+            writeln!(self.output, "{:>5} │ {:<6} │ {:<7}", "No.", "Move", "Score");
+        };
+        print_divider();
+        writeln!(self.output, "  Move Scores:");
+        print_divider();
+        print_headers();
+        print_divider();
+        let moves = self.generate_moves();
+        let moves = if sort { self.sort_moves(&moves) } else { moves };
+        for (index, &move_) in moves.iter().enumerate() {
+            let score = self.score_move(move_);
+            writeln!(
+                self.output,
+                "{:>5} │ {:<6} │ {:<7}",
+                index + 1,
+                moves::format(move_),
+                score
+            );
+        }
+        print_divider();
+        print_headers();
+        print_divider();
+        writeln!(self.output, "  Total moves: {}", moves.len());
+        print_divider();
+    }
+
+    pub fn print(&self) {
+        let EngineState {
+            bitboards,
+            side,
+            castling,
+            en_passant,
+            half_moves,
+            full_moves,
+            ..
+        } = self.state;
+        for rank in 0..8 {
+            write!(self.output, "{} ", 8 - rank);
+            for file in 0..8 {
+                let square = rank * 8 + file;
+                let mut found = false;
+                bitboards.iter().enumerate().for_each(|(index, &bitboard)| {
+                    if get_bit!(bitboard, square) {
+                        write!(self.output, "{} ", ASCII_PIECES[index]);
+                        found = true
+                    }
+                });
+                if !found {
+                    write!(self.output, "• ");
+                }
+            }
+            writeln!(self.output);
+        }
+        writeln!(self.output, "  a b c d e f g h");
+
+        writeln!(self.output);
+        writeln!(self.output, "Side: {}", side::format(side));
+        writeln!(self.output, "Castling: {}", castling::format(castling));
+        writeln!(
+            self.output,
+            "Enpassant: {}",
+            en_passant.map_or_else(|| "-".to_string(), |sq| { index_to_algebraic(sq as usize) })
+        );
+        writeln!(self.output, "Halfmove: {}", half_moves);
+        writeln!(self.output, "Fullmove: {}", full_moves);
+        #[cfg(feature = "crazyhouse")]
+        if self.rules.uses_pockets() {
+            let pockets = self
+                .state
+                .pockets
+                .iter()
+                .enumerate()
+                .flat_map(|(side, pocket)| {
+                    pocket
+                        .iter()
+                        .enumerate()
+                        .flat_map(move |(piece_type, &count)| {
+                            std::iter::repeat_n(ASCII_PIECES[piece_type + side * 6], count as usize)
+                        })
+                })
+                .collect::<String>();
+            writeln!(
+                self.output,
+                "Pockets: {}",
+                if pockets.is_empty() { "-" } else { &pockets }
+            );
+        }
+    }
+}
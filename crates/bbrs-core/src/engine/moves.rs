@@ -0,0 +1,177 @@
+use std::fmt;
+
+use crate::engine::{board::index_to_algebraic, piece::side, Color, ASCII_PIECES};
+
+/// A move as encoded by `encode_move!`.
+pub type Move = u32;
+
+/// Passing the turn, `0000` in UCI. Never a legal move, so it also stands for no move.
+pub const NULL_MOVE: u32 = 0;
+
+/// Encodes a chess move into a 32-bit integer.
+/// - `encode_move!(source, target, piece, promotion, flags)`
+/// - `encode_move!(source, target, piece)` (defaults promotion and flags to 0)
+/// - `encode_move!(source, target, piece, flags)` (defaults promotion to 0)
+#[macro_export]
+macro_rules! encode_move {
+    ($source:expr, $target:expr, $piece:expr, $promotion:expr, $flags:expr) => {
+        ($source | ($target << 6) | ($piece << 12) | ($promotion << 16) | ($flags << 20)) as u32
+    };
+    ($source:expr, $target:expr, $piece:expr) => {
+        encode_move!($source, $target, $piece, 0, 0)
+    };
+    ($source:expr, $target:expr, $piece:expr, $flags:expr) => {
+        encode_move!($source, $target, $piece, 0, $flags)
+    };
+}
+
+/// Decodes a 32-bit chess move into a tuple (source, target, piece, promotion, (capture, double pawn push, en passant, castle)).
+#[macro_export]
+macro_rules! decode_move {
+    ($move:expr) => {
+        (
+            ($move & 0x3F) as u8,        // source square (0-63)
+            (($move >> 6) & 0x3F) as u8, // target square (0-63)
+            (($move >> 12) & 0xF) as u8, // piece moved (0-15)
+            (($move >> 16) & 0xF) as u8, // promotion piece (0-15)
+            (
+                ($move & (1 << 20)) != 0, // capture
+                ($move & (1 << 21)) != 0, // double pawn push
+                ($move & (1 << 22)) != 0, // en passant
+                ($move & (1 << 23)) != 0, // castle
+            ),
+        )
+    };
+}
+
+pub mod flags {
+    pub const CAPTURE: u8 = 1 << 0;
+    pub const DOUBLE: u8 = 1 << 1;
+    pub const EN_PASSANT: u8 = 1 << 2;
+    pub const CASTLE: u8 = 1 << 3;
+    #[cfg(feature = "crazyhouse")]
+    pub const DROP: u8 = 1 << 4;
+}
+
+/// Whether the move places a piece from the pocket (source and target are the same square).
+#[cfg(feature = "crazyhouse")]
+pub fn is_drop(move_: u32) -> bool {
+    move_ & ((flags::DROP as u32) << 20) != 0
+}
+
+pub fn format(move_: u32) -> String {
+    let (source, target, _, promotion, _) = decode_move!(move_);
+    #[cfg(feature = "crazyhouse")]
+    if is_drop(move_) {
+        let (_, _, piece, _, _) = decode_move!(move_);
+        return format!(
+            "{}@{}",
+            ASCII_PIECES[(piece % 6) as usize],
+            index_to_algebraic(target as usize)
+        );
+    }
+    if move_ == NULL_MOVE {
+        return "0000".to_string();
+    }
+    // UCI writes promotions in lowercase for both sides
+    let suffix = if promotion != 0 {
+        ASCII_PIECES[(promotion % 6 + 6) as usize].to_string()
+    } else {
+        String::new()
+    };
+
+    format!(
+        "{}{}{}",
+        index_to_algebraic(source as usize),
+        index_to_algebraic(target as usize),
+        suffix
+    )
+}
+
+/// Why a move written in coordinate notation cannot be played, see `Engine::find_move`.
+/// Squares are indices, as in moves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IllegalMove {
+    Notation,
+    PromotionPiece,
+    NoPiece(u8),
+    /// The piece on the square is not of the side to move, which is given.
+    WrongSide {
+        square: u8,
+        to_move: Color,
+    },
+    /// The target holds a piece of the side to move.
+    OwnPiece(u8),
+    /// The piece does not move that way, or something stands in its way.
+    Unreachable {
+        source: u8,
+        target: u8,
+    },
+    /// The king may not castle there now: the right is lost, or the path is blocked or
+    /// attacked.
+    Castling,
+    /// Moving the piece on `square` off its line would expose the king to `pinner`.
+    Pinned {
+        square: u8,
+        pinner: u8,
+    },
+    /// The king is in check, and the move does not get it out.
+    InCheck,
+    LeavesKingInCheck,
+    NotAPromotion,
+    MissingPromotion,
+    NullMoveInCheck,
+    #[cfg(feature = "crazyhouse")]
+    Drop,
+    #[cfg(feature = "crazyhouse")]
+    IllegalDrop,
+    /// Forbidden by the rules of the variant.
+    Variant,
+}
+
+impl fmt::Display for IllegalMove {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let square = |square: u8| index_to_algebraic(square as usize);
+        match *self {
+            IllegalMove::Notation => write!(f, "Not in coordinate notation"),
+            IllegalMove::PromotionPiece => write!(f, "Invalid promotion piece"),
+            IllegalMove::NoPiece(on) => write!(f, "No piece on {}", square(on)),
+            IllegalMove::WrongSide {
+                square: on,
+                to_move,
+            } => write!(
+                f,
+                "The piece on {} is {}, but {} is to move",
+                square(on),
+                side::format(to_move.opponent().side()),
+                side::format(to_move.side())
+            ),
+            IllegalMove::OwnPiece(on) => {
+                write!(f, "{} holds a piece of the side to move", square(on))
+            }
+            IllegalMove::Unreachable { source, target } => write!(
+                f,
+                "The piece on {} cannot move to {}",
+                square(source),
+                square(target)
+            ),
+            IllegalMove::Castling => write!(f, "Castling is not possible in this position"),
+            IllegalMove::Pinned { square: on, pinner } => write!(
+                f,
+                "The piece on {} is pinned to the king by the one on {}",
+                square(on),
+                square(pinner)
+            ),
+            IllegalMove::InCheck => write!(f, "The king is in check and the move does not help"),
+            IllegalMove::LeavesKingInCheck => write!(f, "The move leaves the king in check"),
+            IllegalMove::NotAPromotion => write!(f, "Not a promotion"),
+            IllegalMove::MissingPromotion => write!(f, "Missing the promotion piece"),
+            IllegalMove::NullMoveInCheck => write!(f, "A null move is not possible in check"),
+            #[cfg(feature = "crazyhouse")]
+            IllegalMove::Drop => write!(f, "Invalid drop"),
+            #[cfg(feature = "crazyhouse")]
+            IllegalMove::IllegalDrop => write!(f, "Not a legal drop"),
+            IllegalMove::Variant => write!(f, "Not a legal move in this variant"),
+        }
+    }
+}
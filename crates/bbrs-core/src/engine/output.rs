@@ -0,0 +1,72 @@
+use std::{
+    fmt,
+    io::{self, Write},
+    sync::{Arc, Mutex},
+};
+
+/// Where the engine writes search info, perft tables and boards.
+///
+/// Clones share the same writer, so a binary can hand one to the engine and keep
+/// writing its own responses to it. Write errors are ignored: a GUI closing its end
+/// of the pipe should not take the search down with it.
+#[derive(Clone)]
+pub struct Output {
+    writer: Arc<Mutex<Box<dyn Write + Send>>>,
+}
+
+/// Stdout, or with the `silent` feature nowhere.
+impl Default for Output {
+    fn default() -> Self {
+        if cfg!(feature = "silent") {
+            Output::new(io::sink())
+        } else {
+            Output::stdout()
+        }
+    }
+}
+
+impl fmt::Debug for Output {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Output")
+    }
+}
+
+impl Output {
+    pub fn new(writer: impl Write + Send + 'static) -> Self {
+        Output {
+            writer: Arc::new(Mutex::new(Box::new(writer))),
+        }
+    }
+
+    pub fn stdout() -> Self {
+        Output::new(io::stdout())
+    }
+
+    /// Discards everything written to it.
+    pub fn sink() -> Self {
+        Output::new(io::sink())
+    }
+
+    /// Called by `write!` and `writeln!`. Each call writes and flushes atomically.
+    pub fn write_fmt(&self, args: fmt::Arguments<'_>) {
+        let mut writer = self.writer.lock().unwrap_or_else(|err| err.into_inner());
+        let _ = writer.write_fmt(args).and_then(|_| writer.flush());
+    }
+}
+
+/// Writes everything to both writers, e.g. stdout and a log file.
+#[derive(Debug)]
+pub struct Tee<A, B>(pub A, pub B);
+
+impl<A: Write, B: Write> Write for Tee<A, B> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write_all(buf)?;
+        self.1.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()?;
+        self.1.flush()
+    }
+}
@@ -0,0 +1,117 @@
+use super::{
+    attacks::{masks, AttackTable},
+    evaluate,
+    piece::side,
+};
+
+/// Pawn structure terms that only depend on the placement of the pawns.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PawnEntry {
+    white_pawns: u64,
+    black_pawns: u64,
+    /// Score of the pawn-only terms from White's point of view.
+    pub score: i32,
+    /// Passed pawns per side.
+    pub passed: [u64; 2],
+    /// Squares on each side's third and fourth ranks its pawns can never defend.
+    pub holes: [u64; 2],
+}
+
+/// A hash of the pawn structure. Its upper half is well mixed and suited for indexing.
+pub fn pawn_key(white_pawns: u64, black_pawns: u64) -> u64 {
+    (white_pawns ^ black_pawns.rotate_left(32)).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+}
+
+/// A cache of `PawnEntry` keyed by the pawn bitboards of both sides.
+///
+/// The bitboards are stored in full, so a hit is never a false positive.
+#[derive(Debug, Clone)]
+pub struct PawnTable {
+    entries: Vec<PawnEntry>,
+}
+
+impl PawnTable {
+    /// Creates a table with `size` entries, rounded up to a power of two.
+    pub fn new(size: usize) -> Self {
+        PawnTable {
+            entries: vec![PawnEntry::default(); size.next_power_of_two()],
+        }
+    }
+
+    pub fn memory(&self) -> usize {
+        std::mem::size_of_val(self.entries.as_slice())
+    }
+
+    fn index(&self, white_pawns: u64, black_pawns: u64) -> usize {
+        (pawn_key(white_pawns, black_pawns) >> 32) as usize & (self.entries.len() - 1)
+    }
+
+    /// Returns the entry for the given pawns, calling `compute` on a miss.
+    pub fn probe(
+        &mut self,
+        white_pawns: u64,
+        black_pawns: u64,
+        compute: impl FnOnce() -> PawnEntry,
+    ) -> PawnEntry {
+        let index = self.index(white_pawns, black_pawns);
+        let entry = self.entries[index];
+        if entry.white_pawns == white_pawns && entry.black_pawns == black_pawns {
+            return entry;
+        }
+        let entry = PawnEntry {
+            white_pawns,
+            black_pawns,
+            ..compute()
+        };
+        self.entries[index] = entry;
+        entry
+    }
+}
+
+/// The rank of a square counted from `side`'s first rank.
+pub fn relative_rank(side: u8, square: u8) -> usize {
+    let rank = (square >> 3) as usize;
+    if side == side::WHITE {
+        7 - rank
+    } else {
+        rank
+    }
+}
+
+/// Computes the pawn-only terms: passed pawns with their rank bonus, and holes.
+pub fn evaluate(attack_table: &AttackTable, pawns: [u64; 2]) -> PawnEntry {
+    let mut entry = PawnEntry::default();
+    for side in [side::WHITE, side::BLACK] {
+        let (own, enemy) = (pawns[side as usize], pawns[(side ^ 1) as usize]);
+        let mut bitboard = own;
+        let mut score = 0;
+        let mut defendable = 0;
+        while bitboard != 0 {
+            let square = get_lsb!(bitboard) as usize;
+            defendable |= attack_table.get_pawn_attack_span(side, square);
+            // Only the frontmost pawn of a doubled pair can be passed
+            if attack_table.get_passed_pawn_mask(side, square) & enemy == 0
+                && attack_table.get_forward_file(side, square) & own == 0
+            {
+                set_bit!(entry.passed[side as usize], square);
+                score += evaluate::PASSED_PAWN_BONUS[relative_rank(side, square as u8)];
+            }
+            clear_lsb!(bitboard);
+        }
+
+        // Without pawns every square would count, which says nothing about the structure
+        if own != 0 {
+            let camp = if side == side::WHITE {
+                masks::RANK_MASKS[5] | masks::RANK_MASKS[4]
+            } else {
+                masks::RANK_MASKS[2] | masks::RANK_MASKS[3]
+            };
+            let holes = camp & !defendable;
+            entry.holes[side as usize] = holes;
+            score -= count_bits!(holes) as i32 * evaluate::HOLE_PENALTY;
+        }
+
+        entry.score += if side == side::WHITE { score } else { -score };
+    }
+    entry
+}
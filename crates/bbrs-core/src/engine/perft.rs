@@ -0,0 +1,363 @@
+use std::{fmt, mem, time::Duration};
+
+use super::playout::Rng;
+
+/// Normal quantile for a 95% confidence bound.
+const Z: f64 = 1.96;
+
+/// Random paths followed between checks of the time limit of an estimate.
+pub const ESTIMATE_BATCH: u64 = 64;
+/// How long exact perfts of shallow depths run to measure the speed of a deep one.
+pub const SPEED_SAMPLE: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Entry {
+    nodes: u64,
+    /// The upper half of the key: the lower half picks the slot, so two positions only
+    /// collide if their keys agree in the bits of both.
+    check: u32,
+    depth: u8,
+}
+
+/// How many cached counts an audit checked against a recomputation, and how many of them
+/// were wrong because two positions shared an entry.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PerftAudit {
+    /// Counts taken from the table.
+    pub hits: u64,
+    /// Hits recomputed by a plain perft.
+    pub checked: u64,
+    pub mismatches: u64,
+}
+
+impl PerftAudit {
+    /// An upper bound, at 95% confidence, on the share of hits that return a wrong count:
+    /// the Wilson score interval around the share found among the checked ones.
+    pub fn collision_rate_bound(&self) -> Option<f64> {
+        if self.checked == 0 {
+            return None;
+        }
+        let n = self.checked as f64;
+        let p = self.mismatches as f64 / n;
+        let centre = p + Z * Z / (2.0 * n);
+        let spread = Z * (p * (1.0 - p) / n + Z * Z / (4.0 * n * n)).sqrt();
+        Some(((centre + spread) / (1.0 + Z * Z / n)).min(1.0))
+    }
+}
+
+/// A perft count estimated by Monte Carlo sampling. Each random path weighs the product
+/// of the legal move counts along it, or zero if the game ends first (Knuth's estimator),
+/// and the mean weight of many paths approaches the exact count.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PerftEstimate {
+    pub paths: u64,
+    sum: f64,
+    squares: f64,
+}
+
+impl PerftEstimate {
+    pub fn add(&mut self, weight: f64) {
+        self.paths += 1;
+        self.sum += weight;
+        self.squares += weight * weight;
+    }
+
+    pub fn nodes(&self) -> f64 {
+        self.sum / self.paths.max(1) as f64
+    }
+
+    /// The half-width of a 95% confidence interval around `nodes`.
+    pub fn margin(&self) -> f64 {
+        if self.paths < 2 {
+            return f64::INFINITY;
+        }
+        let n = self.paths as f64;
+        let variance = ((self.squares - self.sum * self.sum / n) / (n - 1.0)).max(0.0);
+        Z * (variance / n).sqrt()
+    }
+}
+
+/// Where two divided perfts part: the position reached by `moves` from the compared one,
+/// the depth left there, and each move counted differently as (move, ours, theirs), with
+/// `None` for a move one side does not have.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PerftDiff {
+    pub fen: String,
+    pub moves: Vec<String>,
+    pub depth: u8,
+    pub differences: Vec<(String, Option<u64>, Option<u64>)>,
+}
+
+impl fmt::Display for PerftDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let count = |count: Option<u64>| count.map_or("missing".to_string(), |n| n.to_string());
+        writeln!(f, "Position: {}", self.fen)?;
+        writeln!(f, "Moves: {}", self.moves.join(" "))?;
+        write!(f, "Depth: {}", self.depth)?;
+        for (move_, ours, theirs) in self.differences.iter() {
+            write!(
+                f,
+                "\n{}: bbrs {}, reference {}",
+                move_,
+                count(*ours),
+                count(*theirs)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The moves counted differently by two divides, in the order of the moves.
+pub fn compare_divides(
+    ours: &[(String, u64)],
+    theirs: &[(String, u64)],
+) -> Vec<(String, Option<u64>, Option<u64>)> {
+    let find = |divide: &[(String, u64)], move_: &str| {
+        divide
+            .iter()
+            .find(|(other, _)| other == move_)
+            .map(|&(_, count)| count)
+    };
+    let mut moves = ours
+        .iter()
+        .chain(theirs.iter())
+        .map(|(move_, _)| move_.clone())
+        .collect::<Vec<String>>();
+    moves.sort();
+    moves.dedup();
+    moves
+        .into_iter()
+        .map(|move_| {
+            let (ours, theirs) = (find(ours, &move_), find(theirs, &move_));
+            (move_, ours, theirs)
+        })
+        .filter(|(_, ours, theirs)| ours != theirs)
+        .collect()
+}
+
+/// Reads the `move: count` lines of a divided perft as Stockfish prints them.
+pub fn parse_divide(text: &str) -> Vec<(String, u64)> {
+    text.lines()
+        .filter_map(|line| line.split_once(':'))
+        .filter(|(move_, _)| (4..=5).contains(&move_.trim().len()))
+        .filter_map(|(move_, count)| Some((move_.trim().to_string(), count.trim().parse().ok()?)))
+        .collect()
+}
+
+/// A position with reference perft counts, as an EPD line such as
+/// `<fen> ;D1 20 ;D2 400`.
+pub fn parse_perft_epd(line: &str) -> Option<(String, Vec<(u8, u64)>)> {
+    let mut fields = line.split(';');
+    let fen = fields.next()?.trim();
+    if fen.split_whitespace().count() < 4 {
+        return None;
+    }
+    let counts = fields
+        .filter_map(|field| {
+            let (depth, count) = field.trim().strip_prefix('D')?.split_once(' ')?;
+            Some((depth.parse().ok()?, count.trim().parse().ok()?))
+        })
+        .collect();
+    Some((fen.to_string(), counts))
+}
+
+/// A rough duration, in the largest unit that keeps it above one.
+pub(crate) fn rough_duration(seconds: f64) -> String {
+    const UNITS: [(f64, &str); 4] = [
+        (365.25 * 86400.0, "years"),
+        (86400.0, "days"),
+        (3600.0, "hours"),
+        (60.0, "minutes"),
+    ];
+    UNITS
+        .iter()
+        .find(|&&(unit, _)| seconds >= unit)
+        .map_or(format!("{:.1} seconds", seconds), |&(unit, name)| {
+            format!("{:.1} {}", seconds / unit, name)
+        })
+}
+
+/// Perft counts of positions already seen, keyed by Zobrist hash and depth, so that
+/// transpositions are counted once. Entries keep only part of the key, so a count can
+/// belong to another position; an audit recomputes a sample of the hits to tell how often.
+#[derive(Debug)]
+pub struct PerftTable {
+    entries: Vec<Entry>,
+    /// Recompute one in this many hits, along with the generator that picks them.
+    sampling: Option<(usize, Rng)>,
+    audit: PerftAudit,
+}
+
+impl PerftTable {
+    /// Creates a table that takes up about `megabytes` of memory.
+    pub fn new(megabytes: usize) -> Self {
+        let len = (megabytes * 1024 * 1024 / mem::size_of::<Entry>()).max(1);
+        PerftTable {
+            entries: vec![Entry::default(); len],
+            sampling: None,
+            audit: PerftAudit::default(),
+        }
+    }
+
+    /// Audits about one in `every` hits, picked at random from `seed`.
+    pub fn with_audit(mut self, every: usize, seed: u64) -> Self {
+        self.sampling = Some((every.max(1), Rng::new(seed)));
+        self
+    }
+
+    pub fn audit(&self) -> PerftAudit {
+        self.audit
+    }
+
+    fn index(&self, key: u64) -> usize {
+        ((key as u32 as u64 * self.entries.len() as u64) >> 32) as usize
+    }
+
+    pub fn probe(&mut self, key: u64, depth: u8) -> Option<u64> {
+        let entry = self.entries[self.index(key)];
+        let hit = entry.depth == depth && entry.check == (key >> 32) as u32;
+        if hit {
+            self.audit.hits += 1;
+        }
+        hit.then_some(entry.nodes)
+    }
+
+    pub fn store(&mut self, key: u64, depth: u8, nodes: u64) {
+        let index = self.index(key);
+        self.entries[index] = Entry {
+            nodes,
+            check: (key >> 32) as u32,
+            depth,
+        };
+    }
+
+    /// Whether the hit just made should be checked against a recomputation.
+    pub(crate) fn sample(&mut self) -> bool {
+        self.sampling
+            .as_mut()
+            .is_some_and(|(every, rng)| rng.below(*every) == 0)
+    }
+
+    pub(crate) fn record(&mut self, cached: u64, actual: u64) {
+        self.audit.checked += 1;
+        if cached != actual {
+            self.audit.mismatches += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{Engine, Output, START_POSITION};
+
+    #[test]
+    fn test_audited_hashed_perft() {
+        let mut engine =
+            Engine::new("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+                .unwrap();
+        let mut table = PerftTable::new(1).with_audit(1, 42);
+        assert_eq!(engine.hashed_perft(4, &mut table), 4085603);
+        let audit = table.audit();
+        assert!(audit.hits > 0);
+        assert_eq!((audit.checked, audit.mismatches), (audit.hits, 0));
+
+        let mut table = PerftTable::new(1);
+        assert_eq!(engine.hashed_perft(4, &mut table), 4085603);
+        assert_eq!(table.audit().checked, 0);
+    }
+
+    #[test]
+    fn test_estimate_is_close_to_the_exact_count() {
+        let mut engine =
+            Engine::new("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+                .unwrap();
+        let estimate = engine.estimate_perft(3, 2000, &mut Rng::new(7));
+        assert_eq!(estimate.paths, 2000);
+        assert!((estimate.nodes() - 97862.0).abs() < 2.0 * estimate.margin());
+        assert!(estimate.margin() < 0.1 * 97862.0);
+
+        // A single legal move at every ply leaves nothing to chance
+        let mut engine = Engine::new("k7/8/8/8/8/8/8/K7 w - - 0 1").unwrap();
+        assert_eq!(engine.estimate_perft(1, 10, &mut Rng::new(1)).margin(), 0.0);
+    }
+
+    /// The moves of a reference that forgets Ne2 after 1. e4 d5.
+    fn buggy_moves(engine: &mut Engine) -> Vec<u32> {
+        let buggy = engine.fen().starts_with("rnbqkbnr/ppp1pppp/8/3p4/4P3/");
+        let mut moves = engine.generate_legal_moves();
+        moves.retain(|&move_| !buggy || engine.format_move(move_) != "g1e2");
+        moves
+    }
+
+    fn buggy_perft(engine: &mut Engine, depth: u8) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let mut nodes = 0;
+        for move_ in buggy_moves(engine) {
+            engine.make_move(move_);
+            nodes += buggy_perft(engine, depth - 1);
+            engine.take_back();
+        }
+        nodes
+    }
+
+    #[test]
+    fn test_perft_diff_finds_the_missing_move() {
+        let mut engine = Engine::new(START_POSITION).unwrap();
+        let mut reference = Engine::new(START_POSITION).unwrap();
+        reference.set_output(Output::sink());
+        let fen = engine.fen();
+        let mut divide = |position: &str, depth: u8| {
+            reference.set_position(position)?;
+            let mut divide = vec![];
+            for move_ in buggy_moves(&mut reference) {
+                reference.make_move(move_);
+                let nodes = buggy_perft(&mut reference, depth - 1);
+                reference.take_back();
+                divide.push((reference.format_move(move_), nodes));
+            }
+            Ok(divide)
+        };
+        assert_eq!(engine.find_perft_diff(2, &mut divide), Ok(None));
+        let diff = engine.find_perft_diff(3, &mut divide).unwrap().unwrap();
+        assert_eq!(diff.moves, ["e2e4", "d7d5"]);
+        assert_eq!(diff.depth, 1);
+        assert_eq!(diff.differences, [("g1e2".to_string(), Some(1), None)]);
+        assert_eq!(engine.fen(), fen);
+    }
+
+    #[test]
+    fn test_parse_reference_counts() {
+        let divide = parse_divide("info string\ne2e4: 20\na7a8q: 3\n\nNodes searched: 23\n");
+        assert_eq!(divide, [("e2e4".to_string(), 20), ("a7a8q".to_string(), 3)]);
+        let (fen, counts) = parse_perft_epd(&format!("{} ;D1 20 ;D2 400", START_POSITION)).unwrap();
+        assert_eq!(
+            (fen.as_str(), counts),
+            (START_POSITION, vec![(1, 20), (2, 400)])
+        );
+        assert_eq!(parse_perft_epd("not a position"), None);
+    }
+
+    #[test]
+    fn test_rough_duration() {
+        assert_eq!(rough_duration(4.24), "4.2 seconds");
+        assert_eq!(rough_duration(5400.0), "1.5 hours");
+        assert_eq!(rough_duration(3.0 * 365.25 * 86400.0), "3.0 years");
+    }
+
+    #[test]
+    fn test_collision_rate_bound() {
+        let audit = |checked, mismatches| PerftAudit {
+            hits: checked,
+            checked,
+            mismatches,
+        };
+        assert_eq!(audit(0, 0).collision_rate_bound(), None);
+        // Close to the rule of three when nothing was found
+        let bound = audit(1000, 0).collision_rate_bound().unwrap();
+        assert!(bound > 0.003 && bound < 0.004);
+        assert!(audit(1000, 10).collision_rate_bound().unwrap() > 0.01);
+    }
+}
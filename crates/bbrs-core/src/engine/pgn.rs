@@ -0,0 +1,309 @@
+use std::time::Duration;
+
+use super::START_POSITION;
+
+/// A move of a PGN game along with its annotations and the alternatives given for it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PgnMove {
+    pub san: String,
+    /// Numeric annotation glyphs, with `!` read as `$1`, `?` as `$2` and so on.
+    pub nags: Vec<u8>,
+    pub comment: Option<String>,
+    /// Lines played instead of this move, each starting from the position before it.
+    pub variations: Vec<Vec<PgnMove>>,
+}
+
+impl PgnMove {
+    pub fn new(san: &str) -> Self {
+        PgnMove {
+            san: san.to_string(),
+            ..PgnMove::default()
+        }
+    }
+
+    /// The clock time left after the move, from a `[%clk 1:02:03.5]` comment command.
+    pub fn clock(&self) -> Option<Duration> {
+        let comment = self.comment.as_deref()?;
+        let (_, rest) = comment.split_once("[%clk ")?;
+        let (time, _) = rest.split_once(']')?;
+        let seconds = time.trim().split(':').try_fold(0.0, |total, part| {
+            Some(total * 60.0 + part.parse::<f64>().ok()?)
+        })?;
+        Duration::try_from_secs_f64(seconds).ok()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<PgnMove>,
+    /// `1-0`, `0-1`, `1/2-1/2` or `*`.
+    pub result: String,
+}
+
+impl PgnGame {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The position the game starts from, as given by the `FEN` tag.
+    pub fn start_position(&self) -> &str {
+        self.tag("FEN").unwrap_or(START_POSITION)
+    }
+}
+
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Nag(u8),
+    Open,
+    Close,
+    Result(String),
+    Move(String, Option<u8>),
+}
+
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+const SUFFIX_NAGS: [(&str, u8); 6] = [
+    ("!!", 3),
+    ("??", 4),
+    ("!?", 5),
+    ("?!", 6),
+    ("!", 1),
+    ("?", 2),
+];
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = text.chars().peekable();
+    let read_until = |chars: &mut std::iter::Peekable<std::str::Chars>, end: char| {
+        let mut value = String::new();
+        for ch in chars.by_ref() {
+            if ch == end {
+                return Some(value);
+            }
+            value.push(ch);
+        }
+        None
+    };
+    while let Some(&ch) = chars.peek() {
+        match ch {
+            _ if ch.is_whitespace() => {
+                chars.next();
+            }
+            '[' => {
+                chars.next();
+                let tag = read_until(&mut chars, ']').ok_or("Unterminated tag")?;
+                let (name, value) = tag.trim().split_once(' ').ok_or("Invalid tag")?;
+                let value = value.trim().trim_matches('"').replace("\\\"", "\"");
+                tokens.push(Token::Tag(name.to_string(), value));
+            }
+            '{' => {
+                chars.next();
+                let comment = read_until(&mut chars, '}').ok_or("Unterminated comment")?;
+                tokens.push(Token::Comment(comment.trim().to_string()));
+            }
+            ';' => {
+                chars.next();
+                let comment = read_until(&mut chars, '\n').unwrap_or_default();
+                tokens.push(Token::Comment(comment.trim().to_string()));
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            _ => {
+                let mut symbol = String::new();
+                while let Some(&ch) = chars.peek() {
+                    if ch.is_whitespace() || "[]{}();".contains(ch) {
+                        break;
+                    }
+                    symbol.push(ch);
+                    chars.next();
+                }
+                if let Some(nag) = symbol.strip_prefix('$') {
+                    tokens.push(Token::Nag(
+                        nag.parse()
+                            .map_err(|_| format!("Invalid NAG: {}", symbol))?,
+                    ));
+                } else if RESULTS.contains(&symbol.as_str()) {
+                    tokens.push(Token::Result(symbol));
+                } else {
+                    // Move numbers such as `12.` or `12...` may run into the move itself, but
+                    // the digits of `0-0` are not one
+                    let after_digits = symbol.trim_start_matches(|ch: char| ch.is_ascii_digit());
+                    let san = if after_digits.starts_with('.') {
+                        after_digits.trim_start_matches('.')
+                    } else {
+                        symbol.as_str()
+                    };
+                    if san.is_empty() {
+                        continue;
+                    }
+                    let (san, nag) = SUFFIX_NAGS
+                        .iter()
+                        .find_map(|&(suffix, nag)| {
+                            san.strip_suffix(suffix).map(|san| (san, Some(nag)))
+                        })
+                        .unwrap_or((san, None));
+                    tokens.push(Token::Move(san.to_string(), nag));
+                }
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Parses every game in a PGN file, with comments, NAGs and nested variations.
+///
+/// Moves are kept as written; they are only checked for legality when played.
+pub fn parse(text: &str) -> Result<Vec<PgnGame>, String> {
+    let mut games = vec![];
+    let mut game = PgnGame::default();
+    // The main line at the bottom, the innermost open variation on top
+    let mut lines: Vec<Vec<PgnMove>> = vec![vec![]];
+
+    let finish = |game: &mut PgnGame, lines: &mut Vec<Vec<PgnMove>>| {
+        if lines.len() > 1 {
+            return Err("Unclosed variation".to_string());
+        }
+        game.moves = lines.pop().unwrap_or_default();
+        lines.push(vec![]);
+        Ok(std::mem::take(game))
+    };
+
+    for token in tokenize(text)? {
+        match token {
+            Token::Tag(name, value) => {
+                // Tags after moves start the next game, even if the result was left out
+                if !lines[0].is_empty() {
+                    games.push(finish(&mut game, &mut lines)?);
+                }
+                game.tags.push((name, value));
+            }
+            Token::Move(san, nag) => {
+                let mut pgn_move = PgnMove::new(&san);
+                pgn_move.nags.extend(nag);
+                lines.last_mut().unwrap().push(pgn_move);
+            }
+            Token::Comment(comment) => {
+                if let Some(last) = lines.last_mut().unwrap().last_mut() {
+                    last.comment = Some(match last.comment.take() {
+                        Some(previous) => format!("{} {}", previous, comment),
+                        None => comment,
+                    });
+                }
+            }
+            Token::Nag(nag) => {
+                let last = lines.last_mut().unwrap().last_mut();
+                last.ok_or("NAG before any move")?.nags.push(nag);
+            }
+            Token::Open => {
+                if lines.last().unwrap().is_empty() {
+                    return Err("Variation before any move".to_string());
+                }
+                lines.push(vec![]);
+            }
+            Token::Close => {
+                if lines.len() < 2 {
+                    return Err("Unmatched )".to_string());
+                }
+                let variation = lines.pop().unwrap();
+                let last = lines.last_mut().unwrap().last_mut().unwrap();
+                last.variations.push(variation);
+            }
+            Token::Result(result) => {
+                game.result = result;
+                games.push(finish(&mut game, &mut lines)?);
+            }
+        }
+    }
+    if !game.tags.is_empty() || !lines[0].is_empty() {
+        if game.result.is_empty() {
+            game.result = "*".to_string();
+        }
+        games.push(finish(&mut game, &mut lines)?);
+    }
+    Ok(games)
+}
+
+/// The ply a game starts at, counting from White's first move, from the FEN's side to
+/// move and fullmove number.
+fn start_ply(fen: &str) -> usize {
+    let fields = fen.split_whitespace().collect::<Vec<&str>>();
+    let full_moves = fields
+        .get(5)
+        .and_then(|n| n.parse::<usize>().ok())
+        .unwrap_or(1);
+    2 * full_moves.saturating_sub(1) + usize::from(fields.get(1) == Some(&"b"))
+}
+
+fn format_line(line: &[PgnMove], ply: usize, tokens: &mut Vec<String>) {
+    // Black's moves need their number after anything that interrupts the move list
+    let mut numbered = false;
+    for (index, pgn_move) in line.iter().enumerate() {
+        let ply = ply + index;
+        // Move numbers stay on the same line as their move
+        tokens.push(if ply.is_multiple_of(2) {
+            format!("{}. {}", ply / 2 + 1, pgn_move.san)
+        } else if !numbered {
+            format!("{}... {}", ply / 2 + 1, pgn_move.san)
+        } else {
+            pgn_move.san.clone()
+        });
+        tokens.extend(pgn_move.nags.iter().map(|nag| format!("${}", nag)));
+        numbered = pgn_move.comment.is_none() && pgn_move.variations.is_empty();
+        if let Some(comment) = &pgn_move.comment {
+            tokens.push(format!("{{ {} }}", comment));
+        }
+        for variation in pgn_move.variations.iter() {
+            tokens.push("(".to_string());
+            format_line(variation, ply, tokens);
+            tokens.push(")".to_string());
+        }
+    }
+}
+
+/// Writes a game back as PGN, with its comments, NAGs and variations, wrapping the
+/// movetext at 80 columns.
+pub fn format(game: &PgnGame) -> String {
+    let mut pgn = String::new();
+    for (name, value) in game.tags.iter() {
+        pgn += &format!("[{} \"{}\"]\n", name, value.replace('"', "\\\""));
+    }
+    if !game.tags.is_empty() {
+        pgn.push('\n');
+    }
+    let mut tokens = vec![];
+    format_line(&game.moves, start_ply(game.start_position()), &mut tokens);
+    tokens.push(
+        if game.result.is_empty() {
+            "*"
+        } else {
+            &game.result
+        }
+        .to_string(),
+    );
+
+    let mut line = String::new();
+    for token in tokens {
+        let joined = !line.is_empty() && (line.ends_with('(') || token == ")");
+        if !line.is_empty() && !joined && line.len() + 1 + token.len() > 80 {
+            pgn += &line;
+            pgn.push('\n');
+            line.clear();
+        } else if !line.is_empty() && !joined {
+            line.push(' ');
+        }
+        line += &token;
+    }
+    pgn += &line;
+    pgn.push('\n');
+    pgn
+}
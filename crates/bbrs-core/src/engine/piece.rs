@@ -1,3 +1,32 @@
+/// The side to move or a piece's side, for code outside the engine. Inside it sides are
+/// the `side::WHITE` and `side::BLACK` indices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Color {
+    White,
+    Black,
+}
+
+impl Color {
+    pub fn from_side(side: u8) -> Color {
+        if side == side::WHITE {
+            Color::White
+        } else {
+            Color::Black
+        }
+    }
+
+    pub fn side(self) -> u8 {
+        match self {
+            Color::White => side::WHITE,
+            Color::Black => side::BLACK,
+        }
+    }
+
+    pub fn opponent(self) -> Color {
+        Color::from_side(self.side() ^ 1)
+    }
+}
+
 pub mod side {
     use super::range;
     use std::ops::Range;
@@ -0,0 +1,42 @@
+/// A xorshift64 generator. The same seed always gives the same playouts.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // xorshift never leaves zero
+        Rng {
+            state: if seed == 0 {
+                0x2545_F491_4F6C_DD1D
+            } else {
+                seed
+            },
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    /// A number in `0..bound`.
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+}
+
+/// How a random playout picks its moves.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PlayoutPolicy {
+    /// Every legal move is equally likely.
+    #[default]
+    Uniform,
+    /// Captures are played `CAPTURE_BIAS` out of 4 times when there are any.
+    CaptureBiased,
+}
+
+pub const CAPTURE_BIAS: usize = 3;
@@ -0,0 +1,110 @@
+use std::{
+    ops::{Deref, DerefMut},
+    sync::{Arc, Condvar, Mutex},
+};
+
+use super::{attacks::AttackTable, Engine, Output, START_POSITION};
+
+/// A fixed set of engines handed out to concurrent callers, such as the requests of a server.
+///
+/// All engines share one set of attack tables, while each keeps its own transposition
+/// table. Engines come back as they were left, so a caller should set the position and
+/// rules it needs, and clear the hash if results must not depend on earlier requests.
+pub struct EnginePool {
+    engines: Mutex<Vec<Engine>>,
+    returned: Condvar,
+    size: usize,
+}
+
+impl EnginePool {
+    /// Creates `size` engines, at least one, with `hash_megabytes` of hash each.
+    /// Their output goes to `Output::sink` until a caller sets another.
+    pub fn new(size: usize, hash_megabytes: usize) -> Self {
+        let size = size.max(1);
+        let attack_table = Arc::new(AttackTable::init());
+        let engines = (0..size)
+            .map(|_| {
+                let mut engine =
+                    Engine::with_attack_table(Arc::clone(&attack_table), START_POSITION)
+                        .expect("Invalid start position");
+                engine.set_output(Output::sink());
+                engine.set_hash_size(hash_megabytes);
+                engine
+            })
+            .collect();
+        EnginePool {
+            engines: Mutex::new(engines),
+            returned: Condvar::new(),
+            size,
+        }
+    }
+
+    /// The number of engines in the pool.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The number of engines not currently handed out.
+    pub fn available(&self) -> usize {
+        self.engines.lock().unwrap().len()
+    }
+
+    /// Takes an engine, waiting for one to be returned if all are in use.
+    pub fn get(&self) -> PooledEngine<'_> {
+        let mut engines = self.engines.lock().unwrap();
+        loop {
+            if let Some(engine) = engines.pop() {
+                return PooledEngine::new(self, engine);
+            }
+            engines = self.returned.wait(engines).unwrap();
+        }
+    }
+
+    /// Takes an engine if one is free.
+    pub fn try_get(&self) -> Option<PooledEngine<'_>> {
+        let engine = self.engines.lock().unwrap().pop()?;
+        Some(PooledEngine::new(self, engine))
+    }
+
+    fn put(&self, engine: Engine) {
+        self.engines.lock().unwrap().push(engine);
+        self.returned.notify_one();
+    }
+}
+
+/// An engine borrowed from an `EnginePool`, returned to it when dropped.
+pub struct PooledEngine<'a> {
+    pool: &'a EnginePool,
+    engine: Option<Engine>,
+}
+
+impl<'a> PooledEngine<'a> {
+    fn new(pool: &'a EnginePool, engine: Engine) -> Self {
+        PooledEngine {
+            pool,
+            engine: Some(engine),
+        }
+    }
+}
+
+impl Deref for PooledEngine<'_> {
+    type Target = Engine;
+
+    fn deref(&self) -> &Engine {
+        self.engine.as_ref().expect("Engine already returned")
+    }
+}
+
+impl DerefMut for PooledEngine<'_> {
+    fn deref_mut(&mut self) -> &mut Engine {
+        self.engine.as_mut().expect("Engine already returned")
+    }
+}
+
+impl Drop for PooledEngine<'_> {
+    fn drop(&mut self) {
+        if let Some(engine) = self.engine.take() {
+            self.pool.put(engine);
+        }
+    }
+}
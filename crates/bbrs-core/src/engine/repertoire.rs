@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use super::{
+    pgn::{self, PgnMove},
+    Engine, Output, START_POSITION,
+};
+
+/// The moves of an opening repertoire, keyed by the position they are played in.
+#[derive(Debug, Clone, Default)]
+pub struct Repertoire {
+    moves: HashMap<u64, Vec<u32>>,
+}
+
+impl Repertoire {
+    pub fn new() -> Self {
+        Repertoire::default()
+    }
+
+    /// Collects every move of every game and variation in a PGN file.
+    pub fn from_pgn(text: &str) -> Result<Self, String> {
+        let mut repertoire = Repertoire::new();
+        let mut engine = Engine::new(START_POSITION).expect("Invalid start position");
+        engine.set_output(Output::sink());
+        for game in pgn::parse(text)? {
+            let fen = game.start_position();
+            engine
+                .set_position(fen)
+                .map_err(|err| format!("{}: {}", err, fen))?;
+            repertoire.add_line(&mut engine, &game.moves)?;
+        }
+        Ok(repertoire)
+    }
+
+    fn add_line(&mut self, engine: &mut Engine, line: &[PgnMove]) -> Result<(), String> {
+        let mut played = 0;
+        let result = line.iter().try_for_each(|pgn_move| {
+            let move_ = engine
+                .parse_san(&pgn_move.san)
+                .ok_or_else(|| format!("Illegal move in repertoire: {}", pgn_move.san))?;
+            self.add(engine.position_key(), move_);
+            for variation in pgn_move.variations.iter() {
+                self.add_line(engine, variation)?;
+            }
+            engine.make_move(move_);
+            played += 1;
+            Ok(())
+        });
+        for _ in 0..played {
+            engine.take_back();
+        }
+        result
+    }
+
+    /// Adds `move_` as a repertoire move in the position with the given `Engine::position_key`.
+    pub fn add(&mut self, hash: u64, move_: u32) {
+        let moves = self.moves.entry(hash).or_default();
+        if !moves.contains(&move_) {
+            moves.push(move_);
+        }
+    }
+
+    /// The repertoire moves in the engine's current position, empty once out of book.
+    pub fn moves(&self, engine: &Engine) -> &[u32] {
+        self.moves
+            .get(&engine.position_key())
+            .map_or(&[], |moves| moves.as_slice())
+    }
+
+    /// The number of positions with repertoire moves.
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+}
@@ -0,0 +1,251 @@
+use std::fmt::Debug;
+
+use super::{
+    attacks::masks,
+    piece::{pieces::*, range, side, Color},
+    Engine,
+};
+
+/// The result of a game for the side to move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Win,
+    Loss,
+    Draw,
+}
+
+/// Whether the game is over in a position, and how it ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameState {
+    Ongoing,
+    /// Won by the given side.
+    Checkmate(Color),
+    Stalemate,
+    /// The position occurred for the third time.
+    DrawByRepetition,
+    DrawByFiftyMove,
+    DrawByInsufficientMaterial,
+    /// Decided by the variant's own win conditions, with the outcome for the side to move.
+    VariantEnd(Outcome),
+}
+
+/// The rule set a game is played under.
+///
+/// Move generation, castling and the search call into these hooks, so a variant
+/// only has to describe how it differs from standard chess.
+pub trait Rules: Debug + Send + Sync {
+    /// The variant name, as used by `UCI_Variant`.
+    fn name(&self) -> &'static str;
+
+    /// Whether castling moves are written as the king capturing its own rook (`e1h1`)
+    /// rather than by the king's destination (`e1g1`).
+    fn king_captures_rook(&self) -> bool {
+        false
+    }
+
+    /// Whether castling moves are generated at all.
+    fn allows_castling(&self) -> bool {
+        true
+    }
+
+    /// Whether captured pieces go to the capturer's pocket and can be dropped back.
+    #[cfg(feature = "crazyhouse")]
+    fn uses_pockets(&self) -> bool {
+        false
+    }
+
+    /// Filters pseudo-legal moves before they are made. King safety is checked separately.
+    fn is_move_allowed(&self, _engine: &Engine, _move_: u32) -> bool {
+        true
+    }
+
+    /// Adds moves that standard move generation does not know about.
+    fn generate_extra_moves(&self, _engine: &Engine, _moves: &mut Vec<u32>) {}
+
+    /// Checked by `make_move` after the move is made, once the mover's king is known to be safe.
+    fn is_position_legal(&self, _engine: &Engine) -> bool {
+        true
+    }
+
+    /// The outcome for the side to move if the game is already decided by the variant's
+    /// own win conditions, checked before any move is generated.
+    fn terminal_outcome(&self, _engine: &Engine) -> Option<Outcome> {
+        None
+    }
+
+    /// Whether bare kings and a lone minor piece draw, as neither side could mate.
+    fn draws_by_insufficient_material(&self) -> bool {
+        true
+    }
+}
+
+/// Standard chess.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Standard;
+
+impl Rules for Standard {
+    fn name(&self) -> &'static str {
+        "chess"
+    }
+}
+
+/// Fischer random chess. Castling rooks come from Shredder-FEN or X-FEN castling rights.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Chess960;
+
+impl Rules for Chess960 {
+    fn name(&self) -> &'static str {
+        "chess960"
+    }
+
+    fn king_captures_rook(&self) -> bool {
+        true
+    }
+}
+
+/// Crazyhouse: captured pieces change sides and can be dropped back onto the board.
+#[cfg(feature = "crazyhouse")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Crazyhouse;
+
+#[cfg(feature = "crazyhouse")]
+impl Rules for Crazyhouse {
+    fn name(&self) -> &'static str {
+        "crazyhouse"
+    }
+
+    fn uses_pockets(&self) -> bool {
+        true
+    }
+
+    fn draws_by_insufficient_material(&self) -> bool {
+        false
+    }
+}
+
+/// Horde: White's pawn horde has no king and loses once every white piece is captured.
+/// White pawns on the first rank may advance two squares.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Horde;
+
+impl Rules for Horde {
+    fn name(&self) -> &'static str {
+        "horde"
+    }
+
+    fn generate_extra_moves(&self, engine: &Engine, moves: &mut Vec<u32>) {
+        let bitboards = &engine.state.bitboards;
+        if engine.state.side != side::WHITE {
+            return;
+        }
+        let empty = !bitboards.iter().fold(0, |acc, bitboard| acc | bitboard);
+        let mut pawns = bitboards[WHITE_PAWN as usize] & masks::RANK_1;
+        while pawns != 0 {
+            let source = get_lsb!(pawns) as usize;
+            // A double push from the first rank cannot be captured en passant
+            if get_bit!(empty, source - 8) && get_bit!(empty, source - 16) {
+                moves.push(encode_move!(source, source - 16, WHITE_PAWN as usize));
+            }
+            clear_lsb!(pawns);
+        }
+    }
+
+    fn terminal_outcome(&self, engine: &Engine) -> Option<Outcome> {
+        let horde = engine.state.bitboards[range::WHITE]
+            .iter()
+            .fold(0, |acc, bitboard| acc | bitboard);
+        (horde == 0).then_some(if engine.state.side == side::WHITE {
+            Outcome::Loss
+        } else {
+            Outcome::Win
+        })
+    }
+
+    fn draws_by_insufficient_material(&self) -> bool {
+        false
+    }
+}
+
+/// Racing Kings: checks are forbidden and the first king to reach the eighth rank wins.
+/// If White gets there first, Black has one move to draw by reaching it too.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RacingKings;
+
+impl Rules for RacingKings {
+    fn name(&self) -> &'static str {
+        "racingkings"
+    }
+
+    fn allows_castling(&self) -> bool {
+        false
+    }
+
+    fn is_position_legal(&self, engine: &Engine) -> bool {
+        !engine.in_check()
+    }
+
+    fn terminal_outcome(&self, engine: &Engine) -> Option<Outcome> {
+        let bitboards = &engine.state.bitboards;
+        let white_home = bitboards[WHITE_KING as usize] & masks::RANK_8 != 0;
+        let black_home = bitboards[BLACK_KING as usize] & masks::RANK_8 != 0;
+        let to_move_is_white = engine.state.side == side::WHITE;
+        match (white_home, black_home) {
+            (true, true) => Some(Outcome::Draw),
+            (false, true) if to_move_is_white => Some(Outcome::Loss),
+            (false, true) => Some(Outcome::Win),
+            (true, false) if to_move_is_white => Some(Outcome::Win),
+            _ => None,
+        }
+    }
+
+    fn draws_by_insufficient_material(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn perft(fen: &str, rules: Arc<dyn Rules>, depth: u8) -> u64 {
+        let mut engine = Engine::new(fen).unwrap();
+        engine.set_rules(rules);
+        engine.perft_driver(depth)
+    }
+
+    #[test]
+    fn test_chess960_perft() {
+        let fen = "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9";
+        assert_eq!(perft(fen, Arc::new(Chess960), 3), 12189);
+    }
+
+    #[test]
+    fn test_horde_perft() {
+        let fen = "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1";
+        assert_eq!(perft(fen, Arc::new(Horde), 4), 23310);
+    }
+
+    #[test]
+    fn test_racing_kings_perft() {
+        let fen = "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1";
+        assert_eq!(perft(fen, Arc::new(RacingKings), 3), 11264);
+    }
+
+    #[test]
+    fn test_racing_kings_outcome() {
+        let mut engine = Engine::new("1K6/8/8/8/8/8/1k6/8 b - - 0 1").unwrap();
+        engine.set_rules(Arc::new(RacingKings));
+        assert_eq!(RacingKings.terminal_outcome(&engine), None);
+
+        engine
+            .set_position("1K6/8/8/8/8/8/1k6/8 w - - 0 1")
+            .unwrap();
+        assert_eq!(RacingKings.terminal_outcome(&engine), Some(Outcome::Win));
+
+        engine
+            .set_position("1K4k1/8/8/8/8/8/8/8 w - - 0 1")
+            .unwrap();
+        assert_eq!(RacingKings.terminal_outcome(&engine), Some(Outcome::Draw));
+    }
+}
@@ -0,0 +1,531 @@
+use std::{
+    cmp::Reverse,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// Null move pruning is only tried this far from the horizon.
+pub const NULL_MOVE_MIN_DEPTH: u8 = 3;
+/// How much shallower the search after a null move is, on top of the move itself.
+pub const NULL_MOVE_REDUCTION: u8 = 2;
+/// From this depth on, a null move cutoff is verified by a reduced search without null moves.
+pub const NULL_MOVE_VERIFICATION_DEPTH: u8 = 6;
+
+/// How often the search polls for a stop, in nodes, quiescence nodes included. Polling
+/// reads the clock, but at this interval that costs nothing measurable, while a stop is
+/// noticed well within a millisecond even where every node has dozens of captures to sort.
+pub const STOP_POLL_NODES: u64 = 512;
+
+/// The plies from the root the per-ply tables have room for. A node this deep is not
+/// searched, its static eval is returned instead, and no extension reaches past it.
+pub const MAX_PLY: usize = 64;
+
+/// From this depth on, iterations take long enough that the root reports each move as it
+/// starts searching it, so a GUI can show progress.
+pub const CURRMOVE_MIN_DEPTH: u8 = 8;
+
+/// The longest the main thread goes without an info line, checked when it polls for a stop.
+pub const INFO_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How many plies of captures quiescence search follows by default before it settles
+/// for the static eval.
+pub const QUIESCENCE_DEPTH: u8 = 32;
+
+/// Extensions along one line stop once they add up to this many plies, so checks and pawn
+/// pushes cannot carry the search past the end of the per-ply tables.
+pub const MAX_EXTENSIONS: u8 = 16;
+
+/// History scores stay within plus or minus this, as every update is scaled down the
+/// closer the entry already is to it.
+pub const MAX_HISTORY: i32 = 2048;
+
+/// From this halfmove clock on, stored scores no longer cut off the search: they may come
+/// from a path that was further from a fifty-move draw than the current one.
+pub const TT_CUTOFF_MAX_HALF_MOVES: u16 = 90;
+
+/// From `evaluate::CONVERSION_HALF_MOVES` on, a side whose static eval is at least this
+/// tries pawn moves before other quiet moves, to convert before the fifty-move rule.
+pub const CONVERSION_MARGIN: i32 = 150;
+
+/// After this many iterations in a row with the same best move, a search with room in its
+/// time limits settles for part of its budget.
+pub const STABLE_ITERATIONS: u8 = 3;
+/// How far the score may move between iterations for the best move to count as stable.
+pub const STABLE_SCORE_MARGIN: i32 = 20;
+/// A score drop of more than this from the last iteration is a fail low, and buys time.
+pub const FAIL_LOW_MARGIN: i32 = 30;
+
+/// How much of its budget a search should use, given how many iterations in a row have
+/// had the same best move and how the score changed in the last one.
+pub fn time_scale(stable_iterations: u8, score_change: i32) -> f64 {
+    if score_change < -FAIL_LOW_MARGIN {
+        1.5
+    } else if stable_iterations >= STABLE_ITERATIONS && score_change.abs() <= STABLE_SCORE_MARGIN {
+        0.5
+    } else {
+        1.0
+    }
+}
+
+/// A legal move at the root along with the statistics gathered while searching it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RootMove {
+    pub move_: u32,
+    pub score: i32,
+    pub previous_score: i32,
+    /// Nodes spent on this move over all iterations so far.
+    pub nodes: u64,
+    /// Nodes spent on this move in the last iteration.
+    pub iteration_nodes: u64,
+    pub pv: Vec<u32>,
+}
+
+impl RootMove {
+    pub fn new(move_: u32) -> Self {
+        RootMove {
+            move_,
+            score: -super::evaluate::MAX_SCORE,
+            previous_score: -super::evaluate::MAX_SCORE,
+            nodes: 0,
+            iteration_nodes: 0,
+            pv: vec![move_],
+        }
+    }
+}
+
+/// The list of root moves, updated after every iteration of the search.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RootMoves {
+    moves: Vec<RootMove>,
+}
+
+impl RootMoves {
+    pub fn new(moves: &[u32]) -> Self {
+        RootMoves {
+            moves: moves.iter().map(|&move_| RootMove::new(move_)).collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &RootMove> {
+        self.moves.iter()
+    }
+
+    pub fn get(&self, move_: u32) -> Option<&RootMove> {
+        self.moves.iter().find(|root_move| root_move.move_ == move_)
+    }
+
+    pub(crate) fn as_mut_slice(&mut self) -> &mut [RootMove] {
+        &mut self.moves
+    }
+
+    /// Orders the moves from `first` on by what the last search of them found: the best move
+    /// first, then by score. Moves that failed low have no score of their own, so among them
+    /// the ones that took the most nodes to refute go first.
+    pub fn sort_from(&mut self, first: usize) {
+        self.moves[first..]
+            .sort_by_key(|root_move| Reverse((root_move.score, root_move.iteration_nodes)));
+    }
+
+    /// Nodes spent on all root moves over all iterations so far.
+    pub fn nodes(&self) -> u64 {
+        self.moves.iter().map(|root_move| root_move.nodes).sum()
+    }
+
+    /// The share of all root nodes spent on `move_`, between 0 and 1.
+    pub fn effort(&self, move_: u32) -> Option<f64> {
+        let total = self.nodes().max(1);
+        self.get(move_)
+            .map(|root_move| root_move.nodes as f64 / total as f64)
+    }
+
+    /// The root move with the highest score from the last iteration.
+    pub fn best(&self) -> Option<&RootMove> {
+        self.moves.iter().reduce(|best, root_move| {
+            if root_move.score > best.score {
+                root_move
+            } else {
+                best
+            }
+        })
+    }
+}
+
+/// The outcome of a call to `Engine::search_position`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchResult {
+    pub best_move: u32,
+    pub score: i32,
+    pub depth: u8,
+    /// The deepest ply the last iteration reached, quiescence search included.
+    pub seldepth: u8,
+    pub nodes: u64,
+    pub pv: Vec<u32>,
+    pub root_moves: RootMoves,
+}
+
+/// One of the opponent's likely replies, see `Engine::analyse_replies`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplyAnalysis {
+    pub reply: u32,
+    /// The search restricted to the reply, so from the opponent's point of view.
+    pub result: SearchResult,
+}
+
+/// What one search may spend, see `Engine::set_limits`. The node limit and the hard time
+/// limit abort the search mid-iteration, the soft time limit only keeps the next iteration
+/// from starting. Either way the result is that of the last completed iteration.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchLimits {
+    pub nodes: Option<u64>,
+    pub soft_time: Option<Duration>,
+    pub hard_time: Option<Duration>,
+}
+
+/// The nodes and time of each completed iteration, to estimate what the next one will cost.
+#[derive(Debug, Clone, Default)]
+pub struct IterationStats {
+    iterations: Vec<(u64, Duration)>,
+}
+
+impl IterationStats {
+    pub fn push(&mut self, nodes: u64, time: Duration) {
+        self.iterations.push((nodes, time));
+    }
+
+    /// The effective branching factor: the geometric mean of how many times more nodes
+    /// each of the last two iterations took than the one before it.
+    pub fn branching_factor(&self) -> Option<f64> {
+        let ratios = self
+            .iterations
+            .windows(2)
+            .rev()
+            .take(2)
+            .map(|pair| pair[1].0 as f64 / pair[0].0.max(1) as f64)
+            .collect::<Vec<f64>>();
+        (!ratios.is_empty()).then(|| {
+            ratios
+                .iter()
+                .product::<f64>()
+                .powf(1.0 / ratios.len() as f64)
+        })
+    }
+
+    /// How long the next iteration is expected to take.
+    pub fn predict_next(&self) -> Option<Duration> {
+        let &(_, time) = self.iterations.last()?;
+        Some(time.mul_f64(self.branching_factor()?))
+    }
+}
+
+/// Move indices whose beta cutoffs are counted apart, the later ones together.
+pub const CUTOFF_INDICES: usize = 8;
+
+/// What the main thread of the last search saw of its pruning, for tuning. Only collected
+/// when switched on with `Engine::set_collect_stats`, as counting costs a little speed.
+#[derive(Debug, Clone, Default)]
+pub struct SearchStats {
+    /// Nodes of the main thread, quiescence nodes included.
+    pub nodes: u64,
+    pub quiescence_nodes: u64,
+    pub tt_probes: u64,
+    pub tt_hits: u64,
+    pub null_move_tries: u64,
+    pub null_move_cutoffs: u64,
+    /// Beta cutoffs by the index of the move that caused them, the last entry counting
+    /// every index from `CUTOFF_INDICES` on.
+    pub cutoffs: [u64; CUTOFF_INDICES],
+    pub iterations: IterationStats,
+}
+
+/// `part` as a percentage of `whole`, 0 when there is nothing.
+fn percent(part: u64, whole: u64) -> f64 {
+    100.0 * part as f64 / whole.max(1) as f64
+}
+
+impl SearchStats {
+    pub fn beta_cutoffs(&self) -> u64 {
+        self.cutoffs.iter().sum()
+    }
+
+    /// The share of beta cutoffs the first move caused, in percent: how good ordering is.
+    pub fn first_move_cutoff_rate(&self) -> f64 {
+        percent(self.cutoffs[0], self.beta_cutoffs())
+    }
+
+    pub fn tt_hit_rate(&self) -> f64 {
+        percent(self.tt_hits, self.tt_probes)
+    }
+
+    pub fn null_move_cutoff_rate(&self) -> f64 {
+        percent(self.null_move_cutoffs, self.null_move_tries)
+    }
+
+    pub fn quiescence_share(&self) -> f64 {
+        percent(self.quiescence_nodes, self.nodes)
+    }
+}
+
+impl fmt::Display for SearchStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Nodes: {}, {:.1}% in quiescence",
+            self.nodes,
+            self.quiescence_share()
+        )?;
+        writeln!(
+            f,
+            "TT hits: {:.1}% of {} probes",
+            self.tt_hit_rate(),
+            self.tt_probes
+        )?;
+        writeln!(
+            f,
+            "Null moves: {} tried, {:.1}% cut off",
+            self.null_move_tries,
+            self.null_move_cutoff_rate()
+        )?;
+        let by_index = self
+            .cutoffs
+            .iter()
+            .enumerate()
+            .map(|(index, &count)| {
+                let plus = if index == CUTOFF_INDICES - 1 { "+" } else { "" };
+                format!(
+                    "#{}{} {:.1}%",
+                    index + 1,
+                    plus,
+                    percent(count, self.beta_cutoffs())
+                )
+            })
+            .collect::<Vec<String>>();
+        writeln!(
+            f,
+            "Beta cutoffs: {}, by move {}",
+            self.beta_cutoffs(),
+            by_index.join(" ")
+        )?;
+        match self.iterations.branching_factor() {
+            Some(branching_factor) => {
+                write!(f, "Effective branching factor: {:.2}", branching_factor)
+            }
+            None => write!(f, "Effective branching factor: unknown"),
+        }
+    }
+}
+
+/// Which move ordering heuristics the search uses, so what each is worth can be measured.
+/// Captures always come first, by MVV-LVA.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MoveOrdering {
+    pub killers: bool,
+    pub history: bool,
+    /// Captures that lose material by static exchange evaluation go after quiet moves.
+    pub see: bool,
+    /// Pushes of passed pawns to the sixth and seventh ranks go before other quiet moves.
+    pub passed_pawns: bool,
+    /// Pawn moves go before other quiet moves when a better side nears the fifty-move rule.
+    pub conversion: bool,
+}
+
+impl Default for MoveOrdering {
+    fn default() -> Self {
+        MoveOrdering {
+            killers: true,
+            history: true,
+            see: true,
+            passed_pawns: true,
+            conversion: true,
+        }
+    }
+}
+
+impl MoveOrdering {
+    /// Everything on, then each heuristic off in turn, then none of them.
+    pub fn experiments() -> Vec<(&'static str, MoveOrdering)> {
+        let all = MoveOrdering::default();
+        vec![
+            ("all", all),
+            (
+                "no killers",
+                MoveOrdering {
+                    killers: false,
+                    ..all
+                },
+            ),
+            (
+                "no history",
+                MoveOrdering {
+                    history: false,
+                    ..all
+                },
+            ),
+            ("no see", MoveOrdering { see: false, ..all }),
+            (
+                "no passed pawns",
+                MoveOrdering {
+                    passed_pawns: false,
+                    ..all
+                },
+            ),
+            (
+                "no conversion",
+                MoveOrdering {
+                    conversion: false,
+                    ..all
+                },
+            ),
+            (
+                "mvv-lva only",
+                MoveOrdering {
+                    killers: false,
+                    history: false,
+                    see: false,
+                    passed_pawns: false,
+                    conversion: false,
+                },
+            ),
+        ]
+    }
+}
+
+/// Per-ply data for the line currently being searched.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StackEntry {
+    /// The static evaluation of the node, `None` when in check.
+    pub static_eval: Option<i32>,
+    /// Plies added by extensions on the way to the node, itself included.
+    pub extensions: u8,
+}
+
+/// Move ordering tables and the principal variation, kept on the engine between searches.
+#[derive(Debug, Clone)]
+pub struct SearchState {
+    pub killer_moves: [[u32; MAX_PLY]; 2],
+    /// Indexed by piece and target square.
+    pub history_moves: [[i32; 64]; 12],
+    pub pv_length: [u32; MAX_PLY],
+    pub pv_table: [[u32; MAX_PLY]; MAX_PLY],
+    pub stack: [StackEntry; MAX_PLY],
+}
+
+impl Default for SearchState {
+    fn default() -> Self {
+        SearchState {
+            killer_moves: [[0; MAX_PLY]; 2],
+            history_moves: [[0; 64]; 12],
+            pv_length: [0; MAX_PLY],
+            pv_table: [[0; MAX_PLY]; MAX_PLY],
+            stack: [StackEntry::default(); MAX_PLY],
+        }
+    }
+}
+
+impl SearchState {
+    /// Prepares for a search from a new root. Killers, PV and stack are indexed by ply from
+    /// the old root, so they are cleared, while history is halved: it still orders the first
+    /// iterations well, but no longer outweighs what this search learns.
+    pub fn new_search(&mut self) {
+        self.killer_moves = [[0; MAX_PLY]; 2];
+        self.pv_length = [0; MAX_PLY];
+        self.pv_table = [[0; MAX_PLY]; MAX_PLY];
+        self.stack = [StackEntry::default(); MAX_PLY];
+        self.history_moves
+            .iter_mut()
+            .flatten()
+            .for_each(|entry| *entry /= 2);
+    }
+
+    /// Forgets everything, for a new game.
+    pub fn clear(&mut self) {
+        *self = SearchState::default();
+    }
+}
+
+/// Cancels a search started with `Engine::search` from another thread, or once a deadline passes.
+///
+/// Clones share the same flag. Unlike the UCI stop flag, a cancelled search returns even
+/// before its first iteration completes, in which case the result has no best move.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        CancellationToken::default()
+    }
+
+    /// A token that also cancels itself at `deadline`.
+    pub fn with_deadline(deadline: Instant) -> Self {
+        CancellationToken {
+            deadline: Some(deadline),
+            ..CancellationToken::default()
+        }
+    }
+
+    /// A token that also cancels itself once `timeout` has passed from now.
+    pub fn with_timeout(timeout: Duration) -> Self {
+        CancellationToken::with_deadline(Instant::now() + timeout)
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Engine;
+    use super::*;
+
+    /// Either queen can keep checking the other king.
+    const CHECKS: &str = "4k3/8/8/8/8/8/3Q4/q5K1 w - - 0 1";
+
+    #[test]
+    fn test_extensions_stop_at_the_caps() {
+        let mut engine = Engine::new(CHECKS).unwrap();
+        assert_eq!(engine.extend(0, 10, true), 1);
+        // The extended depth must end short of the last ply
+        assert_eq!(engine.extend(50, 12, true), 1);
+        assert_eq!(engine.extend(50, 13, true), 0);
+        // And the line may only be extended so often
+        engine.search_state.stack[19].extensions = MAX_EXTENSIONS - 1;
+        assert_eq!(engine.extend(20, 10, true), 1);
+        engine.search_state.stack[20].extensions = MAX_EXTENSIONS;
+        assert_eq!(engine.extend(21, 10, true), 0);
+        assert_eq!(engine.search_state.stack[21].extensions, MAX_EXTENSIONS);
+    }
+
+    #[test]
+    fn test_checks_stay_within_the_tables() {
+        let mut engine = Engine::new(CHECKS).unwrap();
+        engine.set_node_limit(Some(200_000));
+        engine.negamax(MAX_PLY as u8 - 1, -1, 0);
+        assert!(engine.sel_depth as usize > MAX_PLY / 2);
+        assert!((engine.sel_depth as usize) < MAX_PLY);
+        let stack = &engine.search_state.stack;
+        assert!(stack.iter().any(|entry| entry.extensions > 0));
+        assert!(stack.iter().all(|entry| entry.extensions <= MAX_EXTENSIONS));
+    }
+}
@@ -0,0 +1,47 @@
+use std::time::Duration;
+
+/// Perft counts every build must reproduce: the start position and the well-known
+/// positions that exercise castling, en passant and promotions.
+pub const PERFT_CHECKS: [(&str, u8, u64); 5] = [
+    (
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        3,
+        8902,
+    ),
+    (
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        3,
+        97862,
+    ),
+    ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 4, 43238),
+    (
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        3,
+        9467,
+    ),
+    (
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        3,
+        62379,
+    ),
+];
+
+/// Plies of every legal line from the perft positions replayed to check the hash.
+pub const ZOBRIST_DEPTH: u8 = 2;
+
+/// How long the bench part of a self-check searches, split between the bench positions.
+pub const BENCH_TIME: Duration = Duration::from_secs(1);
+
+/// The outcome of one subsystem in `Engine::self_check`: what was checked when it
+/// passed, what went wrong when it failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelfCheck {
+    pub name: &'static str,
+    pub result: Result<String, String>,
+}
+
+impl SelfCheck {
+    pub fn passed(&self) -> bool {
+        self.result.is_ok()
+    }
+}
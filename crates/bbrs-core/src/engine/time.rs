@@ -0,0 +1,163 @@
+use std::time::{Duration, Instant};
+
+use super::clock;
+
+/// Time kept back from a budget taken from the clock, for the delay between the
+/// search ending and the GUI stopping our clock.
+pub const MOVE_OVERHEAD: Duration = Duration::from_millis(30);
+
+/// Tracks how long the current search may run.
+///
+/// The budget is a soft limit: no iteration starts once it is used up, nor one expected
+/// to end after it. The hard limit aborts the search even in the middle of an iteration,
+/// which then gives way to the last completed one.
+///
+/// The search start is when `go` was received, while the clock start is when our
+/// clock actually began running. The two only differ when pondering: the clock
+/// starts on `ponderhit`, and `count_ponder_time` decides which of them the budget
+/// is measured from.
+#[derive(Debug, Clone)]
+pub struct TimeManager {
+    search_start: Instant,
+    clock_start: Instant,
+    budget: Option<Duration>,
+    hard_limit: Option<Duration>,
+    /// What the budget is multiplied by, see `scale_budget`.
+    scale: f64,
+    pondering: bool,
+    count_ponder_time: bool,
+}
+
+impl Default for TimeManager {
+    fn default() -> Self {
+        TimeManager::new(None)
+    }
+}
+
+impl TimeManager {
+    /// A search limited to `budget`, or unlimited if `None`, that stops as soon as the
+    /// budget is used up.
+    pub fn new(budget: Option<Duration>) -> Self {
+        TimeManager::with_limits(budget, budget)
+    }
+
+    /// A search that starts no iteration after the `soft` limit, but only aborts one at
+    /// the `hard` limit.
+    pub fn with_limits(soft: Option<Duration>, hard: Option<Duration>) -> Self {
+        let now = Instant::now();
+        TimeManager {
+            search_start: now,
+            clock_start: now,
+            budget: soft,
+            hard_limit: hard,
+            scale: 1.0,
+            pondering: false,
+            count_ponder_time: false,
+        }
+    }
+
+    /// A search with the share of our clock's `remaining` time that `clock::allot` gives
+    /// the next move as its budget, less `MOVE_OVERHEAD`. An iteration under way may run on
+    /// to three times that, but never past half of what is left, unless the budget is more.
+    pub fn for_clock(remaining: Duration, increment: Duration, moves_to_go: Option<u32>) -> Self {
+        let remaining = remaining.saturating_sub(MOVE_OVERHEAD);
+        let budget = clock::allot(remaining, increment, moves_to_go);
+        let hard_limit = (budget * 3).min(remaining / 2).max(budget);
+        TimeManager::with_limits(Some(budget), Some(hard_limit))
+    }
+
+    /// A ponder search whose `budget` only starts to apply after `ponderhit`.
+    pub fn ponder(budget: Option<Duration>) -> Self {
+        TimeManager::new(budget).pondering(true)
+    }
+
+    /// Whether the limits only start to apply after `ponderhit`.
+    pub fn pondering(mut self, pondering: bool) -> Self {
+        self.pondering = pondering;
+        self
+    }
+
+    /// Whether time spent pondering is charged against the budget after `ponderhit`.
+    pub fn count_ponder_time(mut self, count: bool) -> Self {
+        self.count_ponder_time = count;
+        self
+    }
+
+    /// Marks the start of the search.
+    pub fn start(&mut self) {
+        self.search_start = Instant::now();
+        self.clock_start = self.search_start;
+    }
+
+    /// Converts the ponder search into a normal one, starting our clock.
+    pub fn ponderhit(&mut self) {
+        if self.pondering {
+            self.pondering = false;
+            self.clock_start = Instant::now();
+        }
+    }
+
+    pub fn is_pondering(&self) -> bool {
+        self.pondering
+    }
+
+    /// The soft limit, as scaled by `scale_budget`.
+    pub fn budget(&self) -> Option<Duration> {
+        let budget = self.budget?.mul_f64(self.scale);
+        Some(self.hard_limit.map_or(budget, |limit| budget.min(limit)))
+    }
+
+    /// Multiplies the budget by `scale`, but never beyond the hard limit: below 1 when the
+    /// search looks settled, above when it is in trouble. A budget that is also the hard
+    /// limit, as for `go movetime`, is fixed.
+    pub fn scale_budget(&mut self, scale: f64) {
+        if self.hard_limit != self.budget {
+            self.scale = scale;
+        }
+    }
+
+    pub fn hard_limit(&self) -> Option<Duration> {
+        self.hard_limit
+    }
+
+    /// Time since `go` was received, including any time spent pondering.
+    pub fn search_elapsed(&self) -> Duration {
+        self.search_start.elapsed()
+    }
+
+    /// Time charged against the budget.
+    pub fn elapsed(&self) -> Duration {
+        if self.count_ponder_time {
+            self.search_start.elapsed()
+        } else {
+            self.clock_start.elapsed()
+        }
+    }
+
+    /// Budget left, or `None` if the search is unlimited or still pondering.
+    pub fn remaining(&self) -> Option<Duration> {
+        if self.pondering {
+            return None;
+        }
+        self.budget()
+            .map(|budget| budget.saturating_sub(self.elapsed()))
+    }
+
+    /// Whether an iteration expected to take `predicted` can finish within the budget.
+    pub fn fits(&self, predicted: Duration) -> bool {
+        self.remaining()
+            .is_none_or(|remaining| predicted <= remaining)
+    }
+
+    /// Whether the budget is used up, so no further iteration should start. Never true
+    /// while pondering.
+    pub fn is_soft_time_up(&self) -> bool {
+        !self.pondering && self.budget().is_some_and(|budget| self.elapsed() >= budget)
+    }
+
+    /// Whether the hard limit is reached and the search must stop at once. Never true
+    /// while pondering.
+    pub fn is_time_up(&self) -> bool {
+        !self.pondering && self.hard_limit.is_some_and(|limit| self.elapsed() >= limit)
+    }
+}
@@ -0,0 +1,117 @@
+use super::{attacks::AttackTable, board::Square};
+
+fn piece_name(letter: char) -> Option<&'static str> {
+    match letter {
+        'K' => Some("King"),
+        'Q' => Some("Queen"),
+        'R' => Some("Rook"),
+        'B' => Some("Bishop"),
+        'N' => Some("Knight"),
+        _ => None,
+    }
+}
+
+/// Reads a move written in SAN aloud, e.g. `Nbxd2+` as "Knight b takes d2, check".
+pub fn announce(san: &str) -> String {
+    let (san, suffix) = match san.strip_suffix('#').or_else(|| san.strip_suffix('+')) {
+        Some(stripped) if san.ends_with('#') => (stripped, ", checkmate"),
+        Some(stripped) => (stripped, ", check"),
+        None => (san, ""),
+    };
+    let text = match san {
+        "O-O" => "Castles kingside".to_string(),
+        "O-O-O" => "Castles queenside".to_string(),
+        _ if san.contains('@') => {
+            let (piece, target) = san.split_once('@').unwrap_or_default();
+            let piece = piece.chars().next().and_then(piece_name).unwrap_or("Pawn");
+            format!("{} drops on {}", piece, target)
+        }
+        _ => {
+            let (san, promotion) = match san.split_once('=') {
+                Some((san, piece)) => (san, piece.chars().next().and_then(piece_name)),
+                None => (san, None),
+            };
+            let mut chars = san.chars().peekable();
+            let mut words = vec![];
+            if let Some(name) = chars.peek().copied().and_then(piece_name) {
+                words.push(name.to_string());
+                chars.next();
+            }
+            let rest = chars.collect::<String>();
+            // The target is always the last two characters, anything before it disambiguates
+            let (from, to) = rest.split_at(rest.len().saturating_sub(2));
+            let (from, capture) = match from.strip_suffix('x') {
+                Some(from) => (from, true),
+                None => (from, false),
+            };
+            if words.is_empty() {
+                words.push("Pawn".to_string());
+            }
+            if !from.is_empty() {
+                words.push(from.to_string());
+            }
+            words.push(if capture { "takes" } else { "to" }.to_string());
+            words.push(to.to_string());
+            if let Some(promotion) = promotion {
+                words.push(format!("promotes to {}", promotion.to_lowercase()));
+            }
+            words.join(" ")
+        }
+    };
+    format!("{}{}", text, suffix)
+}
+
+/// The squares of a shortest knight path from `from` to `to`, both included.
+pub fn knight_path(attack_table: &AttackTable, from: Square, to: Square) -> Vec<Square> {
+    let mut previous = [None; 64];
+    let mut queue = std::collections::VecDeque::from([from.index()]);
+    let mut visited = bitboard!(from.index());
+    while let Some(square) = queue.pop_front() {
+        if square == to.index() {
+            break;
+        }
+        let mut moves = attack_table.get_knight_attacks(square as usize) & !visited;
+        visited |= moves;
+        while moves != 0 {
+            let next = get_lsb!(moves) as u8;
+            previous[next as usize] = Some(square);
+            queue.push_back(next);
+            clear_lsb!(moves);
+        }
+    }
+    let mut path = vec![to];
+    while let Some(square) = previous[path.last().unwrap().index() as usize] {
+        path.push(Square::ALL[square as usize]);
+    }
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_announce() {
+        assert_eq!(announce("e4"), "Pawn to e4");
+        assert_eq!(announce("exd5"), "Pawn e takes d5");
+        assert_eq!(announce("Nbxd2+"), "Knight b takes d2, check");
+        assert_eq!(announce("b8=Q#"), "Pawn to b8 promotes to queen, checkmate");
+        assert_eq!(announce("O-O-O"), "Castles queenside");
+        assert_eq!(announce("N@f3"), "Knight drops on f3");
+    }
+
+    #[test]
+    fn test_knight_path() {
+        let attack_table = AttackTable::init();
+        let path = knight_path(&attack_table, Square::A1, Square::H8);
+        assert_eq!(path.len(), 7);
+        assert_eq!((path[0], path[6]), (Square::A1, Square::H8));
+        // A corner to its diagonal neighbour is the longest short trip
+        assert_eq!(knight_path(&attack_table, Square::A1, Square::B2).len(), 5);
+        assert_eq!(
+            knight_path(&attack_table, Square::E4, Square::E4),
+            [Square::E4]
+        );
+    }
+}
@@ -0,0 +1,348 @@
+use std::{
+    mem,
+    sync::atomic::{AtomicU64, AtomicU8, Ordering},
+};
+
+use super::{
+    checkpoint::{self, Reader},
+    evaluate::{is_mate_score, MATE_SCORE},
+};
+
+const MAGIC: &[u8; 8] = b"BBRSTT01";
+/// The size of an entry in a saved table.
+const ENTRY_BYTES: usize = 22;
+
+/// The size of a new engine's table, in megabytes.
+pub const DEFAULT_HASH_MEGABYTES: usize = 16;
+/// The largest table the engine allows, in megabytes.
+pub const MAX_HASH_MEGABYTES: usize = 4096;
+
+/// How many plies shallower an entry counts for every search since it was stored, when
+/// deciding whether a new result may replace it.
+pub const AGE_PENALTY: i32 = 4;
+
+/// What the stored score says about the true score of the position.
+pub mod flags {
+    /// The score is exact.
+    pub const EXACT: u8 = 0;
+    /// No move raised alpha: the score is an upper bound.
+    pub const ALPHA: u8 = 1;
+    /// A move failed high: the score is a lower bound.
+    pub const BETA: u8 = 2;
+}
+
+/// Stored in place of the static eval when the side to move was in check.
+const NO_EVAL: i32 = i32::MIN;
+
+/// Converts a score from the root's point of view to the node's at `ply`, as stored.
+///
+/// Mate scores count plies from the root, but a position can be reached at different
+/// plies, so they are stored counting from the node itself.
+pub fn score_to_tt(score: i32, ply: usize) -> i32 {
+    match score {
+        score if is_mate_score(score) && score > 0 => score + ply as i32,
+        score if is_mate_score(score) => score - ply as i32,
+        score => score,
+    }
+}
+
+/// Converts a stored score back to the root's point of view, for a node at `ply`.
+pub fn score_from_tt(score: i32, ply: usize) -> i32 {
+    match score {
+        score if is_mate_score(score) && score > 0 => (score - ply as i32).min(MATE_SCORE),
+        score if is_mate_score(score) => (score + ply as i32).max(-MATE_SCORE),
+        score => score,
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Entry {
+    key: u64,
+    pub best_move: u32,
+    pub score: i32,
+    eval: i32,
+    pub depth: u8,
+    pub flag: u8,
+    /// The search that stored the entry, see `TranspositionTable::new_search`.
+    generation: u8,
+}
+
+impl Entry {
+    /// The static evaluation of the position, if it was computed.
+    pub fn eval(&self) -> Option<i32> {
+        (self.eval != NO_EVAL).then_some(self.eval)
+    }
+}
+
+impl Entry {
+    fn pack(&self) -> [u64; 2] {
+        [
+            self.best_move as u64 | (self.score as u32 as u64) << 32,
+            self.eval as u32 as u64
+                | (self.depth as u64) << 32
+                | (self.flag as u64) << 40
+                | (self.generation as u64) << 48,
+        ]
+    }
+
+    fn unpack(key: u64, data: [u64; 2]) -> Self {
+        Entry {
+            key,
+            best_move: data[0] as u32,
+            score: (data[0] >> 32) as i32,
+            eval: data[1] as i32,
+            depth: (data[1] >> 32) as u8,
+            flag: (data[1] >> 40) as u8,
+            generation: (data[1] >> 48) as u8,
+        }
+    }
+}
+
+/// An entry as stored, shared between search threads without locks: the key is kept
+/// xored with the data, so an entry torn by two threads writing at once no longer
+/// matches its key and reads as a miss.
+#[derive(Debug, Default)]
+struct Slot {
+    check: AtomicU64,
+    data: [AtomicU64; 2],
+}
+
+impl Slot {
+    fn load(&self) -> Entry {
+        let data = self
+            .data
+            .each_ref()
+            .map(|word| word.load(Ordering::Relaxed));
+        let key = self.check.load(Ordering::Relaxed) ^ data[0] ^ data[1];
+        Entry::unpack(key, data)
+    }
+
+    fn save(&self, entry: &Entry) {
+        let data = entry.pack();
+        self.check
+            .store(entry.key ^ data[0] ^ data[1], Ordering::Relaxed);
+        for (word, value) in self.data.iter().zip(data) {
+            word.store(value, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Results of earlier searches, keyed by the Zobrist hash of the position. Entries are
+/// read and written through a shared reference, so search threads can share one table.
+#[derive(Debug)]
+pub struct TranspositionTable {
+    entries: Vec<Slot>,
+    /// The size the table was created with.
+    megabytes: usize,
+    generation: AtomicU8,
+}
+
+impl TranspositionTable {
+    /// The bytes each entry takes.
+    pub const ENTRY_SIZE: usize = mem::size_of::<Slot>();
+
+    /// Creates a table that takes up about `megabytes` of memory.
+    pub fn new(megabytes: usize) -> Self {
+        let len = (megabytes * 1024 * 1024 / mem::size_of::<Slot>()).max(1);
+        TranspositionTable {
+            entries: (0..len).map(|_| Slot::default()).collect(),
+            megabytes,
+            generation: AtomicU8::new(0),
+        }
+    }
+
+    /// The size the table was created with, in megabytes.
+    pub fn megabytes(&self) -> usize {
+        self.megabytes
+    }
+
+    /// Starts a new search: entries stored before it age, so that once it fills up, the
+    /// table gives way to what the current search finds rather than keeping results from
+    /// positions long gone. Loaded entries start out as if stored by the first search.
+    pub fn new_search(&self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn generation(&self) -> u8 {
+        self.generation.load(Ordering::Relaxed)
+    }
+
+    /// The table as a file: the entries behind a header and followed by a checksum.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend((self.entries.len() as u64).to_le_bytes());
+        for entry in self.entries.iter().map(Slot::load) {
+            bytes.extend(entry.key.to_le_bytes());
+            bytes.extend(entry.best_move.to_le_bytes());
+            bytes.extend(entry.score.to_le_bytes());
+            bytes.extend(entry.eval.to_le_bytes());
+            bytes.extend([entry.depth, entry.flag]);
+        }
+        bytes.extend(checkpoint::checksum(&bytes).to_le_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader(checkpoint::verify(bytes, MAGIC)?);
+        let len = reader.u64()? as usize;
+        if len == 0 || reader.0.len() != len * ENTRY_BYTES {
+            return Err("Invalid transposition table size".to_string());
+        }
+        let entries = (0..len)
+            .map(|_| {
+                let slot = Slot::default();
+                slot.save(&Entry {
+                    key: reader.u64()?,
+                    best_move: reader.u32()?,
+                    score: reader.i32()?,
+                    eval: reader.i32()?,
+                    depth: reader.u8()?,
+                    flag: reader.u8()?,
+                    generation: 0,
+                });
+                Ok(slot)
+            })
+            .collect::<Result<Vec<Slot>, String>>()?;
+        let megabytes = (len * TranspositionTable::ENTRY_SIZE).div_ceil(1024 * 1024);
+        Ok(TranspositionTable {
+            entries,
+            megabytes,
+            generation: AtomicU8::new(0),
+        })
+    }
+
+    pub fn clear(&self) {
+        self.entries
+            .iter()
+            .for_each(|slot| slot.save(&Entry::default()));
+        self.generation.store(0, Ordering::Relaxed);
+    }
+
+    /// Bytes taken by the entries.
+    pub fn memory(&self) -> usize {
+        mem::size_of_val(self.entries.as_slice())
+    }
+
+    /// How full the table is with entries of the current search, in permille, judged from
+    /// its first thousand entries.
+    pub fn hashfull(&self) -> u32 {
+        let generation = self.generation();
+        let sample = &self.entries[..self.entries.len().min(1000)];
+        let used = sample
+            .iter()
+            .map(Slot::load)
+            .filter(|entry| entry.key != 0 && entry.generation == generation)
+            .count();
+        (used * 1000 / sample.len()) as u32
+    }
+
+    fn index(&self, key: u64) -> usize {
+        ((key as u128 * self.entries.len() as u128) >> 64) as usize
+    }
+
+    pub fn probe(&self, key: u64) -> Option<Entry> {
+        let entry = self.entries[self.index(key)].load();
+        (entry.key == key && key != 0).then_some(entry)
+    }
+
+    /// Stores a search result. Results for the same position always replace the old one,
+    /// other positions only replace it when searched at least as deep, counting the old
+    /// entry `AGE_PENALTY` plies shallower for every search since it was stored.
+    pub fn store(
+        &self,
+        key: u64,
+        depth: u8,
+        flag: u8,
+        score: i32,
+        eval: Option<i32>,
+        best_move: u32,
+    ) {
+        let slot = &self.entries[self.index(key)];
+        let entry = slot.load();
+        let generation = self.generation();
+        let age = generation.wrapping_sub(entry.generation) as i32;
+        if entry.key == key || depth as i32 >= entry.depth as i32 - age * AGE_PENALTY {
+            slot.save(&Entry {
+                key,
+                best_move,
+                score,
+                eval: eval.unwrap_or(NO_EVAL),
+                depth,
+                flag,
+                generation,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mate_scores_are_stored_relative_to_the_node() {
+        let tt = TranspositionTable::new(1);
+        // Found at ply 3: mate two plies after the node, five from the root
+        let mate = MATE_SCORE - 5;
+        tt.store(42, 4, flags::EXACT, score_to_tt(mate, 3), None, 0);
+        let entry = tt.probe(42).unwrap();
+        assert_eq!(entry.score, MATE_SCORE - 2);
+        // Reached again at ply 1 the mate is two plies closer to the root
+        assert_eq!(score_from_tt(entry.score, 1), MATE_SCORE - 3);
+        assert_eq!(score_from_tt(score_to_tt(-mate, 3), 1), -(MATE_SCORE - 3));
+        assert_eq!(score_from_tt(score_to_tt(123, 3), 1), 123);
+    }
+
+    #[test]
+    fn test_deep_entries_give_way_as_they_age() {
+        // A one-entry table, so every position shares the slot
+        let tt = TranspositionTable::new(0);
+        tt.store(1, 10, flags::EXACT, 0, None, 0);
+        tt.store(2, 9, flags::EXACT, 0, None, 0);
+        assert!(tt.probe(1).is_some());
+        assert_eq!(tt.hashfull(), 1000);
+
+        tt.new_search();
+        assert_eq!(tt.hashfull(), 0);
+        tt.store(2, 5, flags::EXACT, 0, None, 0);
+        assert!(tt.probe(1).is_some());
+        tt.store(2, 6, flags::EXACT, 0, None, 0);
+        assert_eq!(tt.probe(2).unwrap().depth, 6);
+        assert_eq!(tt.hashfull(), 1000);
+
+        // The same position is always replaced, even by a shallower result
+        tt.store(2, 1, flags::ALPHA, 0, None, 0);
+        assert_eq!(tt.probe(2).unwrap().depth, 1);
+    }
+
+    #[test]
+    fn test_round_trip_and_corruption() {
+        let tt = TranspositionTable::new(1);
+        tt.store(42, 7, flags::BETA, 150, Some(-20), 0x1234);
+        let bytes = tt.to_bytes();
+        let entry = TranspositionTable::from_bytes(&bytes)
+            .unwrap()
+            .probe(42)
+            .unwrap();
+        assert_eq!(
+            (
+                entry.depth,
+                entry.flag,
+                entry.score,
+                entry.eval(),
+                entry.best_move
+            ),
+            (7, flags::BETA, 150, Some(-20), 0x1234)
+        );
+
+        assert_eq!(
+            TranspositionTable::from_bytes(&bytes).unwrap().megabytes(),
+            1
+        );
+
+        let mut corrupted = bytes.clone();
+        corrupted[20] ^= 1;
+        assert!(TranspositionTable::from_bytes(&corrupted).is_err());
+        assert!(TranspositionTable::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
@@ -0,0 +1,129 @@
+use super::{piece::side, EngineState};
+
+/// Random keys for every feature of a position that is hashed.
+struct Keys {
+    pieces: [[u64; 64]; 12],
+    castling: [u64; 16],
+    en_passant: [u64; 8],
+    side: u64,
+    /// Indexed by side, piece type and number of pieces in hand. Empty pockets have a zero
+    /// key, so positions without pieces in hand hash the same with or without the feature.
+    #[cfg(feature = "crazyhouse")]
+    pockets: [[[u64; 17]; 5]; 2],
+}
+
+/// xorshift64, so the keys are the same on every run and can be built at compile time.
+const fn next(state: u64) -> u64 {
+    let state = state ^ (state << 13);
+    let state = state ^ (state >> 7);
+    state ^ (state << 17)
+}
+
+const fn generate() -> Keys {
+    let mut state = 0x2545_F491_4F6C_DD1D;
+    let mut keys = Keys {
+        pieces: [[0; 64]; 12],
+        castling: [0; 16],
+        en_passant: [0; 8],
+        side: 0,
+        #[cfg(feature = "crazyhouse")]
+        pockets: [[[0; 17]; 5]; 2],
+    };
+    let mut piece = 0;
+    while piece < 12 {
+        let mut square = 0;
+        while square < 64 {
+            state = next(state);
+            keys.pieces[piece][square] = state;
+            square += 1;
+        }
+        piece += 1;
+    }
+    let mut index = 0;
+    while index < 16 {
+        state = next(state);
+        keys.castling[index] = state;
+        index += 1;
+    }
+    let mut file = 0;
+    while file < 8 {
+        state = next(state);
+        keys.en_passant[file] = state;
+        file += 1;
+    }
+    state = next(state);
+    keys.side = state;
+    #[cfg(feature = "crazyhouse")]
+    {
+        let mut side = 0;
+        while side < 2 {
+            let mut piece_type = 0;
+            while piece_type < 5 {
+                let mut count = 1;
+                while count < 17 {
+                    state = next(state);
+                    keys.pockets[side][piece_type][count] = state;
+                    count += 1;
+                }
+                piece_type += 1;
+            }
+            side += 1;
+        }
+    }
+    keys
+}
+
+static KEYS: Keys = generate();
+
+pub fn piece(piece: u8, square: u8) -> u64 {
+    KEYS.pieces[piece as usize][square as usize]
+}
+
+pub fn castling(rights: u8) -> u64 {
+    KEYS.castling[rights as usize]
+}
+
+/// Only the file of the en passant square is hashed, the rank follows from the side to move.
+pub fn en_passant(square: u8) -> u64 {
+    KEYS.en_passant[(square % 8) as usize]
+}
+
+pub fn side() -> u64 {
+    KEYS.side
+}
+
+/// The combined key for the pieces `side` holds in hand.
+#[cfg(feature = "crazyhouse")]
+pub fn pocket(side: usize, pocket: &[u8; 5]) -> u64 {
+    pocket
+        .iter()
+        .enumerate()
+        .fold(0, |hash, (piece_type, &count)| {
+            hash ^ KEYS.pockets[side][piece_type][(count as usize).min(16)]
+        })
+}
+
+/// Hashes the position from scratch. `make_move` keeps `EngineState::hash` up to date
+/// incrementally, this is for setting up a position and for checking it.
+pub fn hash(state: &EngineState) -> u64 {
+    let mut hash = 0;
+    for (piece, &bitboard) in state.bitboards.iter().enumerate() {
+        let mut bitboard = bitboard;
+        while bitboard != 0 {
+            hash ^= self::piece(piece as u8, get_lsb!(bitboard) as u8);
+            clear_lsb!(bitboard);
+        }
+    }
+    hash ^= castling(state.castling);
+    if let Some(square) = state.en_passant {
+        hash ^= en_passant(square);
+    }
+    if state.side == side::BLACK {
+        hash ^= self::side();
+    }
+    #[cfg(feature = "crazyhouse")]
+    for (side, pieces) in state.pockets.iter().enumerate() {
+        hash ^= pocket(side, pieces);
+    }
+    hash
+}
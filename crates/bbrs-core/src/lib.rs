@@ -0,0 +1,8 @@
+// The library only writes through `engine::Output`, so whoever embeds it decides where
+// output goes. Printing to the console is left to the binaries and the `debug` helpers.
+#![deny(clippy::print_stdout, clippy::print_stderr, clippy::dbg_macro)]
+
+pub mod engine;
+pub mod prelude;
+#[cfg(feature = "debug")]
+mod utils;
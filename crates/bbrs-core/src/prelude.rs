@@ -0,0 +1,7 @@
+//! What a program embedding the engine usually needs, in one import:
+//! `use bbrs_core::prelude::*;`. Everything else stays reachable through `bbrs_core::engine`.
+
+pub use crate::engine::{
+    Color, Engine, EngineState as Position, Move, Output, SearchLimits, SearchResult, Square,
+    START_POSITION,
+};
@@ -0,0 +1,74 @@
+use bbrs_core::engine::{info::Score, pgn, Classification, Engine, Output, START_POSITION};
+use std::time::Duration;
+
+#[test]
+fn test_analyze_game() {
+    let text = "1. e4 {[%clk 0:05:00]} e5 {[%clk 0:04:58]} 2. Qh5 {[%clk 0:04:55]} \
+                Nc6 {[%clk 0:04:50.5]} 3. Bc4 Nf6 4. Qxf7# 1-0";
+    let game = pgn::parse(text).unwrap().remove(0);
+    let mut engine = Engine::new(START_POSITION).unwrap();
+    engine.set_output(Output::sink());
+    let analysis = engine.analyze_game(&game, 2).unwrap();
+    assert_eq!(engine.fen(), START_POSITION);
+
+    assert_eq!(analysis.plies.len(), 7);
+    assert_eq!(analysis.scores.len(), 8);
+    // Black missed the mate threat with Nf6, the engine would have defended
+    assert_ne!(analysis.best_move(5), Some("Nf6"));
+    assert!(matches!(
+        Score::from_search(analysis.scores[6]),
+        Score::Mate(1)
+    ));
+    assert!(matches!(
+        Score::from_search(analysis.scores[7]),
+        Score::Mate(0)
+    ));
+
+    assert_eq!(analysis.plies[2].clock, Some(Duration::from_secs(295)));
+    assert_eq!(analysis.plies[2].clock_used, Some(Duration::from_secs(5)));
+    assert_eq!(
+        analysis.plies[3].clock_used,
+        Some(Duration::from_millis(7500))
+    );
+    assert_eq!(analysis.plies[0].clock_used, None);
+    assert_eq!(analysis.plies[4].clock, None);
+
+    let csv = analysis.to_csv();
+    assert_eq!(csv.lines().count(), 9);
+    assert!(csv.lines().nth(4).unwrap().starts_with("3,Qh5,"));
+    assert!(csv.lines().nth(4).unwrap().ends_with(",295.0,5.0"));
+    let json = analysis.to_json();
+    assert!(json.starts_with("{\"depth\":2,\"scores\":[{\"cp\":"));
+    assert!(json.contains("{\"mate\":1},{\"mate\":0}],\"plies\":[{\"san\":\"e4\""));
+}
+
+#[test]
+fn test_annotate() {
+    let text =
+        "[Event \"Scholar\"]\n\n1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 {Hoping for Qxe5+} 4. Qxf7# 1-0";
+    let game = pgn::parse(text).unwrap().remove(0);
+    let mut engine = Engine::new(START_POSITION).unwrap();
+    engine.set_output(Output::sink());
+    let analysis = engine.analyze_game(&game, 2).unwrap();
+    let annotated = analysis.annotate(&game);
+    assert_eq!(annotated.tag("Event"), Some("Scholar"));
+    assert_eq!(annotated.tag("ECO"), Some("C20"));
+    assert_eq!(analysis.classify(5), Some(Classification::Blunder));
+    assert!(analysis.loss(5) >= 300);
+    let nf6 = &annotated.moves[5];
+    assert_eq!(nf6.nags, [Classification::Blunder.nag()]);
+    let comment = nf6.comment.as_deref().unwrap();
+    assert!(comment.starts_with("[%eval #1] Blunder."));
+    assert!(comment.ends_with("Hoping for Qxe5+"));
+    assert_eq!(nf6.variations[0][0].san, analysis.best_move(5).unwrap());
+    // No eval once mated
+    assert_eq!(annotated.moves[6].comment, None);
+
+    let text = pgn::format(&annotated);
+    assert!(text.starts_with("[Event \"Scholar\"]\n[ECO \"C20\"]\n"));
+    assert!(text.contains("\n\n1. e4 { [%eval "));
+    assert!(text.contains("3... Nf6 $4"));
+    assert!(text.contains("4. Qxf7#") && text.ends_with("1-0\n"));
+    assert!(text.lines().all(|line| line.len() <= 80));
+    assert_eq!(pgn::parse(&text).unwrap(), [annotated]);
+}
@@ -0,0 +1,61 @@
+use bbrs_core::engine::{moves, AnalysisJob, Arena, Engine, Output};
+
+#[test]
+fn test_analyse_positions_concurrently() {
+    let jobs = [
+        // Mate in one: Qxf7#
+        AnalysisJob::new(
+            "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4",
+            3,
+        ),
+        AnalysisJob::new("not a fen", 3),
+        AnalysisJob::new(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            3,
+        ),
+        // The king takes the undefended queen
+        AnalysisJob::new("4k3/8/8/8/8/8/3q4/4K3 w - - 0 1", 2),
+    ];
+    let results = Arena::new(2).hash_size(4).analyse(&jobs);
+
+    assert_eq!(results.len(), jobs.len());
+    let mate = results[0].as_ref().unwrap();
+    assert_eq!(mate.depth, 3);
+    assert_eq!(mate.pv.len(), 1);
+    assert!(results[1].is_err());
+    assert_eq!(results[2].as_ref().unwrap().depth, 3);
+    assert_eq!(
+        moves::format(results[3].as_ref().unwrap().best_move),
+        "e1d2"
+    );
+}
+
+#[test]
+fn test_low_material_positions_are_skipped() {
+    let jobs = [
+        AnalysisJob::new("4k3/8/8/8/8/8/3q4/4K3 w - - 0 1", 2),
+        AnalysisJob::new("4k3/pppppppp/8/8/8/8/PPPPPPPP/4K3 w - - 0 1", 2),
+    ];
+    let results = Arena::new(2)
+        .hash_size(4)
+        .min_material(1_600)
+        .analyse(&jobs);
+    assert!(results[0].as_ref().unwrap_err().starts_with("Skipped"));
+    assert!(results[1].is_ok());
+}
+
+#[test]
+fn test_queen_promotions_only() {
+    // The knight promotion forks the king and queen
+    let fen = "8/1k1P1q2/8/8/8/8/P7/7K w - - 0 1";
+    let mut engine = Engine::new(fen).unwrap();
+    engine.set_output(Output::sink());
+    assert_eq!(moves::format(engine.search_position(4).best_move), "d7d8n");
+
+    let jobs = [AnalysisJob::new(fen, 4)];
+    let results = Arena::new(1).queen_promotions_only(true).analyse(&jobs);
+    assert_eq!(
+        moves::format(results[0].as_ref().unwrap().best_move),
+        "d7d8q"
+    );
+}
@@ -0,0 +1,42 @@
+use bbrs_core::engine::{
+    Engine, MoveOrdering, Output, BENCH_DEPTH, BENCH_POSITIONS, BENCH_SIGNATURE, START_POSITION,
+};
+
+#[test]
+fn test_bench_signature() {
+    let mut engine = Engine::new(START_POSITION).unwrap();
+    engine.set_output(Output::sink());
+    engine.load_moves(vec!["e2e4"]);
+    assert!(
+        engine.bench_verify(),
+        "The search changed, update BENCH_SIGNATURE from {} to {}",
+        BENCH_SIGNATURE,
+        engine.bench(BENCH_DEPTH)
+    );
+    // The position is restored afterwards
+    assert!(engine
+        .fen()
+        .starts_with("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b"));
+}
+
+#[test]
+fn test_ordering_experiment() {
+    let mut engine = Engine::new(START_POSITION).unwrap();
+    engine.set_output(Output::sink());
+    let positions = &BENCH_POSITIONS[..3];
+    let experiments = MoveOrdering::experiments();
+    let results = engine
+        .ordering_experiment(positions, 3, &experiments)
+        .unwrap();
+    assert_eq!(results.len(), experiments.len());
+    // Each configuration starts from scratch, so running one alone gives the same nodes
+    let no_history = engine
+        .ordering_experiment(positions, 3, &experiments[2..3])
+        .unwrap();
+    assert_eq!(no_history[0].0, results[2].0);
+
+    assert!(engine
+        .ordering_experiment(&["not a fen"], 3, &experiments)
+        .is_err());
+    assert_eq!(engine.fen(), START_POSITION);
+}
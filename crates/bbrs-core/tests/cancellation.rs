@@ -0,0 +1,119 @@
+mod common;
+
+use std::{
+    sync::atomic::Ordering,
+    thread,
+    time::{Duration, Instant},
+};
+
+use bbrs_core::engine::CancellationToken;
+use common::engine;
+
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+/// Every heavy piece can capture, so nearly all nodes are quiescence nodes with long capture lists.
+const CAPTURES: &str = "4k3/8/2qrqrq1/2QRQRQ1/2qrqrq1/2QRQRQ1/8/4K3 w - - 0 1";
+/// Allows for unoptimized builds, which take a few milliseconds where release builds take
+/// well under one.
+const MAX_STOP_LATENCY: Duration = Duration::from_millis(100);
+
+#[test]
+fn test_cancel_from_another_thread() {
+    let mut engine = engine(KIWIPETE);
+    let token = CancellationToken::new();
+    let canceller = {
+        let token = token.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            token.cancel();
+        })
+    };
+
+    let start = Instant::now();
+    engine.search(60, &token);
+    canceller.join().unwrap();
+
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert_eq!(engine.fen(), KIWIPETE);
+    assert!(engine.history.is_empty());
+    assert!(engine.check_consistency().is_ok());
+}
+
+#[test]
+fn test_deadline() {
+    let mut engine = engine(KIWIPETE);
+    let start = Instant::now();
+    engine.search(
+        60,
+        &CancellationToken::with_timeout(Duration::from_millis(100)),
+    );
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert_eq!(engine.fen(), KIWIPETE);
+
+    // The engine is usable again once the token is gone
+    let result = engine.search(2, &CancellationToken::new());
+    assert_eq!(result.depth, 2);
+}
+
+#[test]
+fn test_cancelled_before_start() {
+    let mut engine = engine(KIWIPETE);
+    let token = CancellationToken::new();
+    token.cancel();
+    let result = engine.search(5, &token);
+    assert_eq!(result.best_move, 0);
+    assert_eq!(engine.fen(), KIWIPETE);
+}
+
+#[test]
+fn test_stop_latency_in_capture_explosion() {
+    let mut engine = engine(CAPTURES);
+    let mut worst = Duration::ZERO;
+    for delay in [20, 45, 70, 95] {
+        let delay = Duration::from_millis(delay);
+        let start = Instant::now();
+        engine.search(60, &CancellationToken::with_timeout(delay));
+        worst = worst.max(start.elapsed().saturating_sub(delay));
+
+        let stop = engine.stop_handle();
+        let stopper = thread::spawn(move || {
+            thread::sleep(delay);
+            stop.store(true, Ordering::Relaxed);
+            Instant::now()
+        });
+        engine.search_position(60);
+        let returned = Instant::now();
+        worst = worst.max(returned.saturating_duration_since(stopper.join().unwrap()));
+    }
+    assert!(worst < MAX_STOP_LATENCY, "stop took {:?}", worst);
+    assert_eq!(engine.fen(), CAPTURES);
+}
+
+#[test]
+fn test_node_limit() {
+    let mut engine = engine(KIWIPETE);
+    engine.set_node_limit(Some(20_000));
+    let result = engine.search_position(60);
+    assert_ne!(result.best_move, 0);
+    assert!(result.nodes < 20_100, "searched {} nodes", result.nodes);
+    assert_eq!(engine.fen(), KIWIPETE);
+
+    // The limit was for that search only
+    assert_eq!(engine.search_position(3).depth, 3);
+
+    // Too few nodes to finish the first iteration still gives a move
+    engine.set_node_limit(Some(10));
+    assert_ne!(engine.search_position(60).best_move, 0);
+}
+
+#[test]
+fn test_infinite_search_waits_for_stop() {
+    let mut engine = engine(KIWIPETE);
+    engine.set_infinite(true);
+    let stop = engine.stop_handle();
+    let search = thread::spawn(move || engine.search_position(2));
+    thread::sleep(Duration::from_millis(200));
+    // Depth 2 takes a millisecond, yet the result is held back
+    assert!(!search.is_finished());
+    stop.store(true, Ordering::Relaxed);
+    assert_eq!(search.join().unwrap().depth, 2);
+}
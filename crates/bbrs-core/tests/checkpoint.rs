@@ -0,0 +1,58 @@
+mod common;
+
+use std::{fs, path::PathBuf, time::Duration};
+
+use bbrs_core::engine::{Checkpointing, START_POSITION};
+use common::engine;
+
+const FEN: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+fn temporary_file(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("bbrs-{}-{}", std::process::id(), name))
+}
+
+#[test]
+fn test_resume_from_checkpoint() {
+    let path = temporary_file("resume.ckpt");
+    let mut analysis = engine(FEN);
+    analysis.set_checkpointing(Some(Checkpointing {
+        path: path.clone(),
+        interval: Duration::from_secs(3600),
+    }));
+    let result = analysis.search_position(4);
+
+    let mut resumed = engine(START_POSITION);
+    let checkpoint = resumed.resume(&path).unwrap();
+    assert_eq!(resumed.fen(), FEN);
+    assert_eq!(checkpoint.depth, 4);
+    assert_eq!(checkpoint.root_moves, result.root_moves);
+
+    // The finished iterations come straight from the table
+    let fresh_nodes = engine(FEN).search_position(5).nodes;
+    let resumed_result = resumed.search_position(5);
+    assert!(resumed_result.nodes < fresh_nodes);
+    assert!(resumed_result.root_moves.nodes() > resumed_result.nodes);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_corrupted_checkpoint_is_rejected() {
+    let path = temporary_file("corrupted.ckpt");
+    let mut analysis = engine(FEN);
+    analysis.set_checkpointing(Some(Checkpointing {
+        path: path.clone(),
+        interval: Duration::ZERO,
+    }));
+    analysis.search_position(3);
+
+    let mut bytes = fs::read(&path).unwrap();
+    let middle = bytes.len() / 2;
+    bytes[middle] ^= 0x10;
+    fs::write(&path, &bytes).unwrap();
+
+    let mut engine = engine(START_POSITION);
+    assert!(engine.resume(&path).unwrap_err().contains("Checksum"));
+    assert_eq!(engine.fen(), START_POSITION);
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,8 @@
+use bbrs_core::engine::{Engine, Output};
+
+/// An engine at `fen` that prints nothing.
+pub fn engine(fen: &str) -> Engine {
+    let mut engine = Engine::new(fen).unwrap();
+    engine.set_output(Output::sink());
+    engine
+}
@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use bbrs_core::engine::{Chess960, Engine, Horde, RacingKings, Rules, Standard};
+
+/// A xorshift generator, so failures can be replayed from the seed.
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+/// Plays random legal moves, checking the incremental state against a reload
+/// from its FEN after every move and after taking all of them back.
+fn fuzz(fen: &str, rules: Arc<dyn Rules>, games: u64, plies: usize) {
+    let mut engine = Engine::new(fen).unwrap();
+    engine.set_rules(rules);
+    for seed in 1..=games {
+        let mut random = Random(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        let mut played = vec![];
+        for _ in 0..plies {
+            let moves = engine.generate_legal_moves();
+            if moves.is_empty() {
+                break;
+            }
+            let move_ = moves[random.next() as usize % moves.len()];
+            assert!(engine.make_move(move_));
+            played.push(engine.format_move(move_));
+            if let Err(err) = engine.check_consistency() {
+                panic!("seed {} after {}: {}", seed, played.join(" "), err);
+            }
+        }
+        played.iter().for_each(|_| engine.take_back());
+        assert_eq!(engine.fen(), Engine::new(fen).unwrap().fen());
+    }
+}
+
+#[test]
+fn test_fen_round_trip() {
+    for fen in [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 37 80",
+        "1r2kr2/8/8/8/8/8/8/1R2KR2 w KQkq - 0 1",
+        "rr2k3/8/8/8/8/8/8/RR2K3 w Bb - 0 1",
+    ] {
+        assert_eq!(Engine::new(fen).unwrap().fen(), fen);
+    }
+}
+
+#[test]
+fn test_standard_consistency() {
+    fuzz(
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        Arc::new(Standard),
+        8,
+        80,
+    );
+    fuzz(
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        Arc::new(Standard),
+        8,
+        60,
+    );
+    fuzz(
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        Arc::new(Standard),
+        8,
+        60,
+    );
+}
+
+#[test]
+fn test_variant_consistency() {
+    fuzz(
+        "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+        Arc::new(Chess960),
+        8,
+        60,
+    );
+    fuzz(
+        "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1",
+        Arc::new(Horde),
+        4,
+        60,
+    );
+    fuzz(
+        "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1",
+        Arc::new(RacingKings),
+        4,
+        60,
+    );
+}
+
+#[cfg(feature = "crazyhouse")]
+#[test]
+fn test_crazyhouse_consistency() {
+    fuzz(
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1",
+        Arc::new(bbrs_core::engine::Crazyhouse),
+        4,
+        60,
+    );
+}
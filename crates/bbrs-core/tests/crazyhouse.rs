@@ -0,0 +1,56 @@
+#![cfg(feature = "crazyhouse")]
+
+mod common;
+
+use std::sync::Arc;
+
+use bbrs_core::engine::{Crazyhouse, Engine};
+
+fn crazyhouse(fen: &str) -> Engine {
+    let mut engine = common::engine(fen);
+    engine.set_rules(Arc::new(Crazyhouse));
+    engine.set_position(fen).unwrap();
+    engine
+}
+
+#[test]
+fn test_drops() {
+    let mut engine = crazyhouse("4k3/8/8/8/8/8/8/4K3[Nn] w - - 0 1");
+    let drop = engine.find_move("N@e4").unwrap();
+    assert_eq!(engine.format_move(drop), "N@e4");
+    assert!(engine.find_move("N@e1").is_err());
+    assert!(engine.make_move(drop));
+    assert_eq!(engine.fen(), "4k3/8/8/8/4N3/8/8/4K3[n] b - - 1 1");
+    engine.take_back();
+    assert_eq!(engine.fen(), "4k3/8/8/8/8/8/8/4K3[Nn] w - - 0 1");
+
+    // Pawns are never dropped on the first or last rank
+    let mut engine = crazyhouse("4k3/8/8/8/8/8/8/4K3[P] w - - 0 1");
+    assert_eq!(engine.generate_legal_moves().len(), 5 + 48);
+    assert!(engine.find_move("P@a8").is_err());
+    assert!(engine.find_move("P@h1").is_err());
+}
+
+#[test]
+fn test_captures_fill_the_pocket() {
+    let mut engine = crazyhouse("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1");
+    engine.load_moves(vec!["e2e4", "d7d5", "e4d5", "d8d5"]);
+    assert_eq!(
+        engine.fen(),
+        "rnb1kbnr/ppp1pppp/8/3q4/8/8/PPPP1PPP/RNBQKBNR[Pp] w KQkq - 0 3"
+    );
+    engine.load_moves(vec!["P@e4", "d5e4"]);
+    assert_eq!(
+        engine.fen(),
+        "rnb1kbnr/ppp1pppp/8/8/4q3/8/PPPP1PPP/RNBQKBNR[pp] w KQkq - 0 4"
+    );
+}
+
+#[test]
+fn test_promoted_pieces_are_captured_as_pawns() {
+    let mut engine = crazyhouse("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1");
+    engine.load_moves(vec!["b7b8q"]);
+    assert_eq!(engine.fen(), "rQ~2k3/8/8/8/8/8/8/4K3 b - - 0 1");
+    engine.load_moves(vec!["a8b8"]);
+    assert_eq!(engine.fen(), "1r2k3/8/8/8/8/8/8/4K3[p] w - - 0 2");
+}
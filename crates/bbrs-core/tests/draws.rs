@@ -0,0 +1,126 @@
+use std::sync::Arc;
+
+use bbrs_core::engine::{Color, Engine, GameState, Horde, Output, START_POSITION};
+
+fn search(fen: &str, moves: &[&str], depth: u8) -> i32 {
+    let mut engine = Engine::new(fen).unwrap();
+    engine.set_output(Output::sink());
+    engine.load_moves(moves.to_vec());
+    engine.search_position(depth).score
+}
+
+#[test]
+fn test_fifty_move_rule() {
+    let fen = "k7/8/8/8/8/8/8/KQ6 w - - 0 80";
+    assert!(search(fen, &[], 3) > 500);
+    // Every move is the hundredth half move without a capture or pawn move
+    assert_eq!(search(&fen.replace(" 0 80", " 99 80"), &[], 3), 0);
+    // Unless it mates
+    let mate = "k7/8/1K6/8/8/8/8/6Q1 w - - 99 80";
+    assert!(search(mate, &[], 2) > 40_000);
+}
+
+#[test]
+fn test_repetition_is_a_draw() {
+    // Black has three rooks against the queen, but Qe8+ and Qh5+ give perpetual check
+    let fen = "7k/6p1/8/8/r7/r7/r3Q3/7K w - - 0 1";
+    assert!(search(fen, &[], 1) < -500);
+    assert_eq!(search(fen, &[], 6), 0);
+}
+
+#[test]
+fn test_game_state() {
+    let state = |fen: &str, moves: &[&str]| {
+        let mut engine = Engine::new(fen).unwrap();
+        engine.load_moves(moves.to_vec());
+        engine.game_state()
+    };
+    assert_eq!(state(START_POSITION, &[]), GameState::Ongoing);
+    let fools_mate = ["f2f3", "e7e5", "g2g4", "d8h4"];
+    assert_eq!(
+        state(START_POSITION, &fools_mate),
+        GameState::Checkmate(Color::Black)
+    );
+    assert_eq!(
+        state("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1", &[]),
+        GameState::Stalemate
+    );
+    // The search already scores the second occurrence as a draw, the rules need a third
+    let knights = ["g1f3", "g8f6", "f3g1", "f6g8"];
+    assert_eq!(state(START_POSITION, &knights), GameState::Ongoing);
+    assert_eq!(
+        state(START_POSITION, &[knights, knights].concat()),
+        GameState::DrawByRepetition
+    );
+    assert_eq!(
+        state("k7/8/8/8/8/8/8/KQ6 w - - 100 80", &[]),
+        GameState::DrawByFiftyMove
+    );
+    // Mate on the hundredth half move still counts
+    assert_eq!(
+        state("k7/8/1K6/8/8/8/8/6Q1 w - - 99 80", &["g1g8"]),
+        GameState::Checkmate(Color::White)
+    );
+    for fen in [
+        "k7/8/8/8/8/8/8/K7 w - - 0 1",
+        "k7/8/8/8/8/8/8/KN6 w - - 0 1",
+        "kb6/8/8/8/8/8/8/K1B5 w - - 0 1",
+    ] {
+        assert_eq!(state(fen, &[]), GameState::DrawByInsufficientMaterial);
+    }
+    for fen in [
+        "k7/8/8/8/8/8/8/KNN5 w - - 0 1",
+        "kb6/8/8/8/8/8/8/KB6 w - - 0 1",
+        "k7/8/8/8/8/8/P7/K7 w - - 0 1",
+    ] {
+        assert_eq!(state(fen, &[]), GameState::Ongoing);
+    }
+
+    let mut horde = Engine::new("k7/8/8/8/8/8/8/8 w - - 0 1").unwrap();
+    horde.set_rules(Arc::new(Horde));
+    assert!(matches!(horde.game_state(), GameState::VariantEnd(_)));
+}
+
+#[test]
+fn test_evals_fade_as_the_fifty_move_rule_nears() {
+    let eval = |half_moves: u8| {
+        Engine::new(&format!("k7/8/8/8/8/8/8/KQ6 w - - {} 80", half_moves))
+            .unwrap()
+            .evaluate()
+    };
+    assert_eq!(eval(0), eval(40));
+    assert!(eval(40) > eval(70) && eval(70) > eval(95) && eval(95) > 0);
+    assert_eq!(eval(100), 0);
+    assert_eq!(eval(255), 0);
+}
+
+#[test]
+fn test_cached_evals_fade_for_the_current_clock() {
+    // The hash leaves the clock out, so the second search finds the first one's eval.
+    // This close to the limit stored scores no longer cut off
+    let fen = |half_moves: u16| format!("k7/8/8/8/8/8/8/KQ6 w - - {} 80", half_moves);
+    let mut engine = Engine::new(&fen(0)).unwrap();
+    engine.set_output(Output::sink());
+    let fresh = engine.quiescence(-50000, 50000);
+    engine.set_position(&fen(95)).unwrap();
+    let faded = engine.evaluate();
+    assert!(0 < faded && faded < fresh);
+    assert_eq!(engine.quiescence(-50000, 50000), faded);
+}
+
+#[test]
+fn test_pawn_moves_first_when_converting() {
+    let pawn_over_king = |half_moves: u8| {
+        let fen = format!("4k3/8/8/8/8/8/P7/R3K3 w - - {} 80", half_moves);
+        let mut engine = Engine::new(&fen).unwrap();
+        engine.set_output(Output::sink());
+        engine.search_position(1);
+        let score = |engine: &mut Engine, move_: &str| {
+            let move_ = engine.parse_move(move_).unwrap();
+            engine.score_move(move_)
+        };
+        score(&mut engine, "a2a3") > score(&mut engine, "e1d2")
+    };
+    assert!(pawn_over_king(80));
+    assert!(!pawn_over_king(0));
+}
@@ -0,0 +1,19 @@
+use bbrs_core::engine::Heatmaps;
+
+#[test]
+fn test_heatmaps() {
+    let heatmaps = Heatmaps::from_pgn("1. e4 e5 2. Nf3 *").unwrap();
+    assert_eq!(heatmaps.positions(), 4);
+    // The white king stays on e1 (square 60), the e-pawn spends three positions on e4 (36)
+    assert_eq!(heatmaps.occupancy(5)[60], 4);
+    assert_eq!(heatmaps.occupancy(0)[36], 3);
+    // The g1 knight attacks f3 (45) from g1 in three positions, then h4 from f3
+    assert_eq!(heatmaps.attacks(1)[45], 3);
+    assert_eq!(heatmaps.attacks(1)[39], 1);
+
+    let csv = heatmaps.to_csv();
+    assert_eq!(csv.lines().count(), 1 + 2 * 12 * 64);
+    assert!(csv.contains("occupancy,K,e1,4\n"));
+    let json = heatmaps.to_json();
+    assert!(json.starts_with("{\"positions\":4,\"occupancy\":{\"P\":[0,"));
+}
@@ -0,0 +1,24 @@
+#![cfg(feature = "invariant-checks")]
+
+use bbrs_core::engine::Engine;
+
+#[test]
+fn test_perft_keeps_invariants() {
+    let mut engine =
+        Engine::new("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+            .unwrap();
+    assert_eq!(engine.perft_driver(3), 97862);
+}
+
+#[test]
+#[should_panic(expected = "King captured")]
+fn test_king_capture_panics() {
+    // Black is in check with White to move, which no legal game reaches
+    let mut engine = Engine::new("4k2R/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let capture = engine
+        .generate_moves()
+        .into_iter()
+        .find(|&move_| engine.format_move(move_) == "h8e8")
+        .unwrap();
+    engine.make_move(capture);
+}
@@ -0,0 +1,29 @@
+use bbrs_core::engine::{Engine, Output};
+
+/// Mate in three, the distance of which must not drift as deeper iterations and later
+/// searches reuse transposition table entries stored at other plies.
+const MATE_IN_THREE: &str = "r5rk/5p1p/5R2/4B3/8/8/7P/7K w - - 0 1";
+
+#[test]
+fn test_mate_distance_is_stable() {
+    let mut engine = Engine::new(MATE_IN_THREE).unwrap();
+    engine.set_output(Output::sink());
+
+    let mate = engine.search_position(4);
+    assert_eq!(mate.pv.len(), 5);
+    for depth in 5..=6 {
+        let result = engine.search_position(depth);
+        assert_eq!(result.score, mate.score, "depth {}", depth);
+        assert_eq!(result.pv.len(), 5, "depth {}", depth);
+    }
+
+    // Two plies into the mating line, with the table still holding the earlier searches
+    let moves = mate.pv[..2]
+        .iter()
+        .map(|&move_| engine.format_move(move_))
+        .collect::<Vec<String>>();
+    engine.load_moves(moves.iter().map(String::as_str).collect());
+    let result = engine.search_position(5);
+    assert_eq!(result.score, mate.score + 2);
+    assert_eq!(result.pv.len(), 3);
+}
@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use bbrs_core::engine::{Chess960, Engine, Square, START_POSITION};
+
+#[cfg(not(feature = "invariant-checks"))]
+fn moves(fen: &str) -> Vec<String> {
+    let mut engine = Engine::new(fen).unwrap();
+    let mut moves = engine
+        .generate_moves()
+        .iter()
+        .map(|&move_| engine.format_san(move_))
+        .collect::<Vec<String>>();
+    moves.sort();
+    moves
+}
+
+// Pawns on the last rank are what the invariant checks reject
+#[cfg(not(feature = "invariant-checks"))]
+#[test]
+fn test_pawns_on_the_last_rank_are_skipped() {
+    // In square order, white pawns on the eighth rank come first and black ones on the first last
+    assert_eq!(
+        moves("P3k3/8/8/8/8/8/4P3/4K3 w - - 0 1"),
+        ["Kd1", "Kd2", "Kf1", "Kf2", "e3", "e4"]
+    );
+    assert_eq!(
+        moves("PP2k2P/8/8/8/8/8/P6P/4K3 w - - 0 1"),
+        ["Kd1", "Kd2", "Ke2", "Kf1", "Kf2", "a3", "a4", "h3", "h4"]
+    );
+    assert_eq!(
+        moves("4k3/4p3/8/8/8/8/8/p3K2p b - - 0 1"),
+        ["Kd7", "Kd8", "Kf7", "Kf8", "e5", "e6"]
+    );
+}
+
+#[cfg(not(feature = "invariant-checks"))]
+#[test]
+fn test_pawns_next_to_the_last_rank_still_promote() {
+    assert_eq!(
+        moves("P3k3/1P6/8/8/8/8/8/4K3 w - - 0 1"),
+        ["Kd1", "Kd2", "Ke2", "Kf1", "Kf2", "b8=B", "b8=N", "b8=Q+", "b8=R+"]
+    );
+}
+
+#[test]
+fn test_check_state_follows_the_moves() {
+    let mut engine = Engine::new(START_POSITION).unwrap();
+    engine.load_moves(vec!["e2e4", "f7f6", "d1h5"]);
+    assert!(engine.in_check());
+    assert_eq!(engine.checkers(), 1 << Square::H5 as u64);
+    engine.take_back();
+    assert!(!engine.in_check());
+    // A double check in a parsed position
+    let engine = Engine::new("4k3/8/3N4/8/8/8/8/4RK2 b - - 0 1").unwrap();
+    assert_eq!(engine.checkers().count_ones(), 2);
+}
+
+#[test]
+fn test_chess960_perft() {
+    let fen = "1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 9";
+    let mut engine = Engine::new(fen).unwrap();
+    engine.set_rules(Arc::new(Chess960));
+    assert_eq!(engine.perft_driver(3), 31058);
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use std::time::Duration;
+
+use bbrs_core::engine::{Engine, START_POSITION};
+
+fn engine(fen: &str, lines: usize) -> Engine {
+    let mut engine = common::engine(fen);
+    engine.set_multi_pv(lines);
+    engine
+}
+
+#[test]
+fn test_lines_are_distinct_and_ordered() {
+    let result = engine(START_POSITION, 4).search_position(4);
+    let lines = result.root_moves.iter().take(4).collect::<Vec<_>>();
+    assert_eq!(lines[0].move_, result.best_move);
+    assert_eq!(lines[0].pv, result.pv);
+    for (line, root_move) in lines.iter().enumerate() {
+        assert_eq!(root_move.pv[0], root_move.move_);
+        assert!(lines[..line]
+            .iter()
+            .all(|other| other.move_ != root_move.move_));
+    }
+    assert!(lines.windows(2).all(|pair| pair[0].score >= pair[1].score));
+}
+
+#[test]
+fn test_second_line_excludes_the_mate() {
+    // Only Ra8 mates, every other line is merely winning
+    let fen = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1";
+    let single = engine(fen, 1).search_position(3);
+    let result = engine(fen, 2).search_position(3);
+    assert_eq!(result.best_move, single.best_move);
+    assert_eq!(result.score, single.score);
+    let second = result.root_moves.iter().nth(1).unwrap();
+    assert_ne!(second.move_, result.best_move);
+    assert!(second.score < result.score);
+}
+
+#[test]
+fn test_search_moves_restrict_the_root() {
+    let mut engine = engine(START_POSITION, 1);
+    let moves = ["a2a3", "h2h4"].map(|move_| engine.parse_move(move_).unwrap());
+    engine.set_search_moves(moves.to_vec());
+    let result = engine.search_position(3);
+    assert_eq!(result.root_moves.len(), 2);
+    assert!(moves.contains(&result.best_move));
+    // The restriction was for that search only
+    assert_eq!(engine.search_position(1).root_moves.len(), 20);
+}
+
+#[test]
+fn test_excluded_moves_find_the_alternative() {
+    let fen = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1";
+    let mut engine = engine(fen, 1);
+    let mate = engine.parse_move("a1a8").unwrap();
+    assert_eq!(engine.search_position(3).best_move, mate);
+    engine.set_excluded_moves(vec![mate]);
+    let result = engine.search_position(3);
+    assert_ne!(result.best_move, mate);
+    assert!(result
+        .root_moves
+        .iter()
+        .all(|root_move| root_move.move_ != mate));
+    // Excluding every legal move excludes none of them
+    let mut engine = self::engine(START_POSITION, 1);
+    let all = engine.generate_legal_moves();
+    engine.set_excluded_moves(all);
+    assert_eq!(engine.search_position(1).root_moves.len(), 20);
+}
+
+#[test]
+fn test_analyse_replies() {
+    let mut engine = engine(START_POSITION, 1);
+    engine.load_moves(vec!["e2e4"]);
+    let analyses = engine.analyse_replies(3, Duration::from_millis(400));
+    assert_eq!(analyses.len(), 3);
+    for (index, analysis) in analyses.iter().enumerate() {
+        assert_eq!(analysis.result.best_move, analysis.reply);
+        assert!(analysis.result.depth > 0);
+        assert!(analyses[..index]
+            .iter()
+            .all(|other| other.reply != analysis.reply));
+    }
+}
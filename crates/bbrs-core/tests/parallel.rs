@@ -0,0 +1,41 @@
+mod common;
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use bbrs_core::engine::Engine;
+
+const MATE_IN_THREE: &str = "r5rk/5p1p/5R2/4B3/8/8/7P/7K w - - 0 1";
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+fn engine(fen: &str, threads: usize) -> Engine {
+    let mut engine = common::engine(fen);
+    engine.set_threads(threads);
+    engine
+}
+
+#[test]
+fn test_parallel_search_finds_the_mate() {
+    let single = engine(MATE_IN_THREE, 1).search_position(5);
+    let parallel = engine(MATE_IN_THREE, 4).search_position(5);
+    assert_eq!(parallel.score, single.score);
+    assert_eq!(parallel.best_move, single.best_move);
+}
+
+#[test]
+fn test_stop_ends_all_threads() {
+    let mut engine = engine(KIWIPETE, 4);
+    let stop = engine.stop_handle();
+    let stopper = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(200));
+        stop.store(true, std::sync::atomic::Ordering::Relaxed);
+    });
+    let start = Instant::now();
+    let result = engine.search_position(60);
+    stopper.join().unwrap();
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert_ne!(result.best_move, 0);
+    assert_eq!(engine.fen(), KIWIPETE);
+}
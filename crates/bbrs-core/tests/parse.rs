@@ -0,0 +1,128 @@
+mod common;
+
+use std::sync::Arc;
+
+use bbrs_core::engine::{moves, Chess960, Color, Engine, IllegalMove};
+use common::engine;
+
+const CASTLING: &str = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+
+#[test]
+fn test_castling_in_both_notations() {
+    let mut engine = engine(CASTLING);
+    let king_side = engine.find_move("e1g1").unwrap();
+    assert_eq!(engine.find_move("e1h1"), Ok(king_side));
+    assert_eq!(engine.format_move(king_side), "e1g1");
+    let queen_side = engine.find_move("e1c1").unwrap();
+    assert_eq!(engine.find_move("e1a1"), Ok(queen_side));
+
+    engine.set_rules(Arc::new(Chess960));
+    engine.set_position(CASTLING).unwrap();
+    let king_side = engine.find_move("e1h1").unwrap();
+    assert_eq!(engine.find_move("e1g1"), Ok(king_side));
+    assert_eq!(engine.format_move(king_side), "e1h1");
+}
+
+#[test]
+fn test_promotions() {
+    let mut engine = engine("1n5k/P7/8/8/8/8/8/7K w - - 0 1");
+    for text in ["a7a8q", "a7a8n", "a7b8r", "a7b8b"] {
+        let move_ = engine.find_move(text).unwrap();
+        assert_eq!(engine.format_move(move_), text);
+    }
+    assert_eq!(engine.find_move("a7a8Q"), engine.find_move("a7a8q"));
+    assert_eq!(engine.find_move("a7a8"), Err(IllegalMove::MissingPromotion));
+    assert_eq!(engine.find_move("a7a8k"), Err(IllegalMove::PromotionPiece));
+    assert_eq!(engine.find_move("h1h2q"), Err(IllegalMove::NotAPromotion));
+}
+
+#[test]
+fn test_null_move_and_errors() {
+    let mut analysis = engine(CASTLING);
+    assert_eq!(analysis.find_move("0000"), Ok(moves::NULL_MOVE));
+    assert_eq!(analysis.parse_move("0000"), None);
+    assert_eq!(moves::format(moves::NULL_MOVE), "0000");
+    analysis.load_moves(vec!["0000", "e8g8"]);
+    assert_eq!(analysis.fen(), "r4rk1/8/8/8/8/8/8/R3K2R w KQ - 2 2");
+
+    let mut engine = engine(CASTLING);
+    let reason = |engine: &mut Engine, text| engine.find_move(text).unwrap_err().to_string();
+    assert_eq!(reason(&mut engine, "e3e4"), "No piece on e3");
+    assert_eq!(
+        engine.find_move("a8a7"),
+        Err(IllegalMove::WrongSide {
+            square: 0,
+            to_move: Color::White
+        })
+    );
+    assert_eq!(
+        reason(&mut engine, "a8a7"),
+        "The piece on a8 is black, but white is to move"
+    );
+    assert_eq!(reason(&mut engine, "a1a9"), "Not in coordinate notation");
+    assert_eq!(
+        reason(&mut engine, "a1b2"),
+        "The piece on a1 cannot move to b2"
+    );
+    let mut check = self::engine("4k3/8/8/8/8/8/4r3/4K3 w - - 0 1");
+    assert_eq!(check.find_move("0000"), Err(IllegalMove::NullMoveInCheck));
+}
+
+#[test]
+fn test_illegal_moves_are_explained() {
+    let mut engine = engine("4k3/5r2/8/8/1b6/2N5/3P4/R3K2R w K - 0 1");
+    assert_eq!(engine.find_move("e1d2"), Err(IllegalMove::OwnPiece(51)));
+    // No right to castle queenside, and f1 is attacked
+    assert_eq!(engine.find_move("e1c1"), Err(IllegalMove::Castling));
+    assert_eq!(engine.find_move("e1a1"), Err(IllegalMove::Castling));
+    assert_eq!(engine.find_move("e1g1"), Err(IllegalMove::Castling));
+    assert_eq!(
+        engine.find_move("e1f2").unwrap_err().to_string(),
+        "The move leaves the king in check"
+    );
+    assert_eq!(
+        engine.find_move("a1b2"),
+        Err(IllegalMove::Unreachable {
+            source: 56,
+            target: 49
+        })
+    );
+
+    let mut engine = self::engine("4k3/8/8/8/1b6/2N5/8/4K3 w - - 0 1");
+    assert_eq!(
+        engine.find_move("c3e4").unwrap_err().to_string(),
+        "The piece on c3 is pinned to the king by the one on b4"
+    );
+    let mut engine = self::engine("4k3/4r3/8/8/8/2N5/8/4K3 w - - 0 1");
+    assert_eq!(engine.find_move("c3d5"), Err(IllegalMove::InCheck));
+    assert!(engine.find_move("c3e2").is_ok());
+}
+
+#[test]
+fn test_move_counters() {
+    // Beyond what fits in a byte
+    let fen = "4k3/8/8/8/8/8/8/R3K3 b - - 300 420";
+    let mut engine = Engine::new(fen).unwrap();
+    assert_eq!(engine.fen(), fen);
+    engine.load_moves(vec!["e8d7", "a1a2"]);
+    assert_eq!(engine.fen(), "8/3k4/8/8/8/8/R7/4K3 b - - 302 421");
+    engine.take_back();
+    engine.take_back();
+    assert_eq!(engine.fen(), fen);
+}
+
+#[test]
+fn test_chess960_castling_rights() {
+    // X-FEN letters stand for the outermost rooks, the same ones as these rook files
+    let fen = "1r2kr2/5p2/8/8/8/8/5P2/1R2KR2 w KQkq - 0 1";
+    let shredder = engine("1r2kr2/5p2/8/8/8/8/5P2/1R2KR2 w FBfb - 0 1");
+    assert_eq!(shredder.fen(), fen);
+    assert_eq!(engine(fen).fen(), fen);
+    // An inner rook keeps its file
+    let inner = "rr2k3/8/8/8/8/8/8/RR2K3 w Bb - 0 1";
+    assert_eq!(engine(inner).fen(), inner);
+    let mut engine = engine(inner);
+    engine.set_rules(Arc::new(Chess960));
+    assert!(engine.find_move("e1b1").is_ok());
+    assert!(engine.find_move("e1a1").is_err());
+}
@@ -0,0 +1,159 @@
+mod common;
+
+use bbrs_core::engine::{pgn, Database, Repertoire, START_POSITION};
+use common::engine;
+
+#[test]
+fn test_san_round_trip() {
+    let positions = [
+        // Knights on b1 and f3 both reach d2, rooks on a1 and a5 both reach a3
+        ("7k/8/8/8/8/5N2/8/1N5K w - - 0 1", "Nbd2", "b1d2"),
+        ("8/7k/8/R7/8/8/8/R6K w - - 0 1", "R1a3", "a1a3"),
+        ("7k/1P6/8/8/8/8/8/7K w - - 0 1", "b8=Q+", "b7b8q"),
+        ("r3k3/8/8/8/8/8/8/4K2R w K - 0 1", "O-O", "e1g1"),
+        ("k7/8/1K6/8/8/8/8/6Q1 w - - 0 1", "Qg8#", "g1g8"),
+        ("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1", "exd5", "e4d5"),
+    ];
+    for (fen, san, uci) in positions {
+        let mut engine = engine(fen);
+        let move_ = engine
+            .generate_legal_moves()
+            .into_iter()
+            .find(|&legal| engine.format_move(legal).eq_ignore_ascii_case(uci))
+            .unwrap();
+        assert_eq!(engine.format_san(move_), san, "{}", fen);
+        assert_eq!(engine.parse_san(san), Some(move_), "{}", fen);
+    }
+    // Suffixes, annotations and zeros for castling are accepted when parsing
+    let mut engine = engine("r3k3/8/8/8/8/8/8/4K2R w K - 0 1");
+    assert_eq!(engine.parse_san("0-0!?"), engine.parse_move("e1g1"));
+    assert_eq!(engine.parse_san("Nf3"), None);
+}
+
+#[test]
+fn test_parse_pgn() {
+    let text = r#"
+[Event "Test"]
+[White "A"]
+
+1. e4 {Best by test} e5 (1... c5 2. Nf3 (2. Nc3) d6) 2. Nf3! $14 Nc6 1-0
+
+[Event "Second"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"]
+
+1. e3 ; a rest of line comment
+Kd7 *
+"#;
+    let games = pgn::parse(text).unwrap();
+    assert_eq!(games.len(), 2);
+    let game = &games[0];
+    assert_eq!(game.tag("White"), Some("A"));
+    assert_eq!(game.start_position(), START_POSITION);
+    assert_eq!(game.result, "1-0");
+    let sans = game
+        .moves
+        .iter()
+        .map(|m| m.san.as_str())
+        .collect::<Vec<&str>>();
+    assert_eq!(sans, ["e4", "e5", "Nf3", "Nc6"]);
+    assert_eq!(game.moves[0].comment.as_deref(), Some("Best by test"));
+    assert_eq!(game.moves[2].nags, [1, 14]);
+    let variation = &game.moves[1].variations[0];
+    assert_eq!(variation.len(), 3);
+    assert_eq!(variation[1].variations[0][0].san, "Nc3");
+
+    assert_eq!(games[1].start_position(), "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+    assert_eq!(
+        games[1].moves[0].comment.as_deref(),
+        Some("a rest of line comment")
+    );
+    assert_eq!(games[1].result, "*");
+
+    // Formatting keeps everything that was parsed
+    for game in games.iter() {
+        assert_eq!(&pgn::parse(&pgn::format(game)).unwrap()[0], game);
+    }
+    assert!(pgn::format(game).contains("1. e4 { Best by test } 1... e5 (1... c5 2. Nf3 (2. Nc3)"));
+
+    assert!(pgn::parse("1. e4 (e5").is_err());
+    assert!(pgn::parse("1. e4 e5)").is_err());
+}
+
+#[test]
+fn test_repertoire() {
+    let repertoire = Repertoire::from_pgn("1. e4 e5 (1... c5 2. Nf3) 2. Nf3 Nc6 *").unwrap();
+    assert_eq!(repertoire.len(), 5);
+    let mut engine = engine(START_POSITION);
+    let e4 = engine.parse_san("e4").unwrap();
+    assert_eq!(repertoire.moves(&engine), [e4]);
+    engine.make_move(e4);
+    let replies = [
+        engine.parse_san("e5").unwrap(),
+        engine.parse_san("c5").unwrap(),
+    ];
+    assert_eq!(repertoire.moves(&engine), replies);
+    engine.make_move(replies[1]);
+    let nf3 = engine.parse_san("Nf3").unwrap();
+    assert_eq!(repertoire.moves(&engine), [nf3]);
+    engine.make_move(nf3);
+    assert!(repertoire.moves(&engine).is_empty());
+
+    assert!(Repertoire::from_pgn("1. e4 e5 2. Ke3 *").is_err());
+
+    // Castling written with zeros, also run into the move number
+    let repertoire =
+        Repertoire::from_pgn("1. e4 e5 2. Nf3 Nc6 3. Bc4 Bc5 4. 0-0 Nf6 5.d3 0-0 *").unwrap();
+    assert_eq!(repertoire.len(), 10);
+}
+
+#[test]
+fn test_eco() {
+    let eco = |text: &str| {
+        pgn::parse(text).unwrap()[0]
+            .eco()
+            .map(|opening| opening.eco)
+    };
+    assert_eq!(
+        eco("1. e4 c5 2. Nf3 d6 3. d4 cxd4 4. Nxd4 Nf6 5. Nc3 a6 6. Be3 *"),
+        Some("B90")
+    );
+    // Reached by transposition from the English
+    assert_eq!(eco("1. c4 Nf6 2. d4 e6 3. Nc3 Bb4 *"), Some("E20"));
+    assert_eq!(eco("1. c4 e6 2. d4 d5 *"), Some("D30"));
+    assert_eq!(eco("1. a3 *"), None);
+}
+
+#[test]
+fn test_database() {
+    let text = r#"
+[White "A"]
+1. d4 d5 2. c4 e6 *
+
+[White "B"]
+1. c4 e6 2. d4 d5 3. Nc3 *
+
+[White "C"]
+1. d4 {A comment} d5 2. c4 e6 1-0
+
+[White "D"]
+1. e4 *
+"#;
+    let mut database = Database::from_pgn(text).unwrap();
+    assert_eq!(database.len(), 4);
+    // Tags, comments and results do not matter
+    assert_eq!(database.duplicates(), [vec![0, 2]]);
+
+    let transpositions = database.transpositions();
+    assert_eq!(transpositions.len(), 1);
+    assert_eq!(transpositions[0].occurrences, [(0, 4), (1, 4), (2, 4)]);
+
+    let mut engine = engine(START_POSITION);
+    engine.load_moves(vec!["c2c4", "e7e6"]);
+    assert_eq!(database.find(&engine), [(1, 2)]);
+
+    assert_eq!(database.dedupe(), 1);
+    assert_eq!(database.len(), 3);
+    assert_eq!(database.games()[2].tag("White"), Some("D"));
+    assert!(database.duplicates().is_empty());
+    assert_eq!(database.transpositions()[0].occurrences, [(0, 4), (1, 4)]);
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use bbrs_core::engine::{Outcome, PlayoutPolicy, Rng, START_POSITION};
+use common::engine;
+
+#[test]
+fn test_random_playout() {
+    // Fool's mate has already happened
+    let mated = "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3";
+    let mut rng = Rng::new(1);
+    let policy = PlayoutPolicy::Uniform;
+    assert_eq!(
+        engine(mated).random_playout(&mut rng, 100, policy),
+        Some(Outcome::Loss)
+    );
+
+    let stalemate = "k7/2K5/1Q6/8/8/8/8/8 b - - 0 1";
+    assert_eq!(
+        engine(stalemate).random_playout(&mut rng, 100, policy),
+        Some(Outcome::Draw)
+    );
+    // White wins if its first move is Qb7#, but playouts stop at the ply limit
+    let mut engine = engine(&stalemate.replace(" b ", " w "));
+    assert_eq!(engine.random_playout(&mut rng, 0, policy), None);
+
+    let mut engine = self::engine(START_POSITION);
+    let outcomes = (0..20)
+        .map(|_| engine.random_playout(&mut Rng::new(7), 200, PlayoutPolicy::CaptureBiased))
+        .collect::<Vec<Option<Outcome>>>();
+    assert!(outcomes.windows(2).all(|pair| pair[0] == pair[1]));
+    assert_eq!(engine.fen(), START_POSITION);
+}
+
+#[test]
+fn test_playout_score() {
+    // Black can never win with a bare king
+    let mut engine = engine("4k3/8/8/8/8/8/8/3QK3 w - - 0 1");
+    let mut rng = Rng::new(3);
+    let score = engine.playout_score(&mut rng, 50, 100, PlayoutPolicy::CaptureBiased);
+    assert!((0.5..=1.0).contains(&score));
+}
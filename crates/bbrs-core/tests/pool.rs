@@ -0,0 +1,36 @@
+use std::thread;
+
+use bbrs_core::engine::{moves, EnginePool};
+
+#[test]
+fn test_pool_hands_out_engines_concurrently() {
+    let pool = EnginePool::new(2, 1);
+    assert_eq!(pool.size(), 2);
+
+    let first = pool.get();
+    let second = pool.try_get().unwrap();
+    assert!(pool.try_get().is_none());
+    drop(second);
+    assert_eq!(pool.available(), 1);
+    drop(first);
+
+    let best_moves = thread::scope(|scope| {
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut engine = pool.get();
+                    engine
+                        .set_position("4k3/8/8/8/8/8/3q4/4K3 w - - 0 1")
+                        .unwrap();
+                    moves::format(engine.search_position(2).best_move)
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+    assert_eq!(best_moves, vec!["e1d2"; 4]);
+    assert_eq!(pool.available(), 2);
+}
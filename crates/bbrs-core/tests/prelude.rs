@@ -0,0 +1,24 @@
+use bbrs_core::prelude::*;
+
+#[test]
+fn test_search_through_the_prelude() {
+    let mut engine = Engine::new(START_POSITION).unwrap();
+    engine.set_output(Output::sink());
+    assert_eq!(engine.side_to_move(), Color::White);
+    assert_eq!(Color::White.opponent(), Color::Black);
+
+    engine.set_limits(SearchLimits {
+        nodes: Some(2000),
+        ..SearchLimits::default()
+    });
+    let result: SearchResult = engine.search_position(10);
+    let best_move: Move = result.best_move;
+    assert!(engine.generate_legal_moves().contains(&best_move));
+
+    let position: &Position = &engine.state;
+    assert_eq!(
+        position.material_key(),
+        Engine::new(START_POSITION).unwrap().state.material_key()
+    );
+    assert_eq!(Square::parse("e4"), Some(Square::E4));
+}
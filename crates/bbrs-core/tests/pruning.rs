@@ -0,0 +1,58 @@
+use bbrs_core::engine::Engine;
+
+/// Searches a null window just below zero from a clean table. The search fails
+/// hard, so only reverse futility pruning can return more than beta.
+fn null_window(fen: &str, depth: u8) -> (i32, i32) {
+    let mut engine = Engine::new(fen).unwrap();
+    engine.clear_hash();
+    (engine.negamax(depth, -1, 0), engine.evaluate())
+}
+
+#[test]
+fn test_reverse_futility_pruning() {
+    // A queen up stands far enough above beta to return the static eval at low depth
+    let (score, eval) = null_window("4k3/8/8/8/8/8/8/3QK3 w - - 0 1", 2);
+    assert!(eval > 500);
+    assert_eq!(score, eval);
+    // But searches deeper nodes for real
+    let (score, _) = null_window("4k3/8/8/8/8/8/8/3QK3 w - - 0 1", 4);
+    assert_eq!(score, 0);
+}
+
+#[test]
+fn test_no_reverse_futility_pruning_in_check() {
+    let (score, eval) = null_window("4k3/8/8/8/8/8/3Q4/4K2r w - - 0 1", 2);
+    assert!(eval > 300);
+    assert_eq!(score, 0);
+}
+
+#[test]
+fn test_no_reverse_futility_pruning_at_pv_nodes() {
+    let mut engine = Engine::new("4k3/8/8/8/8/8/8/3QK3 w - - 0 1").unwrap();
+    assert!(engine.evaluate() > 500);
+    assert_eq!(engine.negamax(2, -1000, 0), 0);
+}
+
+#[test]
+fn test_no_reverse_futility_pruning_with_only_pawns() {
+    // Zugzwang is too likely to trust the static eval
+    let (score, eval) = null_window("4k3/8/8/8/8/8/PPPP4/4K3 w - - 0 1", 2);
+    assert!(eval > 300);
+    assert_eq!(score, 0);
+}
+
+#[test]
+fn test_null_move_verification() {
+    // Only the knight can move and either move loses it, while passing keeps white well ahead
+    let fen = "K7/P1k5/8/8/7p/4p2P/4P3/7N w - - 0 1";
+    let zugzwang = |verification_depth| {
+        let mut engine = Engine::new(fen).unwrap();
+        engine.set_null_move_verification_depth(verification_depth);
+        engine.negamax(7, 99, 100)
+    };
+    assert!(Engine::new(fen).unwrap().evaluate() > 500);
+    assert_eq!(zugzwang(Some(7)), 99);
+    // Without verification, or below its depth, the null move fails high
+    assert_eq!(zugzwang(None), 100);
+    assert_eq!(zugzwang(Some(8)), 100);
+}
@@ -0,0 +1,80 @@
+use bbrs_core::engine::{Engine, Output, SelfCheck, START_POSITION};
+
+#[test]
+fn test_seldepth_counts_quiescence_plies() {
+    let search = |fen: &str, depth: u8| {
+        let mut engine = Engine::new(fen).unwrap();
+        engine.set_output(Output::sink());
+        engine.search_position(depth)
+    };
+    // Captures everywhere, which quiescence search follows past the horizon
+    let result = search(
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        3,
+    );
+    assert!(result.seldepth > result.depth);
+    // Nothing to capture
+    assert_eq!(search("k7/8/8/8/8/8/P7/K7 w - - 0 1", 1).seldepth, 1);
+}
+
+#[test]
+fn test_self_check_restores_the_engine() {
+    let fen = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 11";
+    let mut engine = Engine::new(fen).unwrap();
+    engine.set_output(Output::sink());
+    engine.load_moves(vec!["e2e4"]);
+    let checks = engine.self_check();
+    assert!(checks.iter().all(SelfCheck::passed), "{:?}", checks);
+    assert_eq!(engine.fen(), "8/2p5/3p4/KP5r/1R2Pp1k/8/6P1/8 b - e3 0 11");
+}
+
+#[test]
+fn test_search_stats_leave_the_search_unchanged() {
+    let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    let mut engine = Engine::new(fen).unwrap();
+    engine.set_output(Output::sink());
+    let plain = engine.search_position(5);
+    assert!(engine.search_stats().is_none());
+
+    engine.new_game();
+    engine.set_collect_stats(true);
+    let counted = engine.search_position(5);
+    assert_eq!(
+        (counted.nodes, counted.best_move),
+        (plain.nodes, plain.best_move)
+    );
+    let stats = engine.search_stats().unwrap();
+    assert_eq!(stats.nodes, counted.nodes);
+    assert!(stats.quiescence_nodes > 0 && stats.quiescence_nodes < stats.nodes);
+    assert!(stats.tt_hits > 0 && stats.null_move_tries > 0);
+    assert!(stats.first_move_cutoff_rate() > 50.0);
+    assert!(stats.iterations.branching_factor().is_some());
+}
+
+#[test]
+fn test_new_game_forgets_earlier_searches() {
+    let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    let mut fresh = Engine::new(fen).unwrap();
+    fresh.set_output(Output::sink());
+    let expected = fresh.search_position(5).nodes;
+
+    let mut engine = Engine::new(START_POSITION).unwrap();
+    engine.set_output(Output::sink());
+    engine.search_position(5);
+    engine.new_game();
+    engine.set_position(fen).unwrap();
+    assert_eq!(engine.search_position(5).nodes, expected);
+}
+
+#[test]
+fn test_root_moves_are_ordered_by_last_iteration() {
+    let mut engine = Engine::new(START_POSITION).unwrap();
+    engine.set_output(Output::sink());
+    let result = engine.search_position(4);
+    let root_moves = result.root_moves.iter().collect::<Vec<_>>();
+    assert_eq!(root_moves[0].move_, result.best_move);
+    assert!(root_moves
+        .windows(2)
+        .all(|pair| (pair[0].score, pair[0].iteration_nodes)
+            >= (pair[1].score, pair[1].iteration_nodes)));
+}
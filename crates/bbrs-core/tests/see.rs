@@ -0,0 +1,80 @@
+use bbrs_core::engine::{Engine, START_POSITION};
+
+/// Positions with the expected exchange outcome of a move, in centipawns with
+/// pawn 100, knight 300, bishop 325, rook 500 and queen 1000.
+const SEE_SUITE: &[(&str, &str, i32)] = &[
+    // An undefended pawn
+    (
+        "1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1",
+        "e1e5",
+        100,
+    ),
+    // A pawn defended by a pawn, taken with a rook
+    ("4k3/8/3p4/4p3/8/8/8/K3R3 w - - 0 1", "e1e5", -400),
+    // Knight takes a pawn defended by a pawn and x-rayed by a rook and queen
+    (
+        "1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1",
+        "d3e5",
+        -200,
+    ),
+    // Rook takes a knight defended by a rook, backed up by the other rook: R for N
+    ("4k3/4r3/8/4n3/8/8/4R3/4R1K1 w - - 0 1", "e2e5", 300),
+    // Quiet move onto a square attacked by a pawn
+    ("4k3/8/3p4/8/4N3/8/8/4K3 w - - 0 1", "e4c5", -300),
+    // Quiet move onto a safe square
+    ("4k3/8/8/8/4N3/8/8/4K3 w - - 0 1", "e4c5", 0),
+    // En passant, recaptured by a pawn
+    ("4k3/2p5/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6", 0),
+    // Promotion capture that cannot be recaptured
+    ("3r2k1/2P5/8/8/8/8/8/4K3 w - - 0 1", "c7d8q", 1400),
+    // Promotion recaptured by the king
+    ("8/2Pk4/8/8/8/8/8/4K3 w - - 0 1", "c7c8q", -100),
+    // The king cannot recapture a defended piece
+    ("3rk3/8/8/8/8/8/3r4/3RK3 b - - 0 1", "d2d1", 500),
+    // A pawn takes an undefended bishop
+    ("4k3/8/8/3p4/4B3/8/8/3QK3 b - - 0 1", "d5e4", 325),
+    // Queen takes a pawn defended by a bishop
+    ("4k3/6b1/8/4p3/8/8/8/4QK2 w - - 0 1", "e1e5", -900),
+];
+
+#[test]
+fn test_see_suite() {
+    for &(fen, move_, expected) in SEE_SUITE {
+        let mut engine = Engine::new(fen).unwrap();
+        let parsed = engine
+            .generate_legal_moves()
+            .into_iter()
+            .find(|&legal| engine.format_move(legal).eq_ignore_ascii_case(move_))
+            .unwrap_or_else(|| panic!("{} is not legal in {}", move_, fen));
+        assert_eq!(engine.see(parsed), expected, "{} in {}", move_, fen);
+    }
+}
+
+#[test]
+fn test_quiescence_depth_cap() {
+    // Qxd6 wins the queen only until cxd6 is seen
+    let mut engine = Engine::new("4k3/2p5/3q4/8/8/3Q4/8/4K3 w - - 0 1").unwrap();
+    let quiescence = |engine: &mut Engine, depth| {
+        // Results stored at another cap would be reused
+        engine.clear_hash();
+        engine.set_quiescence_depth(depth);
+        engine.quiescence(-50000, 50000)
+    };
+    let stand_pat = engine.evaluate();
+    assert_eq!(quiescence(&mut engine, 0), stand_pat);
+    let greedy = quiescence(&mut engine, 1);
+    assert!(greedy > stand_pat + 500);
+    assert!(quiescence(&mut engine, 2) < greedy - 500);
+}
+
+#[test]
+fn test_quiescence_in_check() {
+    // Mated after the fool's mate instead of standing pat
+    let mut engine = Engine::new(START_POSITION).unwrap();
+    engine.load_moves(vec!["f2f3", "e7e5", "g2g4", "d8h4"]);
+    assert!(engine.quiescence(-50000, 50000) < -40000);
+    // Black is a queen up, but every evasion from Nc7+ loses it
+    let mut engine = Engine::new("q3k3/2N5/8/8/8/8/8/4K3 b - - 0 1").unwrap();
+    let stand_pat = engine.evaluate();
+    assert!(engine.quiescence(-50000, 50000) < stand_pat - 500);
+}
@@ -0,0 +1,153 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use bbrs_core::engine::{Clock, IterationStats, SearchLimits, TimeControl, TimeManager};
+use common::engine;
+
+const FEN: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+#[test]
+fn test_predict_next_iteration() {
+    let mut stats = IterationStats::default();
+    stats.push(100, Duration::from_millis(1));
+    assert_eq!(stats.predict_next(), None);
+    stats.push(200, Duration::from_millis(2));
+    stats.push(800, Duration::from_millis(8));
+    // The geometric mean of 2 and 4
+    let branching_factor = stats.branching_factor().unwrap();
+    assert!((branching_factor - 8f64.sqrt()).abs() < 1e-9);
+    assert_eq!(
+        stats.predict_next(),
+        Some(Duration::from_millis(8).mul_f64(branching_factor))
+    );
+
+    let time = TimeManager::new(Some(Duration::from_secs(60)));
+    assert!(time.fits(Duration::from_secs(1)));
+    assert!(!time.fits(Duration::from_secs(61)));
+    assert!(TimeManager::new(None).fits(Duration::MAX));
+    assert!(TimeManager::ponder(Some(Duration::ZERO)).fits(Duration::MAX));
+}
+
+#[test]
+fn test_time_control() {
+    let seconds = Duration::from_secs;
+    let control = TimeControl::parse("40/5400+30:1800+30").unwrap();
+    assert_eq!(control.stages().len(), 2);
+    assert_eq!(control.stages()[0].moves, Some(40));
+    assert_eq!(control.stages()[1].time, seconds(1800));
+    assert_eq!(control.to_string(), "40/5400+30:1800+30");
+    assert_eq!(
+        TimeControl::parse("0.5+0.1").unwrap().to_string(),
+        "0.5+0.1"
+    );
+    assert!(TimeControl::parse("300:40/100").is_err());
+    assert!(TimeControl::parse("-").is_err());
+
+    let mut clock = Clock::new(control);
+    assert_eq!(clock.moves_to_go(), Some(40));
+    assert!(clock.record_move(seconds(100)));
+    assert_eq!(clock.remaining(), seconds(5330));
+    for _ in 0..39 {
+        assert!(clock.record_move(seconds(30)));
+    }
+    // The second stage's time arrives with the 40th move
+    assert_eq!(clock.remaining(), seconds(5330 + 1800));
+    assert_eq!(clock.moves_to_go(), None);
+    assert_eq!(clock.allotment(), seconds(7130) / 30 + seconds(30));
+
+    // A repeating last stage adds its time again after every period
+    let mut clock = Clock::new(TimeControl::parse("2/60").unwrap());
+    clock.record_move(seconds(10));
+    clock.record_move(seconds(10));
+    assert_eq!(clock.remaining(), seconds(100));
+    assert!(!clock.record_move(seconds(101)));
+    assert!(clock.is_flagged());
+    assert_eq!(clock.remaining(), Duration::ZERO);
+}
+
+#[test]
+fn test_clock_budget() {
+    let seconds = Duration::from_secs;
+    // Less than the clock's own allotment, which knows nothing of the overhead
+    let time = TimeManager::for_clock(seconds(7130), seconds(30), None);
+    assert!(time.budget() < Some(seconds(7130) / 30 + seconds(30)));
+    // The last move before the time control gets all of it, short of the overhead
+    let time = TimeManager::for_clock(Duration::from_millis(60_030), Duration::ZERO, Some(1));
+    assert_eq!(time.budget(), Some(seconds(60)));
+    // Nearly out of time, only the overhead is held back
+    let time = TimeManager::for_clock(Duration::from_millis(100), seconds(2), Some(10));
+    assert_eq!(time.budget(), Some(Duration::from_millis(70)));
+    let time = TimeManager::for_clock(Duration::from_millis(10), seconds(2), None);
+    assert_eq!(time.budget(), Some(Duration::ZERO));
+}
+
+#[test]
+fn test_budget_scaling() {
+    let seconds = Duration::from_secs;
+    let mut time = TimeManager::for_clock(seconds(7130), seconds(30), None);
+    let budget = time.budget().unwrap();
+    // A stable best move gives back half the budget, a fail low may use up to the hard limit
+    time.scale_budget(0.5);
+    assert_eq!(time.budget(), Some(budget / 2));
+    time.scale_budget(4.0);
+    assert_eq!(time.budget(), time.hard_limit());
+    // A fixed move time is never scaled
+    let mut time = TimeManager::new(Some(seconds(5)));
+    time.scale_budget(0.5);
+    assert_eq!(time.budget(), Some(seconds(5)));
+}
+
+#[test]
+fn test_move_time() {
+    let mut engine = engine(FEN);
+    let move_time = Duration::from_millis(200);
+    engine.set_time_manager(TimeManager::new(Some(move_time)));
+    let start = Instant::now();
+    let result = engine.search_position(60);
+    assert!(start.elapsed() < move_time + Duration::from_millis(100));
+    assert!(result.depth >= 1);
+    assert_eq!(result.pv[0], result.best_move);
+}
+
+#[test]
+fn test_clock_hard_limit() {
+    let seconds = Duration::from_secs;
+    let time = TimeManager::for_clock(seconds(7130), seconds(30), None);
+    assert_eq!(time.hard_limit(), time.budget().map(|budget| budget * 3));
+    // Nearly out of time, the hard limit is no further than the budget
+    let time = TimeManager::for_clock(Duration::from_millis(100), seconds(2), Some(10));
+    assert_eq!(time.hard_limit(), time.budget());
+}
+
+#[test]
+fn test_hard_limit_keeps_last_completed_iteration() {
+    let mut engine = engine(FEN);
+    let hard_time = Duration::from_millis(100);
+    engine.set_limits(SearchLimits {
+        hard_time: Some(hard_time),
+        ..SearchLimits::default()
+    });
+    let start = Instant::now();
+    let result = engine.search_position(60);
+    assert!(start.elapsed() < hard_time + Duration::from_millis(100));
+    assert!(result.depth >= 1 && result.depth < 60);
+
+    // The aborted iteration leaves no trace in the result
+    let completed = common::engine(FEN).search_position(result.depth);
+    assert_eq!(result.best_move, completed.best_move);
+    assert_eq!(result.score, completed.score);
+    assert_eq!(result.pv, completed.pv);
+}
+
+#[test]
+fn test_soft_limit_finishes_the_iteration() {
+    let mut engine = engine(FEN);
+    engine.set_limits(SearchLimits {
+        soft_time: Some(Duration::ZERO),
+        ..SearchLimits::default()
+    });
+    // Only checked between iterations, so the first one completes
+    let result = engine.search_position(60);
+    assert_eq!(result.depth, 1);
+}
@@ -0,0 +1,336 @@
+use bbrs_core::engine::{
+    announce, moves, Clock, Engine, GameState, IllegalMove, Output, Repertoire, Rng, Square,
+    TimeControl, TimeManager, START_POSITION,
+};
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::process;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+extern crate bbrs_core;
+
+const USAGE: &str = "Usage: play [--fen <fen>] [--side white|black] [--depth <n>] [--tc <tc>] \
+     [--repertoire <file.pgn>] [--announce] [--blindfold] [--quiz colors|knights] [--rounds <n>]";
+
+#[derive(Clone, Copy)]
+enum Quiz {
+    Colors,
+    Knights,
+}
+
+struct Options {
+    fen: String,
+    user_is_white: bool,
+    depth: Option<u8>,
+    /// Both sides play on a clock, and the engine searches until its share of it is used.
+    time_control: Option<TimeControl>,
+    repertoire: Option<Repertoire>,
+    /// Moves are written out in words rather than SAN.
+    announce: bool,
+    /// Blindfold play: the board is only printed when asked for.
+    show_board: bool,
+    quiz: Option<Quiz>,
+    rounds: usize,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        fen: START_POSITION.to_string(),
+        user_is_white: true,
+        depth: None,
+        time_control: None,
+        repertoire: None,
+        announce: false,
+        show_board: true,
+        quiz: None,
+        rounds: 10,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value for {}", arg));
+        match arg.as_str() {
+            "--fen" => options.fen = value()?.to_string(),
+            "--side" => {
+                options.user_is_white = match value()?.as_str() {
+                    "white" => true,
+                    "black" => false,
+                    side => return Err(format!("Invalid side: {}", side)),
+                }
+            }
+            "--depth" => {
+                let depth = value()?;
+                options.depth = Some(
+                    depth
+                        .parse()
+                        .map_err(|_| format!("Invalid depth: {}", depth))?,
+                );
+            }
+            "--tc" => {
+                let tc = value()?;
+                options.time_control =
+                    Some(TimeControl::parse(tc).map_err(|err| format!("{}: {}", err, tc))?);
+            }
+            "--repertoire" => {
+                let path = value()?;
+                // Polyglot books hash positions with their own key table, which we do not ship
+                if path.ends_with(".bin") {
+                    return Err("Polyglot books are not supported, use a PGN file".to_string());
+                }
+                let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+                options.repertoire = Some(Repertoire::from_pgn(&text)?);
+            }
+            "--announce" => options.announce = true,
+            "--blindfold" => {
+                options.announce = true;
+                options.show_board = false;
+            }
+            "--quiz" => {
+                options.quiz = Some(match value()?.as_str() {
+                    "colors" => Quiz::Colors,
+                    "knights" => Quiz::Knights,
+                    quiz => return Err(format!("Invalid quiz: {}", quiz)),
+                })
+            }
+            "--rounds" => {
+                let rounds = value()?;
+                options.rounds = rounds
+                    .parse()
+                    .map_err(|_| format!("Invalid rounds: {}", rounds))?;
+            }
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+    Ok(options)
+}
+
+/// Seeds from the clock, so book moves and quizzes vary between runs.
+fn random() -> Rng {
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_nanos() as u64);
+    Rng::new(seed)
+}
+
+/// Tells why the game is over, if it is.
+fn game_over(engine: &mut Engine) -> Option<&'static str> {
+    Some(match engine.game_state() {
+        GameState::Ongoing => return None,
+        GameState::Checkmate(_) => "Checkmate",
+        GameState::Stalemate => "Stalemate",
+        GameState::DrawByRepetition => "Draw by threefold repetition",
+        GameState::DrawByFiftyMove => "Draw by the fifty-move rule",
+        GameState::DrawByInsufficientMaterial => "Draw by insufficient material",
+        GameState::VariantEnd(_) => "Game over",
+    })
+}
+
+fn describe(options: &Options, san: &str) -> String {
+    if options.announce {
+        announce(san)
+    } else {
+        san.to_string()
+    }
+}
+
+fn format_clock(time: Duration) -> String {
+    let seconds = time.as_secs_f64();
+    format!("{}:{:04.1}", (seconds / 60.0) as u64, seconds % 60.0)
+}
+
+fn list_moves(engine: &mut Engine, options: &Options, moves: &[u32]) -> String {
+    moves
+        .iter()
+        .map(|&move_| describe(options, &engine.format_san(move_)))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn ask(lines: &mut impl Iterator<Item = String>, question: &str) -> Option<String> {
+    print!("{} ", question);
+    io::stdout().flush().unwrap();
+    lines.next().map(|line| line.trim().to_lowercase())
+}
+
+/// Asks for square colours or knight distances and prints the score at the end.
+fn run_quiz(
+    engine: &Engine,
+    options: &Options,
+    quiz: Quiz,
+    lines: &mut impl Iterator<Item = String>,
+) {
+    let mut random = random();
+    let mut correct = 0;
+    for _ in 0..options.rounds {
+        let square = Square::ALL[random.below(64)];
+        let right = match quiz {
+            Quiz::Colors => {
+                let Some(answer) = ask(lines, &format!("Is {} light or dark?", square)) else {
+                    break;
+                };
+                let color = if square.is_light() { "light" } else { "dark" };
+                let right = !answer.is_empty() && color.starts_with(&answer);
+                if !right {
+                    println!("{} is {}", square, color);
+                }
+                right
+            }
+            Quiz::Knights => {
+                let target = Square::ALL[random.below(64)];
+                let question = format!("How many knight moves from {} to {}?", square, target);
+                let Some(answer) = ask(lines, &question) else {
+                    break;
+                };
+                let path = engine.knight_path(square, target);
+                let right = answer.parse() == Ok(path.len() - 1);
+                if !right {
+                    let path = path.iter().map(Square::to_string).collect::<Vec<String>>();
+                    println!("{} moves: {}", path.len() - 1, path.join(" "));
+                }
+                right
+            }
+        };
+        if right {
+            correct += 1;
+            println!("Correct");
+        }
+    }
+    println!("Score: {}/{}", correct, options.rounds);
+}
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    let options = parse_args(&args).unwrap_or_else(|err| {
+        eprintln!("{}\n{}", err, USAGE);
+        process::exit(1);
+    });
+
+    let mut engine = Engine::new(START_POSITION).unwrap();
+    engine.set_output(Output::sink());
+    if let Err(err) = engine.set_position(&options.fen) {
+        eprintln!("{}: {}", err, options.fen);
+        process::exit(1);
+    }
+    engine.set_output(Output::stdout());
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines().map_while(Result::ok);
+    if let Some(quiz) = options.quiz {
+        run_quiz(&engine, &options, quiz, &mut lines);
+        return;
+    }
+
+    let mut random = random();
+    let mut in_book = options.repertoire.is_some();
+    if let Some(repertoire) = &options.repertoire {
+        println!("Loaded a repertoire of {} positions", repertoire.len());
+    }
+
+    // Without a clock the depth is the only limit
+    let depth = options.depth.unwrap_or(if options.time_control.is_some() {
+        32
+    } else {
+        6
+    });
+    let mut clocks = options
+        .time_control
+        .as_ref()
+        .map(|control| [Clock::new(control.clone()), Clock::new(control.clone())]);
+    let mut turn_start = Instant::now();
+
+    if options.show_board {
+        engine.print();
+    }
+
+    loop {
+        if let Some(reason) = game_over(&mut engine) {
+            println!("{}", reason);
+            break;
+        }
+
+        let book_moves = options
+            .repertoire
+            .as_ref()
+            .map_or(vec![], |repertoire| repertoire.moves(&engine).to_vec());
+        if in_book && book_moves.is_empty() {
+            println!("Out of book");
+            in_book = false;
+        }
+
+        let move_ = if engine.is_white_to_move() == options.user_is_white {
+            print!("Your move: ");
+            io::stdout().flush().unwrap();
+            let Some(line) = lines.next() else {
+                break;
+            };
+            match line.trim() {
+                "" => continue,
+                "quit" => break,
+                "board" => {
+                    engine.print();
+                    continue;
+                }
+                input => {
+                    let move_ = match engine.parse_san(input) {
+                        Some(move_) => move_,
+                        None => match engine.find_move(input) {
+                            Ok(move_) if move_ != moves::NULL_MOVE => move_,
+                            // Not coordinates either, so probably a mistyped SAN move
+                            Ok(_) | Err(IllegalMove::Notation) => {
+                                println!("Illegal move: {}", input);
+                                continue;
+                            }
+                            Err(reason) => {
+                                println!("Illegal move: {} ({})", input, reason);
+                                continue;
+                            }
+                        },
+                    };
+                    if in_book && !book_moves.contains(&move_) {
+                        println!(
+                            "Deviation: {} is not in your repertoire, which has {}",
+                            describe(&options, &engine.format_san(move_)),
+                            list_moves(&mut engine, &options, &book_moves)
+                        );
+                    }
+                    move_
+                }
+            }
+        } else if in_book {
+            let move_ = book_moves[random.below(book_moves.len())];
+            println!(
+                "Book move: {}",
+                describe(&options, &engine.format_san(move_))
+            );
+            move_
+        } else {
+            if let Some(clocks) = &clocks {
+                let clock = &clocks[usize::from(!engine.is_white_to_move())];
+                engine.set_time_manager(TimeManager::new(Some(clock.allotment())));
+            }
+            engine.set_output(Output::sink());
+            let result = engine.search_position(depth);
+            engine.set_output(Output::stdout());
+            let san = engine.format_san(result.best_move);
+            println!("Engine move: {}", describe(&options, &san));
+            result.best_move
+        };
+
+        if let Some(clocks) = clocks.as_mut() {
+            let mover = usize::from(!engine.is_white_to_move());
+            if !clocks[mover].record_move(turn_start.elapsed()) {
+                println!("{} lost on time", ["White", "Black"][mover]);
+                break;
+            }
+            println!(
+                "Clock: White {}, Black {}",
+                format_clock(clocks[0].remaining()),
+                format_clock(clocks[1].remaining())
+            );
+        }
+
+        engine.make_move(move_);
+        turn_start = Instant::now();
+        if options.show_board {
+            engine.print();
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use bbrs::engine::Engine;
+use bbrs_core::engine::{Engine, Output};
 
 #[allow(unused_variables)]
 fn main() {
@@ -8,6 +8,7 @@ fn main() {
     let start_position = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
 
     let mut engine = Engine::new(start_position).unwrap();
+    engine.set_output(Output::stdout());
 
     engine.print();
     engine.search_position(8);
@@ -0,0 +1,1192 @@
+// The command parsers borrow from the input line without spelling out the lifetime
+#![allow(mismatched_lifetime_syntaxes)]
+
+use bbrs_core::engine::{
+    parse_divide, parse_perft_epd, Checkpointing, Chess960, Engine, Horde, MoveOrdering, Output,
+    RacingKings, Rules, SelfCheck, Standard, Tee, TimeControl, TimeManager, BENCH_DEPTH,
+    BENCH_POSITIONS, DEFAULT_HASH_MEGABYTES, MAX_HASH_MEGABYTES, MAX_PLY, START_POSITION,
+};
+mod terminal;
+use std::fs::{self, File};
+use std::io::{self, BufRead, Write};
+extern crate bbrs_core;
+use std::path::PathBuf;
+use std::process::{self, Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+enum UCICommand<'a> {
+    Uci,
+    IsReady,
+    Position {
+        fen: Option<String>,
+        moves: Vec<&'a str>,
+    },
+    Go {
+        depth: Option<u32>,
+        nodes: Option<u64>,
+        movetime: Option<Duration>,
+        /// Time left on the clocks of White and Black.
+        time: [Option<Duration>; 2],
+        increment: [Duration; 2],
+        movestogo: Option<u32>,
+        /// Search until `stop`, however deep that is.
+        infinite: bool,
+        /// Search the position after the predicted reply until `ponderhit` or `stop`.
+        ponder: bool,
+        /// Only these root moves are searched.
+        searchmoves: Vec<&'a str>,
+        /// These root moves are left out, a bbrs extension.
+        excludemoves: Vec<&'a str>,
+    },
+    /// Ponders on the opponent's most likely replies, in the position with them to move.
+    Replies {
+        count: Option<usize>,
+        movetime: Option<Duration>,
+    },
+    /// A divided perft printed as `move: count` lines, as other engines do for `go perft`.
+    GoPerft(Option<u8>),
+    /// A divided perft, or with a hash size a hashed one, which may audit its hits.
+    Perft {
+        depth: Option<u32>,
+        hash: Option<usize>,
+        audit: Option<usize>,
+    },
+    /// Estimates a perft count from random paths sampled for a number of seconds.
+    PerftEstimate {
+        depth: Option<u8>,
+        seconds: Option<u64>,
+    },
+    Speedtest {
+        seconds: Option<u64>,
+    },
+    Bench {
+        depth: Option<u8>,
+    },
+    BenchVerify,
+    /// Compares move ordering configurations on the bench positions or an EPD file.
+    Ordering {
+        depth: Option<u8>,
+        epd: Option<String>,
+    },
+    SetOption {
+        name: String,
+        value: Option<String>,
+    },
+    Debug(bool),
+    /// Loads a checkpoint, from the given file or the `CheckpointFile` option.
+    Resume(Option<String>),
+    /// Reports the memory taken by the engine's tables.
+    Memory,
+    /// Prints the killer and history tables the last search left.
+    History,
+    /// Switches search statistics on or off, or without an argument prints the last ones.
+    Stats(Option<bool>),
+    /// The opponent played the predicted move, so the ponder search becomes a normal one.
+    PonderHit,
+    Stop,
+    UciNewGame,
+    Clear,
+    Quit,
+    Unknown(String),
+}
+
+const KIWIPETE_POSITION: &str =
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq -  0 1";
+
+const VARIANTS: &[&str] = &[
+    "chess",
+    "horde",
+    "racingkings",
+    #[cfg(feature = "crazyhouse")]
+    "crazyhouse",
+];
+
+fn parse_variant(name: &str) -> Option<Arc<dyn Rules>> {
+    match name {
+        "chess" => Some(Arc::new(Standard)),
+        "horde" => Some(Arc::new(Horde)),
+        "racingkings" => Some(Arc::new(RacingKings)),
+        #[cfg(feature = "crazyhouse")]
+        "crazyhouse" => Some(Arc::new(bbrs_core::engine::Crazyhouse)),
+        _ => None,
+    }
+}
+
+fn checkpointing(file: &Option<PathBuf>, interval: Duration) -> Option<Checkpointing> {
+    file.clone().map(|path| Checkpointing { path, interval })
+}
+
+fn parse_position(input: &str) -> UCICommand {
+    let mut tokens = input.split_whitespace().skip(1);
+    let subcommand = tokens.next();
+    let fen = match subcommand {
+        Some("startpos") => Some(START_POSITION.to_string()),
+        Some("kiwipete") => Some(KIWIPETE_POSITION.to_string()),
+        // The FEN runs until `moves`, however many fields it has; `take_while` consumes the keyword
+        Some("fen") => {
+            let fen = tokens
+                .by_ref()
+                .take_while(|&token| token != "moves")
+                .collect::<Vec<&str>>()
+                .join(" ");
+            return UCICommand::Position {
+                fen: Some(fen),
+                moves: tokens.collect(),
+            };
+        }
+        _ => return UCICommand::Unknown(input.to_string()),
+    };
+
+    let moves = if tokens.next() == Some("moves") {
+        tokens.collect()
+    } else {
+        vec![]
+    };
+
+    UCICommand::Position { fen, moves }
+}
+
+/// The parameters of `go`, which end the lists of `searchmoves` and `excludemoves`.
+const GO_KEYWORDS: [&str; 13] = [
+    "searchmoves",
+    "excludemoves",
+    "ponder",
+    "wtime",
+    "btime",
+    "winc",
+    "binc",
+    "movestogo",
+    "depth",
+    "nodes",
+    "mate",
+    "movetime",
+    "infinite",
+];
+
+fn parse_go(input: &str) -> UCICommand {
+    let mut tokens = input.split_whitespace().skip(1);
+    let (mut depth, mut nodes, mut movetime, mut movestogo) = (None, None, None, None);
+    let (mut time, mut increment) = ([None; 2], [Duration::ZERO; 2]);
+    let (mut infinite, mut ponder) = (false, false);
+    let (mut searchmoves, mut excludemoves) = (vec![], vec![]);
+    while let Some(token) = tokens.next() {
+        // GUIs may send a negative time once the flag has fallen
+        let mut millis = || {
+            tokens
+                .next()
+                .and_then(|ms| ms.parse::<i64>().ok())
+                .map(|ms| Duration::from_millis(ms.max(0) as u64))
+        };
+        match token {
+            "depth" => depth = tokens.next().and_then(|d| d.parse::<u32>().ok()),
+            "nodes" => nodes = tokens.next().and_then(|n| n.parse::<u64>().ok()),
+            "movetime" => movetime = millis(),
+            "wtime" => time[0] = millis(),
+            "btime" => time[1] = millis(),
+            "winc" => increment[0] = millis().unwrap_or_default(),
+            "binc" => increment[1] = millis().unwrap_or_default(),
+            "movestogo" => movestogo = tokens.next().and_then(|m| m.parse::<u32>().ok()),
+            "infinite" => infinite = true,
+            "ponder" => ponder = true,
+            // The moves run until the next keyword
+            "searchmoves" | "excludemoves" => {
+                let moves = if token == "searchmoves" {
+                    &mut searchmoves
+                } else {
+                    &mut excludemoves
+                };
+                while let Some(move_) = tokens.clone().next().filter(|&t| !GO_KEYWORDS.contains(&t))
+                {
+                    moves.push(move_);
+                    tokens.next();
+                }
+            }
+            _ => {}
+        }
+    }
+    UCICommand::Go {
+        depth,
+        nodes,
+        movetime,
+        time,
+        increment,
+        movestogo,
+        infinite,
+        ponder,
+        searchmoves,
+        excludemoves,
+    }
+}
+
+fn parse_replies(input: &str) -> UCICommand {
+    let mut tokens = input.split_whitespace().skip(1);
+    UCICommand::Replies {
+        count: tokens.next().and_then(|c| c.parse::<usize>().ok()),
+        movetime: tokens
+            .next()
+            .and_then(|ms| ms.parse::<u64>().ok())
+            .map(Duration::from_millis),
+    }
+}
+
+fn parse_perft(input: &str) -> UCICommand {
+    let mut tokens = input.split_whitespace().skip(1).peekable();
+    if tokens.next_if_eq(&"estimate").is_some() {
+        return UCICommand::PerftEstimate {
+            depth: tokens.next().and_then(|d| d.parse::<u8>().ok()),
+            seconds: tokens.next().and_then(|s| s.parse::<u64>().ok()),
+        };
+    }
+    let depth = tokens.next().and_then(|d| d.parse::<u32>().ok());
+    let (mut hash, mut audit) = (None, None);
+    while let Some(token) = tokens.next() {
+        match token {
+            "hash" => hash = tokens.next().and_then(|mb| mb.parse::<usize>().ok()),
+            "audit" => audit = tokens.next().and_then(|n| n.parse::<usize>().ok()),
+            _ => {}
+        }
+    }
+    UCICommand::Perft { depth, hash, audit }
+}
+
+fn parse_speedtest(input: &str) -> UCICommand {
+    let mut tokens = input.split_whitespace().skip(1);
+    let seconds = tokens.next().and_then(|s| s.parse::<u64>().ok());
+    UCICommand::Speedtest { seconds }
+}
+
+fn parse_bench(input: &str) -> UCICommand {
+    let mut tokens = input.split_whitespace().skip(1);
+    match tokens.next() {
+        Some("verify") => UCICommand::BenchVerify,
+        depth => UCICommand::Bench {
+            depth: depth.and_then(|d| d.parse::<u8>().ok()),
+        },
+    }
+}
+
+fn parse_ordering(input: &str) -> UCICommand {
+    let mut tokens = input.split_whitespace().skip(1);
+    UCICommand::Ordering {
+        depth: tokens.next().and_then(|d| d.parse::<u8>().ok()),
+        epd: tokens.next().map(str::to_string),
+    }
+}
+
+/// The positions of an EPD file: the first four fields of each line, the operations dropped.
+fn read_epd(path: &str) -> Result<Vec<String>, String> {
+    let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+    Ok(text
+        .lines()
+        .map(|line| line.split_whitespace().take(4).collect::<Vec<&str>>())
+        .filter(|fields| fields.len() == 4)
+        .map(|fields| fields.join(" "))
+        .collect())
+}
+
+fn parse_setoption(input: &str) -> UCICommand {
+    let mut tokens = input.split_whitespace().skip(1);
+    if tokens.next() != Some("name") {
+        return UCICommand::Unknown(input.to_string());
+    }
+    let name = tokens
+        .by_ref()
+        .take_while(|&token| token != "value")
+        .collect::<Vec<&str>>()
+        .join(" ");
+    let value = tokens.collect::<Vec<&str>>().join(" ");
+    UCICommand::SetOption {
+        name,
+        value: (!value.is_empty()).then_some(value),
+    }
+}
+
+fn parse_uci_command(input: &str) -> UCICommand {
+    let command = input.split_whitespace().next().unwrap_or("");
+    match command {
+        "uci" => UCICommand::Uci,
+        "isready" => UCICommand::IsReady,
+        "position" => parse_position(input),
+        "go" if input.split_whitespace().nth(1) == Some("perft") => {
+            UCICommand::GoPerft(input.split_whitespace().nth(2).and_then(|d| d.parse().ok()))
+        }
+        "go" => parse_go(input),
+        "replies" => parse_replies(input),
+        "perft" => parse_perft(input),
+        "speedtest" => parse_speedtest(input),
+        "bench" => parse_bench(input),
+        "ordering" => parse_ordering(input),
+        "setoption" => parse_setoption(input),
+        "debug" => match input.split_whitespace().nth(1) {
+            Some("on") => UCICommand::Debug(true),
+            Some("off") => UCICommand::Debug(false),
+            _ => UCICommand::Unknown(input.to_string()),
+        },
+        "resume" => {
+            let path = input
+                .split_whitespace()
+                .skip(1)
+                .collect::<Vec<&str>>()
+                .join(" ");
+            UCICommand::Resume((!path.is_empty()).then_some(path))
+        }
+        "memory" => UCICommand::Memory,
+        "history" => UCICommand::History,
+        "stats" => match input.split_whitespace().nth(1) {
+            Some("on") => UCICommand::Stats(Some(true)),
+            Some("off") => UCICommand::Stats(Some(false)),
+            None => UCICommand::Stats(None),
+            _ => UCICommand::Unknown(input.to_string()),
+        },
+        "ponderhit" => UCICommand::PonderHit,
+        "stop" => UCICommand::Stop,
+        "ucinewgame" => UCICommand::UciNewGame,
+        "clear" => UCICommand::Clear,
+        "quit" => UCICommand::Quit,
+        _ => UCICommand::Unknown(input.to_string()),
+    }
+}
+
+/// Scripted sessions run by `--selftest-uci`: the input sent to the engine and
+/// the lines expected in its output, in order, each given by its start. ` .. ` skips any
+/// fields, such as a seldepth that varies. `isready` is answered even while a
+/// search runs, so a session ends with its last search rather than waiting for it.
+const SELFTEST_SESSIONS: &[(&str, &str, &[&str])] = &[
+    (
+        "handshake",
+        "uci\nisready\n",
+        &["id name bbrs", "uciok", "readyok"],
+    ),
+    (
+        "search",
+        "position startpos moves e2e4 e7e5\ngo depth 2\n",
+        &["info depth", "bestmove "],
+    ),
+    (
+        "debug",
+        "debug on\ngo depth 3\ndebug off\nisready\n",
+        &["info string ebf", "bestmove ", "readyok"],
+    ),
+    (
+        "stop and ponderhit",
+        "position kiwipete\ngo depth 1\nstop\nponderhit\nisready\n",
+        &["bestmove ", "readyok"],
+    ),
+    (
+        "stop during search",
+        "position kiwipete\ngo depth 60\nisready\nstop\nposition startpos\ngo depth 1\n",
+        &["readyok", "bestmove ", "info depth 1", "bestmove "],
+    ),
+    (
+        "infinite",
+        "go infinite\nisready\nstop\n",
+        &["readyok", "bestmove "],
+    ),
+    (
+        "ponder",
+        "position startpos moves e2e4\ngo ponder wtime 60000 btime 60000\nisready\nponderhit\n\
+         isready\nstop\n",
+        &["readyok", "readyok", "bestmove "],
+    ),
+    (
+        "hash",
+        "setoption name Hash value 4\nsetoption name Hash value 0\nmemory\n",
+        &[
+            "info string hash 4 MB",
+            "Invalid Hash: 0",
+            "info string memory attacks ",
+        ],
+    ),
+    (
+        "threads",
+        "setoption name Threads value 1\nsetoption name Threads value 0\ngo depth 3\n",
+        &["Invalid Threads: 0", "info depth 3", "bestmove "],
+    ),
+    (
+        "searchmoves",
+        "position startpos\ngo searchmoves a2a3 depth 2\nreplies 2 400\n",
+        &["bestmove a2a3", "info string reply ", "info string reply "],
+    ),
+    (
+        "node limit",
+        "go nodes 3000\n",
+        &["info depth", "bestmove "],
+    ),
+    (
+        "move time",
+        "go movetime 100\n",
+        &["info depth", "bestmove "],
+    ),
+    (
+        "clock",
+        "position startpos moves e2e4\ngo wtime 1000 btime 1000 winc 10 binc 10\n",
+        &["info depth", "bestmove "],
+    ),
+    (
+        "multipv",
+        "setoption name MultiPV value 3\ngo depth 3\n",
+        &[
+            "info depth 3 .. multipv 1 ",
+            "info depth 3 .. multipv 2 ",
+            "info depth 3 .. multipv 3 ",
+            "bestmove ",
+        ],
+    ),
+    (
+        "stats",
+        "stats\nstats on\nposition startpos\ngo depth 4\nposition startpos\nstats\nisready\n",
+        &[
+            "Search statistics are off",
+            "bestmove ",
+            "Nodes: ",
+            "TT hits: ",
+            "Null moves: ",
+            "Beta cutoffs: ",
+            "Effective branching factor: ",
+            "readyok",
+        ],
+    ),
+    (
+        "go perft",
+        "position startpos\ngo perft 2\nisready\n",
+        &["a2a3: 20", "h2h4: 20", "Nodes searched: 400", "readyok"],
+    ),
+    (
+        "history",
+        "history\nposition startpos\ngo depth 4\nposition startpos\nhistory\nisready\n",
+        &[
+            "Killer moves:",
+            "  none",
+            "History scores",
+            "bestmove ",
+            "Killer moves:",
+            "    1 │ ",
+            "History scores",
+            "readyok",
+        ],
+    ),
+    (
+        "memory",
+        "memory\nisready\n",
+        &["info string memory attacks ", "readyok"],
+    ),
+    (
+        "setoption",
+        "setoption name UCI_Variant value horde\nsetoption name UCI_Chess960 value true\n\
+         setoption name Bogus value 1\nisready\n",
+        &["info string Unknown option: Bogus", "readyok"],
+    ),
+    (
+        "malformed input",
+        "position fen not a fen\nposition startpos moves e2e5\nposition startpos moves e2\n\
+         setoption\nfoo bar\n\nisready\n",
+        &[
+            "Invalid FEN",
+            "Invalid move: e2e5",
+            "Invalid move: e2",
+            "Unknown command: setoption",
+            "Unknown command: foo bar",
+            "readyok",
+        ],
+    ),
+];
+
+/// Runs a scripted session against a fresh engine process, returning its output.
+fn run_session(input: &str) -> io::Result<String> {
+    let mut child = Command::new(std::env::current_exe()?)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(input.as_bytes())?;
+    // Closing the input lets the last search finish, where `quit` would stop it
+    drop(stdin);
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!("exited with {}", output.status)));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Checks that every expected line starts some output line, in order.
+fn expect_lines(output: &str, expected: &[&str]) -> Result<(), String> {
+    let mut lines = output.lines();
+    for &pattern in expected {
+        let matches = |line: &str| {
+            let mut parts = pattern.split(" .. ");
+            let prefix = parts.next().unwrap_or_default();
+            let Some(mut rest) = line.strip_prefix(prefix) else {
+                return false;
+            };
+            parts.all(|part| {
+                rest.find(part)
+                    .map(|index| rest = &rest[index + part.len()..])
+                    .is_some()
+            })
+        };
+        if !lines.any(matches) {
+            return Err(format!("missing \"{}\"", pattern));
+        }
+    }
+    Ok(())
+}
+
+fn selftest() -> bool {
+    let mut passed = true;
+    for (name, input, expected) in SELFTEST_SESSIONS {
+        let result = run_session(input)
+            .map_err(|err| err.to_string())
+            .and_then(|output| expect_lines(&output, expected));
+        match result {
+            Ok(()) => println!("ok {}", name),
+            Err(err) => {
+                println!("FAILED {}: {}", name, err);
+                passed = false;
+            }
+        }
+    }
+    passed
+}
+
+/// The most threads the `Threads` option allows: one per core.
+fn cores() -> usize {
+    thread::available_parallelism().map_or(1, |cores| cores.get())
+}
+
+/// The time control `tune-hardware` tunes for unless given another: blitz, five minutes
+/// and three seconds a move.
+const DEFAULT_TIME_CONTROL: &str = "300+3";
+
+/// Recommends Hash and Threads settings for a time control, returning the exit code.
+fn tune_hardware(time_control: &str) -> i32 {
+    let time_control = match TimeControl::parse(time_control) {
+        Ok(time_control) => time_control,
+        Err(err) => {
+            eprintln!("{}: {}", err, time_control);
+            return 1;
+        }
+    };
+    let mut engine = Engine::new(START_POSITION).unwrap();
+    engine.set_output(Output::stdout());
+    println!("Calibrating, this takes a few seconds...");
+    engine.tune_hardware(&time_control);
+    0
+}
+
+/// Checks that this build plays correct chess, returning the exit code.
+fn self_check() -> i32 {
+    let mut engine = Engine::new(START_POSITION).unwrap();
+    engine.set_output(Output::stdout());
+    let checks = engine.self_check();
+    i32::from(!checks.iter().all(SelfCheck::passed))
+}
+
+/// A UCI engine run as a child process, asked for divided perfts with `go perft`.
+struct ReferenceEngine {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: io::BufReader<ChildStdout>,
+}
+
+impl ReferenceEngine {
+    fn spawn(path: &str) -> Result<Self, String> {
+        let mut child = Command::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|err| format!("{}: {}", path, err))?;
+        Ok(ReferenceEngine {
+            stdin: child.stdin.take().unwrap(),
+            stdout: io::BufReader::new(child.stdout.take().unwrap()),
+            child,
+        })
+    }
+
+    fn divide(&mut self, fen: &str, depth: u8) -> Result<Vec<(String, u64)>, String> {
+        writeln!(self.stdin, "position fen {}\ngo perft {}", fen, depth)
+            .map_err(|err| err.to_string())?;
+        let mut text = String::new();
+        loop {
+            let mut line = String::new();
+            match self.stdout.read_line(&mut line) {
+                Ok(0) => return Err("The reference engine quit".to_string()),
+                Ok(_) if line.starts_with("Nodes searched") => break,
+                Ok(_) => text += &line,
+                Err(err) => return Err(err.to_string()),
+            }
+        }
+        Ok(parse_divide(&text))
+    }
+}
+
+impl Drop for ReferenceEngine {
+    fn drop(&mut self) {
+        let _ = writeln!(self.stdin, "quit");
+        let _ = self.child.wait();
+    }
+}
+
+/// Checks the perft counts of every position of an EPD file up to `depth`: against a
+/// reference engine if one is given, which narrows a mismatch down to a position and the
+/// moves leading there, or else against the `;D<depth> <count>` counts stored in the file.
+fn perft_compare(path: &str, depth: &str, reference: Option<&str>) -> i32 {
+    let Ok(depth) = depth.parse::<u8>() else {
+        eprintln!("Invalid depth: {}", depth);
+        return 1;
+    };
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) => {
+            eprintln!("{}: {}", path, err);
+            return 1;
+        }
+    };
+    let mut reference = match reference.map(ReferenceEngine::spawn).transpose() {
+        Ok(reference) => reference,
+        Err(err) => {
+            eprintln!("{}", err);
+            return 1;
+        }
+    };
+    let mut engine = Engine::new(START_POSITION).unwrap();
+    engine.set_output(Output::sink());
+    let mut failures = 0;
+    for (fen, counts) in text.lines().filter_map(parse_perft_epd) {
+        if let Err(err) = engine.set_position(&fen) {
+            println!("FAIL {}: {}", fen, err);
+            failures += 1;
+            continue;
+        }
+        let result = match reference.as_mut() {
+            Some(reference) => engine
+                .find_perft_diff(depth, &mut |fen, depth| reference.divide(fen, depth))
+                .map(|diff| diff.map(|diff| diff.to_string())),
+            None => Ok(counts
+                .iter()
+                .filter(|&&(count_depth, _)| count_depth <= depth)
+                .map(|&(count_depth, count)| (count_depth, engine.perft_driver(count_depth), count))
+                .find(|&(_, ours, theirs)| ours != theirs)
+                .map(|(count_depth, ours, theirs)| {
+                    format!(
+                        "Depth: {}, bbrs {}, reference {}",
+                        count_depth, ours, theirs
+                    )
+                })),
+        };
+        match result {
+            Ok(None) => println!("ok {}", fen),
+            Ok(Some(diff)) => {
+                println!("FAIL {}\n{}", fen, diff);
+                failures += 1;
+            }
+            Err(err) => {
+                println!("FAIL {}: {}", fen, err);
+                failures += 1;
+            }
+        }
+    }
+    i32::from(failures > 0)
+}
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    let mut log = None;
+    match args.iter().map(String::as_str).collect::<Vec<&str>>()[..] {
+        [] => {}
+        ["--selftest-uci"] => process::exit(if selftest() { 0 } else { 1 }),
+        ["selfcheck"] => process::exit(self_check()),
+        ["tune-hardware"] => process::exit(tune_hardware(DEFAULT_TIME_CONTROL)),
+        ["tune-hardware", time_control] => process::exit(tune_hardware(time_control)),
+        ["perft-compare", path, depth] => process::exit(perft_compare(path, depth, None)),
+        ["perft-compare", path, depth, reference] => {
+            process::exit(perft_compare(path, depth, Some(reference)))
+        }
+        ["--log", path] => match File::create(path) {
+            Ok(file) => log = Some(file),
+            Err(err) => {
+                eprintln!("Failed to open log file {}: {}", path, err);
+                process::exit(1);
+            }
+        },
+        _ => {
+            eprintln!(
+                "Usage: uci [--log <file> | --selftest-uci | selfcheck | tune-hardware [<tc>] \
+                 | perft-compare <file.epd> <depth> [<engine>]]"
+            );
+            process::exit(1);
+        }
+    }
+
+    // With a log, everything we print is teed into it and received commands are logged as `> command`
+    let (output, mut input_log) = match log {
+        Some(file) => (
+            Output::new(Tee(io::stdout(), file.try_clone().unwrap())),
+            Some(file),
+        ),
+        None => (Output::stdout(), None),
+    };
+
+    let stdin = io::stdin();
+    let handle = stdin.lock();
+    let reader = io::BufReader::new(handle);
+    let mut engine = Engine::new(START_POSITION).unwrap();
+    engine.set_output(output.clone());
+    // The search runs on a worker thread that owns the engine until it is joined, so
+    // commands, `stop` above all, are still read while it runs
+    let stop = engine.stop_handle();
+    let ponderhit = engine.ponderhit_handle();
+    let mut idle = Some(engine);
+    let mut search: Option<JoinHandle<Engine>> = None;
+    let mut checkpoint_file: Option<PathBuf> = None;
+    let mut checkpoint_interval = Duration::from_secs(60);
+
+    for line in reader.lines().map_while(Result::ok) {
+        if let Some(file) = input_log.as_mut() {
+            let _ = writeln!(file, "> {}", line);
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        let command = parse_uci_command(&line);
+        match command {
+            UCICommand::IsReady => {
+                writeln!(output, "readyok");
+                continue;
+            }
+            // Without a search running there is nothing to convert
+            UCICommand::PonderHit => {
+                if search.is_some() {
+                    ponderhit.store(true, Ordering::Relaxed);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        // Any other command waits for the search, which only `stop` and `quit` cut short
+        if let Some(search) = search.take() {
+            if let UCICommand::Stop | UCICommand::Quit = command {
+                stop.store(true, Ordering::Relaxed);
+            }
+            idle = Some(search.join().expect("The search thread panicked"));
+            stop.store(false, Ordering::Relaxed);
+            ponderhit.store(false, Ordering::Relaxed);
+        }
+        let engine = idle
+            .as_mut()
+            .expect("The engine is idle once the search is joined");
+        match command {
+            UCICommand::Uci => {
+                writeln!(output, "id name bbrs");
+                writeln!(output, "id author Blaze Shomida");
+                writeln!(
+                    output,
+                    "option name Hash type spin default {} min 1 max {}",
+                    DEFAULT_HASH_MEGABYTES, MAX_HASH_MEGABYTES
+                );
+                writeln!(output, "option name Ponder type check default false");
+                writeln!(
+                    output,
+                    "option name Threads type spin default 1 min 1 max {}",
+                    cores()
+                );
+                writeln!(output, "option name UCI_Chess960 type check default false");
+                writeln!(
+                    output,
+                    "option name MultiPV type spin default 1 min 1 max 256"
+                );
+                writeln!(
+                    output,
+                    "option name QueenPromotionsOnly type check default false"
+                );
+                writeln!(
+                    output,
+                    "option name CheckpointFile type string default <empty>"
+                );
+                writeln!(
+                    output,
+                    "option name CheckpointInterval type spin default 60 min 1 max 86400"
+                );
+                writeln!(
+                    output,
+                    "option name UCI_Variant type combo default chess {}",
+                    VARIANTS
+                        .iter()
+                        .map(|variant| format!("var {}", variant))
+                        .collect::<Vec<String>>()
+                        .join(" ")
+                );
+                writeln!(output, "uciok");
+            }
+            UCICommand::IsReady | UCICommand::PonderHit => {
+                unreachable!("isready and ponderhit are handled right away")
+            }
+            UCICommand::Position { fen, moves } => {
+                let fen = fen.unwrap_or(START_POSITION.to_string());
+                match engine.set_position(&fen) {
+                    Ok(()) => engine.load_moves(moves),
+                    Err(err) => writeln!(output, "{}\n", err),
+                }
+            }
+            UCICommand::Go {
+                depth,
+                nodes,
+                movetime,
+                time,
+                increment,
+                movestogo,
+                infinite,
+                ponder,
+                searchmoves,
+                excludemoves,
+            } => {
+                let side = usize::from(!engine.is_white_to_move());
+                // A node or time limit alone searches as deep as it allows, and so does
+                // pondering until `ponderhit` brings in the limits
+                let default_depth = if infinite
+                    || ponder
+                    || nodes.is_some()
+                    || movetime.is_some()
+                    || time[side].is_some()
+                {
+                    MAX_PLY as u32 - 1
+                } else {
+                    6
+                };
+                engine.set_node_limit(nodes);
+                engine.set_infinite(infinite);
+                let searchmoves = searchmoves
+                    .iter()
+                    .filter_map(|move_| engine.parse_move(move_))
+                    .collect();
+                engine.set_search_moves(searchmoves);
+                let excludemoves = excludemoves
+                    .iter()
+                    .filter_map(|move_| engine.parse_move(move_))
+                    .collect();
+                engine.set_excluded_moves(excludemoves);
+                let time_manager = if movetime.is_some() {
+                    TimeManager::new(movetime)
+                } else if let Some(remaining) = time[side] {
+                    TimeManager::for_clock(remaining, increment[side], movestogo)
+                } else {
+                    TimeManager::default()
+                };
+                engine.set_time_manager(time_manager.pondering(ponder));
+                let mut engine = idle.take().unwrap();
+                let output = output.clone();
+                search = Some(thread::spawn(move || {
+                    engine.search_position(depth.unwrap_or(default_depth) as u8);
+                    writeln!(output);
+                    engine
+                }));
+            }
+            UCICommand::Replies { count, movetime } => {
+                let mut engine = idle.take().unwrap();
+                let output = output.clone();
+                search = Some(thread::spawn(move || {
+                    engine.analyse_replies(
+                        count.unwrap_or(3),
+                        movetime.unwrap_or(Duration::from_secs(5)),
+                    );
+                    writeln!(output);
+                    engine
+                }));
+            }
+            UCICommand::Perft { depth, hash, audit } => {
+                let depth = depth.unwrap_or(1) as u8;
+                if hash.is_some() || audit.is_some() {
+                    engine.perft_hashed(depth, hash.unwrap_or(16), audit);
+                } else {
+                    engine.perft(depth);
+                }
+            }
+            UCICommand::GoPerft(depth) => {
+                let divide = engine.divide(depth.unwrap_or(1));
+                for (move_, nodes) in divide.iter() {
+                    writeln!(output, "{}: {}", move_, nodes);
+                }
+                let total = divide.iter().map(|&(_, nodes)| nodes).sum::<u64>();
+                writeln!(output, "\nNodes searched: {}\n", total);
+            }
+            UCICommand::PerftEstimate { depth, seconds } => {
+                engine.perft_estimate(
+                    depth.unwrap_or(1),
+                    Duration::from_secs(seconds.unwrap_or(2)),
+                );
+            }
+            UCICommand::Speedtest { seconds } => {
+                engine.speedtest(Duration::from_secs(seconds.unwrap_or(2)));
+            }
+            UCICommand::Bench { depth } => {
+                engine.bench(depth.unwrap_or(BENCH_DEPTH));
+            }
+            UCICommand::BenchVerify => {
+                engine.bench_verify();
+            }
+            UCICommand::Ordering { depth, epd } => {
+                let positions = match epd.as_deref().map(read_epd) {
+                    Some(Ok(positions)) => positions,
+                    Some(Err(err)) => {
+                        writeln!(output, "{}\n", err);
+                        continue;
+                    }
+                    None => BENCH_POSITIONS.iter().map(|fen| fen.to_string()).collect(),
+                };
+                let positions = positions.iter().map(String::as_str).collect::<Vec<&str>>();
+                let depth = depth.unwrap_or(BENCH_DEPTH);
+                if let Err(err) =
+                    engine.ordering_experiment(&positions, depth, &MoveOrdering::experiments())
+                {
+                    writeln!(output, "{}\n", err);
+                }
+            }
+            UCICommand::SetOption { name, value } => match name.to_lowercase().as_str() {
+                "uci_chess960" => {
+                    if value.as_deref() == Some("true") {
+                        engine.set_rules(Arc::new(Chess960));
+                    } else {
+                        engine.set_rules(Arc::new(Standard));
+                    }
+                }
+                "uci_variant" => match value.as_deref().and_then(parse_variant) {
+                    Some(rules) => engine.set_rules(rules),
+                    None => writeln!(output, "Unknown variant: {}\n", value.unwrap_or_default()),
+                },
+                "multipv" => match value.as_deref().and_then(|v| v.parse().ok()) {
+                    Some(lines @ 1..=256) => engine.set_multi_pv(lines),
+                    _ => writeln!(output, "Invalid MultiPV: {}\n", value.unwrap_or_default()),
+                },
+                // The GUI decides when to ponder, so there is nothing to set
+                "ponder" => {}
+                "hash" => match value.as_deref().and_then(|v| v.parse().ok()) {
+                    Some(megabytes @ 1..=MAX_HASH_MEGABYTES) => {
+                        engine.set_hash_size(megabytes);
+                        writeln!(output, "info string hash {} MB", engine.hash_size());
+                    }
+                    _ => writeln!(output, "Invalid Hash: {}\n", value.unwrap_or_default()),
+                },
+                "threads" => match value.as_deref().and_then(|v| v.parse().ok()) {
+                    Some(threads) if (1..=cores()).contains(&threads) => {
+                        engine.set_threads(threads)
+                    }
+                    _ => writeln!(output, "Invalid Threads: {}\n", value.unwrap_or_default()),
+                },
+                "queenpromotionsonly" => {
+                    engine.set_queen_promotions_only(value.as_deref() == Some("true"))
+                }
+                "checkpointfile" => {
+                    checkpoint_file = value.filter(|path| path != "<empty>").map(PathBuf::from);
+                    engine.set_checkpointing(checkpointing(&checkpoint_file, checkpoint_interval));
+                }
+                "checkpointinterval" => match value.as_deref().and_then(|v| v.parse().ok()) {
+                    Some(seconds) if seconds > 0 => {
+                        checkpoint_interval = Duration::from_secs(seconds);
+                        engine.set_checkpointing(checkpointing(
+                            &checkpoint_file,
+                            checkpoint_interval,
+                        ));
+                    }
+                    _ => writeln!(output, "Invalid interval: {}\n", value.unwrap_or_default()),
+                },
+                _ => writeln!(output, "info string Unknown option: {}", name),
+            },
+            UCICommand::Debug(debug) => engine.set_debug(debug),
+            UCICommand::Resume(path) => match path.map(PathBuf::from).or(checkpoint_file.clone()) {
+                Some(path) => match engine.resume(&path) {
+                    Ok(checkpoint) => writeln!(
+                        output,
+                        "info string resumed {} at depth {}",
+                        checkpoint.fen, checkpoint.depth
+                    ),
+                    Err(err) => writeln!(output, "{}\n", err),
+                },
+                None => writeln!(output, "No checkpoint file given\n"),
+            },
+            UCICommand::History => engine.print_ordering_tables(),
+            UCICommand::Stats(Some(collect)) => engine.set_collect_stats(collect),
+            UCICommand::Stats(None) => match engine.search_stats() {
+                Some(stats) => writeln!(output, "{}\n", stats),
+                None => writeln!(output, "Search statistics are off, see stats on\n"),
+            },
+            UCICommand::Memory => {
+                let memory = engine.memory();
+                writeln!(
+                    output,
+                    "info string memory attacks {} KiB hash {} KiB pawns {} KiB correction {} KiB total {} KiB",
+                    memory.attack_table / 1024,
+                    memory.transposition_table / 1024,
+                    memory.pawn_table / 1024,
+                    memory.correction_history / 1024,
+                    memory.total() / 1024
+                )
+            }
+            // Any search was stopped and joined above
+            UCICommand::Stop => {}
+            UCICommand::UciNewGame => {
+                engine.new_game();
+                engine.set_position(START_POSITION).unwrap();
+            }
+            UCICommand::Clear => {
+                if let Err(err) = terminal::clear_screen() {
+                    writeln!(output, "Failed to clear the screen: {}\n", err);
+                }
+            }
+            UCICommand::Quit => process::exit(0),
+            UCICommand::Unknown(command) => writeln!(output, "Unknown command: {}\n", command),
+        };
+    }
+    // Input ended, as when commands are piped in, so let the last search finish
+    if let Some(search) = search {
+        search.join().expect("The search thread panicked");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(input: &str) -> (String, Vec<&str>) {
+        match parse_position(input) {
+            UCICommand::Position { fen, moves } => (fen.unwrap(), moves),
+            _ => panic!("not a position command: {}", input),
+        }
+    }
+
+    #[test]
+    fn test_parse_position_fen() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 3 20";
+        assert_eq!(
+            position(&format!("position fen {}", fen)),
+            (fen.to_string(), vec![])
+        );
+        assert_eq!(
+            position(&format!("position fen {} moves e1g1 e8c8", fen)),
+            (fen.to_string(), vec!["e1g1", "e8c8"])
+        );
+    }
+
+    #[test]
+    fn test_parse_position_short_fen() {
+        let fen = "8/8/8/8/8/8/4k3/4K3 w - -";
+        assert_eq!(
+            position(&format!("position fen {}", fen)),
+            (fen.to_string(), vec![])
+        );
+        assert_eq!(
+            position(&format!("position   fen {}  moves  e1d1 ", fen)),
+            (fen.to_string(), vec!["e1d1"])
+        );
+        assert!(Engine::new(fen).is_ok());
+    }
+
+    #[test]
+    fn test_parse_position_edge_cases() {
+        assert_eq!(position("position fen"), (String::new(), vec![]));
+        assert_eq!(
+            position("position fen moves e2e4"),
+            (String::new(), vec!["e2e4"])
+        );
+        assert_eq!(
+            position("position startpos moves"),
+            (START_POSITION.to_string(), vec![])
+        );
+        assert_eq!(
+            position("position startpos moves e2e4 e7e5"),
+            (START_POSITION.to_string(), vec!["e2e4", "e7e5"])
+        );
+        assert!(matches!(
+            parse_position("position e2e4"),
+            UCICommand::Unknown(_)
+        ));
+    }
+    #[test]
+    fn test_parse_go() {
+        assert!(matches!(
+            parse_go("go depth 5 nodes 100000"),
+            UCICommand::Go {
+                depth: Some(5),
+                nodes: Some(100000),
+                movetime: None,
+                ..
+            }
+        ));
+        assert!(matches!(
+            parse_go("go nodes 500000"),
+            UCICommand::Go {
+                depth: None,
+                nodes: Some(500000),
+                movetime: None,
+                ..
+            }
+        ));
+        assert!(matches!(
+            parse_go("go"),
+            UCICommand::Go {
+                depth: None,
+                nodes: None,
+                movetime: None,
+                time: [None, None],
+                movestogo: None,
+                ..
+            }
+        ));
+        assert!(matches!(
+            parse_go("go movetime 2000"),
+            UCICommand::Go {
+                depth: None,
+                nodes: None,
+                movetime: Some(time),
+                ..
+            } if time == Duration::from_millis(2000)
+        ));
+        assert!(matches!(
+            parse_go("go wtime 60000 btime -5 winc 1000 movestogo 20"),
+            UCICommand::Go {
+                movetime: None,
+                time: [Some(white), Some(Duration::ZERO)],
+                increment: [winc, Duration::ZERO],
+                movestogo: Some(20),
+                ..
+            } if white == Duration::from_secs(60) && winc == Duration::from_secs(1)
+        ));
+        assert!(matches!(
+            parse_go("go infinite"),
+            UCICommand::Go {
+                depth: None,
+                infinite: true,
+                ponder: false,
+                ..
+            }
+        ));
+        assert!(matches!(
+            parse_go("go searchmoves e2e4 d2d4 depth 3"),
+            UCICommand::Go {
+                depth: Some(3),
+                ref searchmoves,
+                ..
+            } if searchmoves == &["e2e4", "d2d4"]
+        ));
+        assert!(matches!(
+            parse_go("go excludemoves e2e4 searchmoves d2d4 e2e4"),
+            UCICommand::Go {
+                ref searchmoves,
+                ref excludemoves,
+                ..
+            } if searchmoves == &["d2d4", "e2e4"] && excludemoves == &["e2e4"]
+        ));
+        assert!(matches!(
+            parse_go("go ponder wtime 1000 btime 1000"),
+            UCICommand::Go {
+                infinite: false,
+                ponder: true,
+                time: [Some(_), Some(_)],
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,37 @@
+use std::io::{self, Write};
+
+/// Clears the screen and moves the cursor to the top left corner.
+pub fn clear_screen() -> io::Result<()> {
+    enable_ansi();
+    let mut stdout = io::stdout().lock();
+    // Erase the display and the scrollback, then home the cursor
+    stdout.write_all(b"\x1b[2J\x1b[3J\x1b[H")?;
+    stdout.flush()
+}
+
+/// Windows consoles only interpret escape sequences once virtual terminal
+/// processing is turned on for the output handle.
+#[cfg(windows)]
+fn enable_ansi() {
+    use std::ffi::c_void;
+
+    const STD_OUTPUT_HANDLE: u32 = -11i32 as u32;
+    const ENABLE_VIRTUAL_TERMINAL_PROCESSING: u32 = 0x0004;
+
+    extern "system" {
+        fn GetStdHandle(handle: u32) -> *mut c_void;
+        fn GetConsoleMode(handle: *mut c_void, mode: *mut u32) -> i32;
+        fn SetConsoleMode(handle: *mut c_void, mode: u32) -> i32;
+    }
+
+    unsafe {
+        let handle = GetStdHandle(STD_OUTPUT_HANDLE);
+        let mut mode = 0;
+        if GetConsoleMode(handle, &mut mode) != 0 {
+            SetConsoleMode(handle, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING);
+        }
+    }
+}
+
+#[cfg(not(windows))]
+fn enable_ansi() {}
@@ -0,0 +1,292 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Output, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn uci(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_uci"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn session(input: &str) -> Vec<String> {
+    let output = uci(&[], input);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+fn position(lines: &[String], prefix: &str) -> usize {
+    lines
+        .iter()
+        .position(|line| line.starts_with(prefix))
+        .unwrap_or_else(|| panic!("missing \"{}\" in {:#?}", prefix, lines))
+}
+
+#[test]
+fn test_handshake() {
+    let lines = session("uci\nisready\nquit\n");
+    assert_eq!(lines[0], "id name bbrs");
+    assert!(lines
+        .iter()
+        .any(|line| line.starts_with("option name UCI_Variant")));
+    assert!(position(&lines, "uciok") < position(&lines, "readyok"));
+}
+
+#[test]
+fn test_go_reports_bestmove_before_next_command() {
+    // `position` waits for the search, while `stop` could cut it short
+    let lines =
+        session("position startpos moves e2e4\ngo depth 2\nposition startpos\nisready\nquit\n");
+    let bestmove = position(&lines, "bestmove ");
+    assert!(position(&lines, "info depth") < bestmove);
+    assert!(position(&lines, "info string nodes per move") < bestmove);
+    assert!(bestmove < position(&lines, "readyok"));
+    assert_eq!(
+        lines
+            .iter()
+            .filter(|line| line.starts_with("bestmove"))
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn test_deep_iterations_report_the_current_move() {
+    let lines = session(
+        "position fen 4k3/8/8/8/8/8/3PPP2/4K3 w - - 0 1\ngo depth 8\nposition startpos\nquit\n",
+    );
+    let numbers = lines
+        .iter()
+        .filter(|line| line.starts_with("info depth 8 ") && line.contains(" currmove "))
+        .map(|line| line.split_whitespace().last().unwrap().parse().unwrap())
+        .collect::<Vec<u32>>();
+    assert!(!numbers.is_empty());
+    assert_eq!(numbers, (1..=numbers.len() as u32).collect::<Vec<u32>>());
+    assert!(!lines
+        .iter()
+        .any(|line| line.starts_with("info depth 7 ") && line.contains(" currmove ")));
+    assert!(position(&lines, "info depth 8 time") < position(&lines, "info depth 8 seldepth"));
+}
+
+#[test]
+fn test_long_iterations_report_progress() {
+    // A time limit may stop before an iteration runs long, so this stops the search itself
+    // once it has reported progress, or after long enough that it should have. Lines are
+    // read on another thread so that a silent engine cannot hold up the deadline.
+    let mut child = Command::new(env!("CARGO_BIN_EXE_uci"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    stdin
+        .write_all(
+            b"position fen r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1\n\
+              go infinite\n",
+        )
+        .unwrap();
+    let stdout = child.stdout.take().unwrap();
+    let (sender, receiver) = mpsc::channel();
+    let reader = thread::spawn(move || {
+        for line in BufReader::new(stdout).lines() {
+            sender.send(line.unwrap()).unwrap();
+        }
+    });
+    let deadline = Instant::now() + Duration::from_secs(30);
+    let mut lines = vec![];
+    while let Ok(line) = receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+        let progress = line.starts_with("info nodes ");
+        lines.push(line);
+        if progress {
+            break;
+        }
+    }
+    stdin.write_all(b"stop\nquit\n").unwrap();
+    lines.extend(receiver);
+    reader.join().unwrap();
+    child.wait().unwrap();
+    let progress = position(&lines, "info nodes ");
+    assert!(lines[progress].contains(" hashfull ") && lines[progress].contains(" time "));
+    assert!(progress < position(&lines, "bestmove "));
+}
+
+#[test]
+fn test_mates_are_reported_in_moves() {
+    let lines = session(
+        "position fen r5rk/5p1p/5R2/4B3/8/8/7P/7K w - - 0 1\ngo depth 5\n\
+         position fen k7/1Q6/1K6/8/8/8/8/8 b - - 0 1\ngo depth 3\nposition startpos\nquit\n",
+    );
+    let scores = lines
+        .iter()
+        .filter_map(|line| line.split(" score ").nth(1))
+        .map(|score| {
+            score
+                .split_whitespace()
+                .take(2)
+                .collect::<Vec<&str>>()
+                .join(" ")
+        })
+        .collect::<Vec<String>>();
+    assert_eq!(scores[scores.len() - 3..], ["mate 3", "mate 3", "mate 0"]);
+    assert!(scores.iter().all(|score| score.starts_with("mate ")
+        || score
+            .strip_prefix("cp ")
+            .unwrap()
+            .parse::<i32>()
+            .unwrap()
+            .abs()
+            < 10_000));
+}
+
+#[test]
+fn test_malformed_input_is_reported() {
+    let lines = session(
+        "position fen 8/8/8 w\nposition startpos moves e7e5\nsetoption name UCI_Variant value nope\n\
+         setoption name Bogus value 1\n\
+         go depth 1 extra\nponderhit\n\nisready\nquit\n",
+    );
+    position(&lines, "Invalid FEN");
+    position(&lines, "Invalid move: e7e5");
+    position(&lines, "Unknown variant: nope");
+    position(&lines, "info string Unknown option: Bogus");
+    position(&lines, "bestmove ");
+    position(&lines, "readyok");
+}
+
+#[test]
+fn test_chess960_castles_as_king_takes_rook() {
+    let fen = "1r2kr2/5p2/8/8/8/8/5P2/1R2KR2 w FBfb - 0 1";
+    let lines = session(&format!(
+        "setoption name UCI_Chess960 value true\nposition fen {fen}\ngo perft 1\n\
+         position fen {fen} moves e1b1\ngo perft 1\nquit\n"
+    ));
+    let white = position(&lines, "Nodes searched: ");
+    let moves = |lines: &[String]| {
+        lines
+            .iter()
+            .filter_map(|line| line.strip_suffix(": 1"))
+            .map(str::to_string)
+            .collect::<Vec<String>>()
+    };
+    let white_moves = moves(&lines[..white]);
+    assert!(white_moves.contains(&"e1f1".to_string()));
+    assert!(white_moves.contains(&"e1b1".to_string()));
+    assert!(!white_moves.contains(&"e1g1".to_string()));
+    // The same notation is read back, after which the rook on d1 stops black castling long
+    assert!(!lines.iter().any(|line| line.starts_with("Invalid move")));
+    let black_moves = moves(&lines[white + 1..]);
+    assert!(black_moves.contains(&"e8f8".to_string()));
+    assert!(!black_moves.contains(&"e8b8".to_string()));
+}
+
+#[test]
+fn test_isready_and_stop_during_search() {
+    let lines = session("position kiwipete\ngo depth 60\nisready\nstop\nquit\n");
+    assert!(position(&lines, "readyok") < position(&lines, "bestmove "));
+}
+
+#[test]
+fn test_ponder_search_waits_for_ponderhit() {
+    let lines =
+        session("position startpos moves e2e4\ngo ponder depth 2\nisready\nponderhit\nquit\n");
+    let bestmove = position(&lines, "bestmove ");
+    assert!(position(&lines, "readyok") < bestmove);
+    assert!(lines[bestmove].contains(" ponder "));
+}
+
+#[test]
+fn test_exits_at_end_of_input() {
+    let lines = session("isready\n");
+    assert_eq!(lines, vec!["readyok"]);
+}
+
+#[test]
+fn test_selftest_mode() {
+    let output = uci(&["--selftest-uci"], "");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    assert!(!stdout.contains("FAILED"));
+}
+
+#[test]
+fn test_selfcheck_passes() {
+    let output = uci(&["selfcheck"], "");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    let names = stdout
+        .lines()
+        .map(|line| {
+            line.strip_prefix("PASS ")
+                .unwrap()
+                .split(':')
+                .next()
+                .unwrap()
+        })
+        .collect::<Vec<&str>>();
+    assert_eq!(names, ["perft", "attacks", "zobrist", "bench"]);
+}
+
+#[test]
+fn test_log_records_both_directions() {
+    let path = std::env::temp_dir().join(format!("bbrs-uci-{}.log", std::process::id()));
+    let output = uci(&["--log", path.to_str().unwrap()], "isready\nquit\n");
+    assert!(output.status.success());
+    let log = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(log, "> isready\nreadyok\n> quit\n");
+}
+
+#[test]
+fn test_perft_compare() {
+    let path = std::env::temp_dir().join(format!("bbrs-{}-perft.epd", std::process::id()));
+    std::fs::write(
+        &path,
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - ;D1 20 ;D2 400 ;D3 8902\n\
+         r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - ;D1 48 ;D2 2040\n",
+    )
+    .unwrap();
+    let path = path.to_str().unwrap();
+
+    let output = uci(&["perft-compare", path, "2"], "");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!output.status.success());
+    assert!(stdout.starts_with("ok rnbqkbnr/"), "{}", stdout);
+    assert!(stdout.contains("FAIL r3k2r/"), "{}", stdout);
+    assert!(
+        stdout.contains("Depth: 2, bbrs 2039, reference 2040"),
+        "{}",
+        stdout
+    );
+
+    // Against itself as the reference engine, through `go perft`
+    let output = uci(&["perft-compare", path, "2", env!("CARGO_BIN_EXE_uci")], "");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    assert_eq!(
+        stdout
+            .lines()
+            .filter(|line| line.starts_with("ok "))
+            .count(),
+        2
+    );
+    std::fs::remove_file(path).unwrap();
+}
@@ -1,2 +0,0 @@
-pub mod engine;
-mod utils;